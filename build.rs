@@ -0,0 +1,94 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "src/evolutionmp/nativedb/codegen.rs"]
+mod codegen;
+
+//Names per front-coded block, the first name of a block being stored whole
+const BLOCK: usize = 16;
+
+fn main() {
+    let list = Path::new("src/evolutionmp/hash/objects.list");
+    println!("cargo:rerun-if-changed={}", list.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let out = std::env::var("OUT_DIR").expect("OUT_DIR not set");
+    let out = Path::new(&out);
+    let list = std::fs::read_to_string(list).expect("unable to read object list");
+    let (source, names) = object_table(&list);
+    std::fs::write(out.join("objects.rs"), source).expect("unable to write object table");
+    std::fs::write(out.join("object_names.bin"), names).expect("unable to write object names");
+
+    //A full natives.json can be used instead of the bundled database
+    println!("cargo:rerun-if-env-changed=NATIVES_DB");
+    let path = std::env::var_os("NATIVES_DB").map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("src/evolutionmp/nativedb/natives.json"));
+    println!("cargo:rerun-if-changed={}", path.display());
+    let db = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("unable to read {}: {}", path.display(), e));
+    let db = codegen::NativeDatabase::parse(&db).unwrap_or_else(|e| panic!("invalid native database: {}", e));
+    let generated = codegen::generate(&db);
+    for (native, ty) in &generated.skipped {
+        println!("cargo:warning=no wrapper generated for {}, `{}` has no Rust type", native, ty);
+    }
+    std::fs::write(out.join("natives.rs"), generated.wrappers).expect("unable to write native wrappers");
+    std::fs::write(out.join("native_registry.rs"), generated.registry).expect("unable to write native registry");
+}
+
+/// Builds the hash index and the front-coded name storage of `hash::objects`.
+fn object_table(list: &str) -> (String, Vec<u8>) {
+    let mut entries = list.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let (hash, name) = l.split_once(' ').unwrap_or_else(|| panic!("invalid object entry `{}`", l));
+            let hash = u32::from_str_radix(hash, 16).unwrap_or_else(|_| panic!("invalid object hash `{}`", l)) as i32;
+            assert!(name.len() <= u8::MAX as usize, "object name `{}` too long", name);
+            (hash, name)
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort_by(|a, b| a.1.cmp(b.1));
+    let mut names = Vec::new();
+    let mut blocks = Vec::new();
+    let mut previous = "";
+    for (index, (_, name)) in entries.iter().enumerate() {
+        let shared = if index % BLOCK == 0 {
+            blocks.push(names.len() as u32);
+            0
+        } else {
+            let mut shared = previous.bytes().zip(name.bytes()).take_while(|(a, b)| a == b).count();
+            while !name.is_char_boundary(shared) {
+                shared -= 1;
+            }
+            shared
+        };
+        names.push(shared as u8);
+        names.push((name.len() - shared) as u8);
+        names.extend_from_slice(&name.as_bytes()[shared..]);
+        previous = name;
+    }
+
+    let mut by_hash = entries.iter().enumerate().map(|(index, (hash, _))| (*hash, index)).collect::<Vec<_>>();
+    by_hash.sort();
+    if let Some(w) = by_hash.windows(2).find(|w| w[0].0 == w[1].0) {
+        panic!("object hash {:08X} listed twice", w[0].0);
+    }
+    let index_type = if entries.len() <= u16::MAX as usize + 1 { "u16" } else { "u32" };
+
+    let mut source = String::new();
+    writeln!(source, "const BLOCK: usize = {};", BLOCK).unwrap();
+    write_array(&mut source, "HASHES", "i32", by_hash.iter().map(|(hash, _)| *hash));
+    write_array(&mut source, "INDICES", index_type, by_hash.iter().map(|(_, index)| *index));
+    write_array(&mut source, "BLOCKS", "u32", blocks.iter().cloned());
+    writeln!(source, "static NAMES: &[u8] = include_bytes!(concat!(env!(\"OUT_DIR\"), \"/object_names.bin\"));").unwrap();
+    (source, names)
+}
+
+fn write_array<T, I>(source: &mut String, name: &str, ty: &str, values: I) where T: std::fmt::Display, I: ExactSizeIterator<Item=T> {
+    writeln!(source, "static {}: [{}; {}] = [", name, ty, values.len()).unwrap();
+    for value in values {
+        writeln!(source, "    {},", value).unwrap();
+    }
+    writeln!(source, "];").unwrap();
+}
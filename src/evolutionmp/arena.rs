@@ -0,0 +1,209 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::os::raw::c_char;
+
+thread_local! {
+    static FRAME_ARENA: RefCell<Arena> = RefCell::new(Arena::new());
+    static FRAME_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Owns the C strings and buffers marshalled for native calls until the calls return.
+/// Allocations keep their address for as long as they live, however many are added after them.
+#[derive(Debug, Default)]
+pub struct Arena {
+    buffers: Vec<Box<[u8]>>,
+}
+
+/// Position of an arena, everything allocated after it being freed by `Arena::release`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Mark(usize);
+
+impl Arena {
+    pub fn new() -> Arena {
+        Arena::default()
+    }
+
+    /// Number of live allocations.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    pub fn mark(&self) -> Mark {
+        Mark(self.buffers.len())
+    }
+
+    pub fn release(&mut self, mark: Mark) {
+        self.buffers.truncate(mark.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+
+    pub fn alloc_bytes(&mut self, bytes: &[u8]) -> *mut u8 {
+        let mut buffer = Box::<[u8]>::from(bytes);
+        let ptr = buffer.as_mut_ptr();
+        self.buffers.push(buffer);
+        ptr
+    }
+
+    /// Copies a string with a trailing NUL, panicking if it holds one already like `CString::new` does.
+    pub fn alloc_c_str(&mut self, value: &str) -> *const c_char {
+        let value = CString::new(value).expect("Failed to write C string");
+        self.alloc_bytes(value.as_bytes_with_nul()) as *const c_char
+    }
+}
+
+/// Keeps the allocations of the thread's frame arena alive, the outermost frame freeing them all on drop.
+/// `invoke!` opens one around each native call, callers open one around calls that must see the same
+/// strings, such as the text components of a text command.
+pub struct Frame {
+    //Frames count the depth of the thread that opened them, so they must be dropped on it
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        let depth = FRAME_DEPTH.with(|d| {
+            d.set(d.get() - 1);
+            d.get()
+        });
+        if depth == 0 {
+            FRAME_ARENA.with(|arena| arena.borrow_mut().clear());
+        }
+    }
+}
+
+pub fn frame() -> Frame {
+    FRAME_DEPTH.with(|d| d.set(d.get() + 1));
+    Frame { _not_send: PhantomData }
+}
+
+/// Live allocations of the thread's frame arena.
+pub fn frame_len() -> usize {
+    FRAME_ARENA.with(|arena| arena.borrow().len())
+}
+
+/// Copies a string to the frame arena, the pointer being valid until the outermost `Frame` is dropped,
+/// or the next one when there is none.
+pub fn alloc_c_str(value: &str) -> *const c_char {
+    FRAME_ARENA.with(|arena| arena.borrow_mut().alloc_c_str(value))
+}
+
+pub fn alloc_bytes(bytes: &[u8]) -> *mut u8 {
+    FRAME_ARENA.with(|arena| arena.borrow_mut().alloc_bytes(bytes))
+}
+
+/// Copies a string for natives that keep the pointer, such as label or texture registrations.
+/// It's never freed, so keep the result around instead of calling this every frame.
+pub fn retain_c_str(value: &str) -> &'static CStr {
+    Box::leak(CString::new(value).expect("Failed to write C string").into_boxed_c_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::backend::{self, FakeBackend};
+    use crate::stack::NativeCallContext;
+
+    const DRAW_TEXT: u64 = 1;
+    const ADD_COMPONENT: u64 = 2;
+    const END_COMMAND: u64 = 3;
+
+    //What `invoke!` does with a string argument
+    fn call_with_str(hash: u64, value: &str) {
+        let _frame = frame();
+        let mut args = [0; 32];
+        let mut returns = [0; 3];
+        let mut context = NativeCallContext::new(&mut args, &mut returns, 0);
+        context.push_arg(value);
+        backend::call(hash, &mut context);
+    }
+
+    fn read(ptr: u64) -> String {
+        unsafe { CStr::from_ptr(ptr as *const c_char) }.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn arena_marks() {
+        let mut arena = Arena::new();
+        let first = arena.alloc_c_str("first");
+        let mark = arena.mark();
+        for i in 0..100 {
+            arena.alloc_bytes(&[i; 16]);
+        }
+        //Earlier allocations keep their address however many follow
+        assert_eq!(read(first as u64), "first");
+        assert_eq!(arena.len(), 101);
+        arena.release(mark);
+        assert_eq!(arena.len(), 1);
+        assert_eq!(read(first as u64), "first");
+        arena.clear();
+        assert!(arena.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to write C string")]
+    fn interior_nul() {
+        Arena::new().alloc_c_str("a\0b");
+    }
+
+    #[test]
+    fn strings_live_for_the_call() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let fake = Arc::new(FakeBackend::new());
+        let s = seen.clone();
+        fake.on(DRAW_TEXT, move |context| {
+            s.lock().unwrap().push(read(context.args()[0]));
+            assert_eq!(frame_len(), 1);
+        });
+        backend::with(fake, || {
+            call_with_str(DRAW_TEXT, "hello");
+            call_with_str(DRAW_TEXT, "world");
+        });
+        assert_eq!(*seen.lock().unwrap(), vec!["hello", "world"]);
+        assert_eq!(frame_len(), 0);
+    }
+
+    #[test]
+    fn outer_frame_keeps_components() {
+        //A text command reading its components when it ends, like the game does
+        let components = Arc::new(Mutex::new(Vec::new()));
+        let drawn = Arc::new(Mutex::new(Vec::new()));
+        let fake = Arc::new(FakeBackend::new());
+        let c = components.clone();
+        fake.on(ADD_COMPONENT, move |context| c.lock().unwrap().push(context.args()[0]));
+        let (c, d) = (components.clone(), drawn.clone());
+        fake.on(END_COMMAND, move |_| {
+            d.lock().unwrap().extend(c.lock().unwrap().drain(..).map(read));
+        });
+        backend::with(fake, || {
+            let outer = frame();
+            call_with_str(ADD_COMPONENT, "one");
+            call_with_str(ADD_COMPONENT, "two");
+            assert_eq!(frame_len(), 2);
+            call_with_str(END_COMMAND, "");
+            drop(outer);
+        });
+        assert_eq!(*drawn.lock().unwrap(), vec!["one", "two"]);
+        assert_eq!(frame_len(), 0);
+    }
+
+    #[test]
+    fn retained_strings() {
+        let retained = retain_c_str("label");
+        {
+            let _frame = frame();
+            alloc_c_str("temporary");
+        }
+        assert_eq!(frame_len(), 0);
+        assert_eq!(retained.to_str(), Ok("label"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::stack::{NativeCallContext, NativeStackValue};
+
+/// Runs natives for `invoke!`, the game's native table in the client and a `FakeBackend` in tests.
+pub trait NativeBackend: Send + Sync {
+    fn contains(&self, hash: u64) -> bool;
+
+    /// Runs a native, returning false when the backend doesn't have it.
+    fn call(&self, hash: u64, context: &mut NativeCallContext) -> bool;
+}
+
+static DEFAULT: OnceLock<Box<dyn NativeBackend>> = OnceLock::new();
+
+thread_local! {
+    static OVERRIDE: RefCell<Option<Arc<dyn NativeBackend>>> = const { RefCell::new(None) };
+}
+
+/// Sets the backend of every thread without one of its own, once.
+pub fn set_default<B>(backend: B) -> bool where B: NativeBackend + 'static {
+    DEFAULT.set(Box::new(backend)).is_ok()
+}
+
+/// Runs `f` with natives of the current thread going to `backend`, restoring the previous one after.
+pub fn with<B, F, R>(backend: Arc<B>, f: F) -> R where B: NativeBackend + 'static, F: FnOnce() -> R {
+    struct Restore(Option<Arc<dyn NativeBackend>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            OVERRIDE.with(|o| *o.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(OVERRIDE.with(|o| o.borrow_mut().replace(backend)));
+    f()
+}
+
+pub fn contains(hash: u64) -> bool {
+    OVERRIDE.with(|o| match &*o.borrow() {
+        Some(backend) => backend.contains(hash),
+        None => DEFAULT.get().is_some_and(|backend| backend.contains(hash))
+    })
+}
+
+/// Whether natives of the current thread go to a backend of its own, set by `with`.
+pub fn is_overridden() -> bool {
+    OVERRIDE.with(|o| o.borrow().is_some())
+}
+
+/// Runs a native on the backend of the current thread, returning false when it is missing.
+pub fn try_call(hash: u64, context: &mut NativeCallContext) -> bool {
+    let found = OVERRIDE.with(|o| o.borrow().as_ref().map(|backend| backend.call(hash, context)));
+    match found {
+        Some(found) => found,
+        None => DEFAULT.get().is_some_and(|backend| backend.call(hash, context))
+    }
+}
+
+/// Runs a native on the backend of the current thread, panicking when it is missing.
+pub fn call(hash: u64, context: &mut NativeCallContext) {
+    if !try_call(hash, context) {
+        panic!("Missing native handler for {}", crate::nativedb::describe(hash));
+    }
+}
+
+pub type FakeNative = dyn Fn(&mut NativeCallContext) + Send + Sync;
+
+/// A native call seen by a `FakeBackend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeCall {
+    pub hash: u64,
+    pub args: Vec<u64>,
+    /// Results as left by the fake native
+    pub returns: [u64; 3],
+}
+
+/// Backend of scripted natives, recording every call made to it.
+/// Natives it doesn't know about are missing, unless `allow_unknown` makes them return zeroes.
+#[derive(Default)]
+pub struct FakeBackend {
+    natives: Mutex<HashMap<u64, Arc<FakeNative>>>,
+    calls: Mutex<Vec<FakeCall>>,
+    allow_unknown: bool,
+}
+
+impl FakeBackend {
+    pub fn new() -> FakeBackend {
+        FakeBackend::default()
+    }
+
+    pub fn allow_unknown(mut self, allow: bool) -> FakeBackend {
+        self.allow_unknown = allow;
+        self
+    }
+
+    /// Runs `native` for a hash, replacing what was registered for it.
+    pub fn on<F>(&self, hash: u64, native: F) -> &FakeBackend where F: Fn(&mut NativeCallContext) + Send + Sync + 'static {
+        self.natives.lock().expect("lock poisoned").insert(hash, Arc::new(native));
+        self
+    }
+
+    /// Makes a native return a canned value. Strings should be `&'static CStr`,
+    /// `&str` results being freed once the call returns.
+    pub fn returns<R>(&self, hash: u64, value: R) -> &FakeBackend where R: NativeStackValue + Clone + Send + Sync + 'static {
+        self.on(hash, move |context| {
+            context.set_result(value.clone());
+        })
+    }
+
+    pub fn calls(&self) -> Vec<FakeCall> {
+        self.calls.lock().expect("lock poisoned").clone()
+    }
+
+    pub fn calls_to(&self, hash: u64) -> Vec<FakeCall> {
+        self.calls.lock().expect("lock poisoned").iter().filter(|c| c.hash == hash).cloned().collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.calls.lock().expect("lock poisoned").clear();
+    }
+}
+
+impl NativeBackend for FakeBackend {
+    fn contains(&self, hash: u64) -> bool {
+        self.allow_unknown || self.natives.lock().expect("lock poisoned").contains_key(&hash)
+    }
+
+    fn call(&self, hash: u64, context: &mut NativeCallContext) -> bool {
+        //Not holding the lock while the native runs, as it may call others
+        let native = self.natives.lock().expect("lock poisoned").get(&hash).cloned();
+        if native.is_none() && !self.allow_unknown {
+            return false;
+        }
+        let args = context.args().to_vec();
+        if let Some(native) = native {
+            native(context);
+        }
+        self.calls.lock().expect("lock poisoned").push(FakeCall { hash, args, returns: *context.returns() });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    use super::*;
+
+    fn invoke(hash: u64, arg: u32) -> Option<u32> {
+        let mut args = [0; 32];
+        let mut returns = [0; 3];
+        let mut context = NativeCallContext::new(&mut args, &mut returns, 0);
+        context.push_arg(arg);
+        if try_call(hash, &mut context) {
+            Some(context.get_result())
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn scripted_natives() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.on(0x10, |context| {
+            let arg = context.get_args().read::<u32>();
+            context.set_result(arg * 2);
+        });
+        backend.returns(0x20, 5u32);
+        assert!(!is_overridden());
+        with(backend.clone(), || {
+            assert!(is_overridden());
+            assert!(contains(0x10) && !contains(0x30));
+            assert_eq!(invoke(0x10, 21), Some(42));
+            assert_eq!(invoke(0x20, 1), Some(5));
+            assert_eq!(invoke(0x30, 1), None);
+        });
+        assert!(!is_overridden());
+        assert_eq!(backend.calls(), vec![
+            FakeCall { hash: 0x10, args: vec![21], returns: [42, 0, 0] },
+            FakeCall { hash: 0x20, args: vec![1], returns: [5, 0, 0] },
+        ]);
+        assert_eq!(backend.calls_to(0x20).len(), 1);
+        backend.clear_calls();
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn unknown_natives() {
+        let strict = Arc::new(FakeBackend::new());
+        let result = catch_unwind(AssertUnwindSafe(|| with(strict.clone(), || {
+            let mut args = [0; 32];
+            let mut returns = [0; 3];
+            call(0x1234, &mut NativeCallContext::new(&mut args, &mut returns, 0));
+        })));
+        assert!(result.is_err());
+        //Restored even though the call panicked
+        assert!(!is_overridden());
+        assert!(strict.calls().is_empty());
+
+        let lenient = Arc::new(FakeBackend::new().allow_unknown(true));
+        assert_eq!(with(lenient.clone(), || invoke(0x1234, 3)), Some(0));
+        assert_eq!(lenient.calls_to(0x1234)[0].args, vec![3]);
+    }
+
+    #[test]
+    fn nested_and_per_thread() {
+        let outer = Arc::new(FakeBackend::new());
+        outer.returns(0x1, 1u32);
+        let inner = Arc::new(FakeBackend::new());
+        inner.returns(0x1, 2u32);
+        with(outer.clone(), || {
+            assert_eq!(with(inner.clone(), || invoke(0x1, 0)), Some(2));
+            assert_eq!(invoke(0x1, 0), Some(1));
+            assert!(!std::thread::spawn(is_overridden).join().unwrap());
+        });
+        assert_eq!((outer.calls().len(), inner.calls().len()), (1, 1));
+    }
+
+    #[test]
+    fn natives_calling_natives() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0x2, 7u32);
+        backend.on(0x1, |context| {
+            let inner = invoke(0x2, 0).unwrap();
+            context.set_result(inner + 1);
+        });
+        assert_eq!(with(backend.clone(), || invoke(0x1, 0)), Some(8));
+        let hashes: Vec<u64> = backend.calls().iter().map(|c| c.hash).collect();
+        assert_eq!(hashes, vec![0x2, 0x1]);
+    }
+}
@@ -1,6 +1,133 @@
+use std::io::BufRead;
+use std::sync::atomic::Ordering;
+
 use winapi::um::consoleapi::AllocConsole;
 
+use crate::{detours, nativedb, nativetable, patches, profile, trace};
+use crate::hash::{joaat, Hash};
+use crate::trace::TraceFilter;
+
 pub(crate) fn attach() {
     unsafe { AllocConsole() };
     ansi_term::enable_ansi_support().expect("enabling console ansi support failed");
-}
\ No newline at end of file
+    std::thread::spawn(|| {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => execute(&line),
+                Err(_) => break
+            }
+        }
+    });
+}
+
+pub fn execute(command: &str) {
+    let args = command.split_whitespace().collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => {}
+        ["patches"] => {
+            for patch in patches::list() {
+                info!("{}", patch);
+            }
+        }
+        ["patch", name, state @ ("on" | "off")] => {
+            match patches::set_enabled(name, *state == "on") {
+                Ok(()) => info!("Patch {} turned {}", name, state),
+                Err(e) => error!("{}", e)
+            }
+        }
+        ["patterns", "strict", state @ ("on" | "off")] => {
+            crate::native::STRICT_PATTERNS.store(*state == "on", Ordering::SeqCst);
+            info!("Strict pattern resolution turned {}, for bindings resolved from now on", state);
+        }
+        ["hashes", "reload"] => crate::native::reload_hashes(),
+        ["hooks"] => {
+            for hook in detours::list() {
+                info!("{}", hook);
+            }
+        }
+        ["hook", name, state @ ("on" | "off")] => {
+            match unsafe { detours::set_enabled(name, *state == "on") } {
+                Ok(()) => info!("Hook {} turned {}", name, state),
+                Err(e) => error!("{}", e)
+            }
+        }
+        ["trace", state @ ("on" | "off")] => {
+            trace::set_enabled(*state == "on");
+            info!("Native tracing turned {}, tracing {}", state, trace::filter());
+        }
+        ["trace", "clear"] => trace::clear(),
+        ["trace", "dump"] => {
+            let path = crate::launcher_dir().join(trace::TRACE_FILE);
+            match trace::dump_to_file(&path) {
+                Ok(count) => info!("Dumped {} native calls to {}", count, path.display()),
+                Err(e) => error!("Unable to dump native calls to {}: {}", path.display(), e)
+            }
+        }
+        ["trace", "filter"] => info!("Tracing {}", trace::filter()),
+        ["trace", "filter", "clear"] => trace::set_filter(TraceFilter::default()),
+        ["trace", "filter", kind @ ("native" | "script" | "namespace"), value] => {
+            let mut filter = trace::filter();
+            match *kind {
+                "native" => match parse_native(value) {
+                    Some(hash) => { filter.hashes.insert(hash); }
+                    None => {
+                        warn!("Unknown native `{}`", value);
+                        return;
+                    }
+                },
+                "script" => { filter.scripts.insert(parse_script(value).0); }
+                _ => { filter.namespaces.insert(value.to_uppercase()); }
+            }
+            info!("Tracing {}", filter);
+            trace::set_filter(filter);
+        }
+        ["profile", state @ ("on" | "off")] => {
+            profile::set_enabled(*state == "on");
+            info!("Native profiling turned {}", state);
+        }
+        ["profile", "reset"] => profile::reset(),
+        ["profile", "overlay", state @ ("on" | "off")] => crate::scripts::profiler::show_overlay(*state == "on"),
+        ["profile", "show"] => show_profile(None, 20),
+        ["profile", "show", script] => show_profile(Some(parse_script(script).0), 20),
+        ["natives", "dump"] => {
+            let path = crate::launcher_dir().join(nativetable::DUMP_FILE);
+            match crate::native::dump_native_table(&path) {
+                Ok(count) => info!("Dumped {} native registrations to {}", count, path.display()),
+                Err(e) => error!("Unable to dump native registrations to {}: {}", path.display(), e)
+            }
+        }
+        _ => warn!("Unknown command `{}`", command.trim())
+    }
+}
+
+fn show_profile(script: Option<u32>, lines: usize) {
+    let stats = profile::snapshot();
+    let natives = match script {
+        Some(script) => stats.by_script(script),
+        None => stats.by_native()
+    };
+    info!("{} native calls, {:.2?} in total", stats.calls(), stats.total());
+    for native in natives.iter().take(lines) {
+        info!("{}", native.summary());
+    }
+}
+
+//Native hash or name, `NAMESPACE::NAME` or just `NAME`
+fn parse_native(value: &str) -> Option<u64> {
+    if let Some(hex) = value.strip_prefix("0x") {
+        return u64::from_str_radix(hex, 16).ok();
+    }
+    let (namespace, name) = match value.split_once("::") {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, value)
+    };
+    nativedb::find(namespace, name).map(|n| n.hash)
+}
+
+fn parse_script(value: &str) -> Hash {
+    match value.strip_prefix("0x").and_then(|hex| u32::from_str_radix(hex, 16).ok()) {
+        Some(hash) => Hash(hash),
+        None => joaat(value)
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+
+use detour::RawDetour;
+
+use crate::native::ThreadSafe;
+
+lazy_static! {
+    static ref HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+}
+
+struct Hook {
+    name: String,
+    target: u64,
+    detour: ThreadSafe<RawDetour>,
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    Duplicate(String),
+    Unknown(String),
+    Detour(String, detour::Error),
+}
+
+impl Display for HookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookError::Duplicate(name) => f.write_fmt(format_args!("hook `{}` is already installed", name)),
+            HookError::Unknown(name) => f.write_fmt(format_args!("unknown hook `{}`", name)),
+            HookError::Detour(name, e) => f.write_fmt(format_args!("hook `{}` failed: {}", name, e))
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Snapshot of an installed hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookInfo {
+    pub name: String,
+    pub target: u64,
+    pub trampoline: u64,
+    pub enabled: bool,
+}
+
+impl Display for HookInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.enabled { "enabled" } else { "disabled" };
+        f.write_fmt(format_args!("{} at 0x{:X}, trampoline 0x{:X} ({})", self.name, self.target, self.trampoline, state))
+    }
+}
+
+/// Detours `target` to `replacement` and keeps the hook until the client detaches,
+/// returning the trampoline calling the original function.
+pub unsafe fn install<N>(name: N, target: *const (), replacement: *const ()) -> Result<*const (), HookError> where N: Into<String> {
+    let name = name.into();
+    let mut hooks = HOOKS.lock().expect("mutex poisoned");
+    if hooks.iter().any(|h| h.name == name) {
+        return Err(HookError::Duplicate(name));
+    }
+    let detour = match RawDetour::new(target, replacement).and_then(|d| d.enable().map(|_| d)) {
+        Ok(detour) => detour,
+        Err(e) => return Err(HookError::Detour(name, e))
+    };
+    let trampoline = detour.trampoline() as *const ();
+    hooks.push(Hook { name, target: target as u64, detour: ThreadSafe::new(detour) });
+    Ok(trampoline)
+}
+
+pub unsafe fn set_enabled(name: &str, enabled: bool) -> Result<(), HookError> {
+    let hooks = HOOKS.lock().expect("mutex poisoned");
+    let hook = hooks.iter().find(|h| h.name == name)
+        .ok_or_else(|| HookError::Unknown(String::from(name)))?;
+    let result = if enabled { hook.detour.enable() } else { hook.detour.disable() };
+    result.map_err(|e| HookError::Detour(String::from(name), e))
+}
+
+pub fn list() -> Vec<HookInfo> {
+    HOOKS.lock().expect("mutex poisoned").iter()
+        .map(|h| HookInfo {
+            name: h.name.clone(),
+            target: h.target,
+            trampoline: h.detour.trampoline() as *const () as u64,
+            enabled: h.detour.is_enabled(),
+        })
+        .collect()
+}
+
+//Hooks are only disabled, not dropped: other threads may still be running inside a trampoline
+pub(crate) fn disable_all() {
+    let hooks = HOOKS.lock().expect("mutex poisoned");
+    for hook in hooks.iter().rev() {
+        if let Err(e) = unsafe { hook.detour.disable() } {
+            error!("Unable to disable hook {}: {}", hook.name, e);
+        }
+    }
+}
@@ -76,7 +76,7 @@ impl EventPool {
     }
 }
 
-bind_fn_detour!(CALL_EVENT, "81 BF ? ? 00 00 ? ? 00 00 75 ? 48 8B CF E8", -0x36, call_event, (&(), Option<&Event>) -> *mut ());
+bind_fn_detour!(CALL_EVENT, call_event, (&(), Option<&Event>) -> *mut ());
 
 pub unsafe extern fn call_event(group: &(), event: Option<&Event>) -> *mut () {
     if let Some(event) = event {
@@ -93,7 +93,7 @@ pub unsafe extern fn call_event(group: &(), event: Option<&Event>) -> *mut () {
     CALL_EVENT(group, event)
 }
 
-bind_fn_detour!(GET_EVENT_DATA, "48 85 C0 74 14 4C 8B 10", -28, get_event_data, (i32, i32, *mut i32, u32) -> bool);
+bind_fn_detour!(GET_EVENT_DATA, get_event_data, (i32, i32, *mut i32, u32) -> bool);
 
 pub unsafe extern fn get_event_data(group: i32, event: i32, args: *mut i32, arg_count: u32) -> bool {
     warn!("Getting event data for group {} id {} argc {}", group, event, arg_count);
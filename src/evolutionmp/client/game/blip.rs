@@ -63,6 +63,7 @@ impl Blip {
     }
 
     pub fn set_name(&self, name: BlipName) {
+        let _frame = crate::arena::frame();
         match name {
             BlipName::Localized(format, args) => {
                 invoke!((), 0xF9113A30DE5C6670, format);
@@ -279,4 +280,83 @@ impl Iterator for BlipIterator {
             invoke!(Option<Blip>, 0x14F96AA50D6FBEA7, self.handle)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::backend::{self, FakeBackend};
+    use crate::game::vehicle::Vehicle;
+    use crate::native::pool::Handleable;
+
+    use super::*;
+
+    #[test]
+    fn entity_blips() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0x5CDE92C702A8FCE7, 0x51u32);
+        backend::with(backend.clone(), || {
+            let vehicle = Vehicle::from_handle(0x2A04).unwrap();
+            backend.returns(0xBC8DBDCA2436F7E8, 0u32);
+            assert!(Blip::from_entity(&vehicle).is_none());
+            backend.returns(0xBC8DBDCA2436F7E8, 0x51u32);
+            assert_eq!(Blip::from_entity(&vehicle).map(|b| b.get_handle()), Some(0x51));
+            assert_eq!(Blip::new_for_entity(&vehicle).get_handle(), 0x51);
+        });
+        assert_eq!(backend.calls_to(0xBC8DBDCA2436F7E8)[0].args, vec![0x2A04]);
+        assert_eq!(backend.calls_to(0x5CDE92C702A8FCE7)[0].args, vec![0x2A04]);
+    }
+
+    #[test]
+    #[should_panic(expected = "got zero handle")]
+    fn missing_player_blip() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0xDCD4EC3F419D02FA, 0u32);
+        backend::with(backend, Blip::player);
+    }
+
+    #[test]
+    fn names_and_rotation() {
+        let backend = Arc::new(FakeBackend::new());
+        let pushed = Arc::new(Mutex::new(Vec::new()));
+        for hash in [0xF9113A30DE5C6670, 0x6C188BE134E074AA] {
+            let pushed = pushed.clone();
+            backend.on(hash, move |context| pushed.lock().unwrap().push(context.get_args().read::<String>()));
+        }
+        backend.on(0xBC38B49BCB83BC9B, |_| {});
+        backend.returns(0x11E019C8F43ACC8A, 46u32);
+        backend.on(0xF87683CDF73C3F6E, |_| {});
+        backend::with(backend.clone(), || {
+            let blip = Blip::from_handle(0x51).unwrap();
+            blip.set_name(BlipName::Generic(&"x".repeat(120)));
+            blip.set_name(BlipName::Localized("BLIP_PROPCAT", &["one", "two"]));
+            blip.set_rotation(45.5);
+        });
+        //Long names are pushed in components of 99 bytes
+        assert_eq!(*pushed.lock().unwrap(), vec![
+            String::from("STRING"), "x".repeat(99), "x".repeat(21),
+            String::from("BLIP_PROPCAT"), String::from("one"), String::from("two")
+        ]);
+        assert_eq!(backend.calls_to(0xBC38B49BCB83BC9B).len(), 2);
+        assert_eq!(backend.calls_to(0xBC38B49BCB83BC9B)[1].args, vec![0x51]);
+        assert_eq!(backend.calls_to(0xF87683CDF73C3F6E)[0].args, vec![0x51, 46]);
+    }
+
+    #[test]
+    fn iteration() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0x186E5D252FA50E7D, 5u32);
+        backend.returns(0x1BEDE233E6CD2A1F, 0x51u32);
+        let remaining = Arc::new(Mutex::new(vec![0, 0x53, 0x52]));
+        let next = remaining.clone();
+        backend.on(0x14F96AA50D6FBEA7, move |context| {
+            let blip = next.lock().unwrap().pop().unwrap();
+            context.set_result(blip);
+        });
+        let blips = backend::with(backend.clone(), || get_pool().map(|b| b.get_handle()).collect::<Vec<_>>());
+        assert_eq!(blips, vec![0x51, 0x52, 0x53]);
+        assert!(remaining.lock().unwrap().is_empty());
+        assert!(backend.calls_to(0x14F96AA50D6FBEA7).iter().all(|c| c.args == vec![5]));
+    }
+}
@@ -154,4 +154,64 @@ impl_data_value!(f32, Float, 0x57A995FD75D37F56, 0xC0C527B525D7CFB5, 0xC27E1CC2D
 impl_data_value!(i32, Integer, 0xCABDB751D86FE93B, 0x3E5AE19425CD74BE, 0xE7E035450A7948D5, 0x78F06F6B1FB5A80C);
 impl_data_value!(u32, Integer, 0xCABDB751D86FE93B, 0x3E5AE19425CD74BE, 0xE7E035450A7948D5, 0x78F06F6B1FB5A80C);
 impl_data_value!(&str, String, 0x2F0661C155AEEEAA, 0xD3F2FFEB8D836F52, 0x8FF3847DADD8E30C, 0x3D2FD9E763B24472);
-impl_data_value!(Vector3<f32>, Vector3, 0x407F8D034F70F0C2, 0x8D2064E5B64A628A, 0x4CD49B76338C7DEE, 0x46CD3CB66E0825CC);
\ No newline at end of file
+impl_data_value!(Vector3<f32>, Vector3, 0x407F8D034F70F0C2, 0x8D2064E5B64A628A, 0x4CD49B76338C7DEE, 0x46CD3CB66E0825CC);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use crate::backend::{self, FakeBackend};
+
+    use super::*;
+
+    #[test]
+    fn object_values() {
+        let backend = Arc::new(FakeBackend::new());
+        let values = Arc::new(Mutex::new(HashMap::new()));
+        let set = values.clone();
+        backend.on(0xE7E035450A7948D5, move |context| {
+            let mut args = context.get_args();
+            let object = args.read::<u64>();
+            let key = args.read::<String>();
+            set.lock().unwrap().insert((object, key), args.read::<i32>());
+        });
+        let get = values.clone();
+        backend.on(0x78F06F6B1FB5A80C, move |context| {
+            let mut args = context.get_args();
+            let key = (args.read::<u64>(), args.read::<String>());
+            let value = get.lock().unwrap().get(&key).copied().unwrap_or(0);
+            context.set_result(value);
+        });
+        backend::with(backend.clone(), || {
+            let mut object = Object { handle: 0x40 };
+            object.set("score", -12);
+            object.set(String::from("kills"), 3);
+            assert_eq!(object.get::<_, i32>("score"), -12);
+            assert_eq!(object.get::<_, i32>("kills"), 3);
+            assert_eq!(Object { handle: 0x80 }.get::<_, i32>("score"), 0);
+        });
+        assert_eq!(values.lock().unwrap().len(), 2);
+        assert_eq!(backend.calls_to(0xE7E035450A7948D5)[0].args[2], -12i32 as u32 as u64);
+    }
+
+    #[test]
+    fn array_bounds() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0x065DB281590CEA2D, 2u32);
+        backend.on(0x50C1B2874E50C114, |context| {
+            let mut args = context.get_args();
+            let _array = args.read::<u64>();
+            let index = args.read::<u32>();
+            context.set_result(index == 1);
+        });
+        backend::with(backend.clone(), || {
+            let array = Array::<bool> { handle: 0x10, _ty: PhantomData };
+            assert_eq!(array.get(0), Some(false));
+            assert_eq!(array.get(1), Some(true));
+            assert_eq!(array.get(2), None);
+        });
+        assert_eq!(backend.calls_to(0x50C1B2874E50C114).len(), 2);
+        assert_eq!(backend.calls_to(0x50C1B2874E50C114)[1].args, vec![0x10, 1]);
+    }
+}
@@ -222,4 +222,44 @@ impl<'a, E> Bone<'a, E> where E: Entity {
     pub fn get_rotation(&self) -> Quaternion<f32> {
         invoke!(Quaternion<f32>, 0xCE6294A232D03786, self.entity.get_handle(), self.index)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::backend::{self, FakeBackend};
+
+    use super::*;
+
+    #[test]
+    fn position_of_dead_entities() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.returns(0x5F9532F3B5CC2551, true);
+        backend.returns(0x3FEF770D40960D5A, Vector3::new(1.0f32, 2.0, -3.0));
+        backend::with(backend.clone(), || {
+            let vehicle = Vehicle::from_handle(0x1204).unwrap();
+            assert_eq!(vehicle.get_position(), Vector3::new(1.0, 2.0, -3.0));
+        });
+        assert_eq!(backend.calls_to(0x5F9532F3B5CC2551)[0].args, vec![0x1204]);
+        //Dead entities are asked for their position without the alive flag
+        assert_eq!(backend.calls_to(0x3FEF770D40960D5A)[0].args, vec![0x1204, 0]);
+    }
+
+    #[test]
+    fn entity_types() {
+        let backend = Arc::new(FakeBackend::new());
+        backend::with(backend.clone(), || {
+            let vehicle = Vehicle::from_handle(0x1204).unwrap();
+            backend.returns(0x8ACD366038D14505, 2u32);
+            assert_eq!(vehicle.as_vehicle(), Some(vehicle));
+            assert!(vehicle.as_ped().is_none());
+            backend.returns(0x8ACD366038D14505, 1u32);
+            assert_eq!(vehicle.as_ped().map(|p| p.get_handle()), Some(0x1204));
+            backend.returns(0x8ACD366038D14505, 3u32);
+            assert!(vehicle.as_prop().is_some());
+            assert!(!vehicle.is_vehicle());
+        });
+        assert!(backend.calls().iter().all(|c| c.args == vec![0x1204]));
+    }
+}
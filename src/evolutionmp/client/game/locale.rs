@@ -1,12 +1,12 @@
 use std::collections::HashMap;
-use std::ffi::CString;
+use std::ffi::CStr;
 use std::sync::Mutex;
 
-use crate::{bind_fn_detour_ip, invoke};
-use crate::hash::{Hash, Hashable, joaat};
+use crate::{bind_fn_detour, invoke, joaat};
+use crate::hash::{Hash, LabelHash};
 
-bind_fn_detour_ip!(GET_TEXT, "48 8B CB 8B D0 E8 ? ? ? ? 48 85 C0 0F 95 C0", 5, TranslationTable::get_text, (&TranslationTable, Hash) -> *const u8);
-bind_fn_detour_ip!(GET_TEXT2, "48 85 C0 75 34 8B 0D", -5, TranslationTable::get_text, (&TranslationTable, Hash) -> *const u8);
+bind_fn_detour!(GET_TEXT, TranslationTable::get_text, (&TranslationTable, Hash) -> *const u8);
+bind_fn_detour!(GET_TEXT2, TranslationTable::get_text, (&TranslationTable, Hash) -> *const u8);
 
 pub enum TranslationTable {}
 
@@ -14,7 +14,7 @@ impl TranslationTable {
     extern fn get_text(&self, hash: Hash) -> *const u8 {
         let table = TRANSLATION_TABLE.lock().expect("mutex poisoned");
         if let Some(translation) = table.get(&hash) {
-            return translation.as_bytes_with_nul().as_ptr();
+            return translation.as_ptr() as *const u8;
         }
         //info!("getting text for hash 0x{:08X}", hash.0);
         let result = GET_TEXT(self, hash);
@@ -30,27 +30,30 @@ pub fn hook() {
 }
 
 pub fn init() {
-    set_translation("PM_PAUSE_HDR", "Evolution MP");
+    set_translation(joaat!(LabelHash: "PM_PAUSE_HDR"), "Evolution MP");
     let title = "Загрузка сетевой игры";
-    set_translation("LOADING_SPLAYER_L", title);
-    set_translation("LOADING_MPLAYER_L", title);
+    set_translation(joaat!(LabelHash: "LOADING_SPLAYER_L"), title);
+    set_translation(joaat!(LabelHash: "LOADING_MPLAYER_L"), title);
 }
 
 lazy_static! {
-    static ref TRANSLATION_TABLE: Mutex<HashMap<Hash, CString>> = Mutex::new(HashMap::new());
+    //The game keeps the text it got, so replaced translations are never freed
+    static ref TRANSLATION_TABLE: Mutex<HashMap<Hash, &'static CStr>> = Mutex::new(HashMap::new());
 }
 
 pub fn get_translation<'a>(label: &str) -> &'a str {
     invoke!(&str, 0x7B5280EBA9840C72, label)
 }
 
-pub fn set_translation<H>(label: H, translation: &str) where H: Hashable {
+pub fn set_translation<L>(label: L, translation: &str) where L: Into<LabelHash> {
     let mut table = TRANSLATION_TABLE.lock().expect("mutex poisoned");
-    let hash = label.joaat();
-    if hash == joaat("LOADING_SPLAYER_L") {
+    let label: LabelHash = label.into();
+    let hash = Hash::from(label);
+    if hash == joaat!("LOADING_SPLAYER_L") {
 
     }
-    if let Ok(translation) = CString::new(translation) {
-        table.insert(hash, translation);
+    let unchanged = table.get(&hash).is_some_and(|t| t.to_bytes() == translation.as_bytes());
+    if !unchanged && !translation.contains('\0') {
+        table.insert(hash, crate::arena::retain_c_str(translation));
     }
 }
\ No newline at end of file
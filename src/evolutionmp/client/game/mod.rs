@@ -2,10 +2,9 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use jni_dynamic::{InitArgsBuilder, JavaVM, JNIVersion};
-use serde_derive::{Deserialize, Serialize};
 
 use crate::client::add_dll_directory;
-use crate::{bind_fn_detour_ip, bind_field_ip, launcher_dir};
+use crate::{bind_fn_detour, launcher_dir};
 
 pub mod audio;
 pub mod entity;
@@ -42,38 +41,8 @@ pub mod data;
 pub mod fire;
 pub mod weapon;
 
-pub type Handle = u32;
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct Rgba {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-    pub a: u8,
-}
-
-impl Rgba {
-    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 255);
-    pub const DARK_GRAY: Rgba = Rgba::new(81, 81, 81, 255);
-    pub const BLACK: Rgba = Rgba::new(0, 0, 0, 255);
-
-    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Rgba {
-        Rgba { r, g, b, a }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct Rgb {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-}
-
-impl Rgb {
-    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
-        Rgb { r, g, b }
-    }
-}
+pub use crate::color::{Rgb, Rgba};
+pub use crate::handle::Handle;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
@@ -129,9 +98,9 @@ extern fn skip_init(state: u32) -> bool {
 
 //bind_field_ip!(INIT_STATE, "BA 08 00 00 00 8D 41 FC 83 F8 01", 16, u32);
 // bind_field_ip!(MINIMAP_CLIP_SHAPE, "8A 15 ? ? ? ? F3 0F 10 15 ? ? ? ? 84 D2 74 08", 2, bool);
-bind_fn_detour_ip!(MAIN_FRAME, "32 DB EB 02 B3 01 E8 ? ? ? ? 48 8B", 6, main_frame, () -> ());
-bind_fn_detour_ip!(SKIP_INIT, "32 DB EB 02 B3 01 E8 ? ? ? ? 48 8B", -9, skip_init, (u32) -> bool);
-bind_fn_detour_ip!(LOAD_GAME_NOW, "33 C9 E8 ? ? ? ? 8B 0D ? ? ? ? 48 8B 5C 24 ? 8D 41 FC 83 F8 01 0F 47 CF 89 0D ? ? ? ?", 2, load_game_now, (u8) -> u32);
+bind_fn_detour!(MAIN_FRAME, main_frame, () -> ());
+bind_fn_detour!(SKIP_INIT, skip_init, (u32) -> bool);
+bind_fn_detour!(LOAD_GAME_NOW, load_game_now, (u8) -> u32);
 
 pub fn hook() {
     locale::hook();
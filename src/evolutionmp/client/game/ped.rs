@@ -4,7 +4,7 @@ use crate::game::player::Player;
 use crate::game::vehicle::Vehicle;
 use crate::{invoke, invoke_option};
 use crate::native::pool::{Handleable, Pool, GenericPool};
-use crate::hash::Hashable;
+use crate::hash::{Hash, Hashable, ModelHash, WeaponHash};
 use crate::game::streaming::{AnimDict, PedPhoto};
 use crate::native::{NativeStackValue, NativeVector3};
 use cgmath::{Vector3, MetricSpace, Zero};
@@ -40,7 +40,8 @@ pub fn set_cops(enabled: bool) {
 }
 
 impl Ped {
-    pub fn new<H>(ty: u32, model: H, pos: Vector3<f32>, heading: f32, network: bool, net_mission: bool) -> Option<Ped> where H: Hashable {
+    pub fn new<M>(ty: u32, model: M, pos: Vector3<f32>, heading: f32, network: bool, net_mission: bool) -> Option<Ped> where M: Into<ModelHash> {
+        let model: ModelHash = model.into();
         invoke!(Option<Ped>, 0xD49F9B0955C367DE, ty, model.joaat(), pos, heading, network, net_mission)
     }
 
@@ -105,8 +106,9 @@ impl Ped {
         invoke_option!(result.into(), 0x6C4D0409BA1A2BC2, self.handle, &mut result)
     }
 
-    pub fn give_weapon<W>(&self, weapon: W, ammo: u32, hidden: bool, equip: bool) where W: Hashable {
-        invoke!((), 0xBF0FD6E56C964FCB, self.handle, weapon.joaat(), ammo, hidden, equip)
+    pub fn give_weapon<W>(&self, weapon: W, ammo: u32, hidden: bool, equip: bool) where W: Into<WeaponHash> {
+        let weapon: WeaponHash = weapon.into();
+        invoke!((), 0xBF0FD6E56C964FCB, self.handle, Hash::from(weapon), ammo, hidden, equip)
     }
 
     pub fn get_closest_vehicle<F>(&self, max_distance: f32, filter: F) -> Option<Vehicle>
@@ -2,7 +2,7 @@ use super::Handle;
 use crate::invoke;
 use crate::native::pool;
 use crate::game::ped::Ped;
-use crate::hash::Hashable;
+use crate::hash::{Hashable, ModelHash};
 use crate::native::pool::Handleable;
 use crate::game::streaming::{Model, Resource};
 
@@ -72,7 +72,8 @@ impl Player {
         invoke!((), 0xC142BE3BB9CE125F, self.handle)
     }
 
-    pub fn set_model<H>(&self, model: H) -> bool where H: Hashable {
+    pub fn set_model<M>(&self, model: M) -> bool where M: Into<ModelHash> {
+        let model: ModelHash = model.into();
         let model = Model::from(model);
         if model.is_in_cd_image() && model.is_valid() {
             model.request_and_wait();
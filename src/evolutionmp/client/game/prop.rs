@@ -4,7 +4,7 @@ use crate::invoke;
 use crate::game::{Handle, Rgb};
 use crate::game::entity::Entity;
 use crate::game::streaming::{Model, Resource};
-use crate::hash::Hashable;
+use crate::hash::{Hashable, ModelHash};
 use crate::native::pool::GenericPool;
 use crate::client::native::pool::CProp;
 
@@ -18,7 +18,8 @@ pub struct Prop {
 }
 
 impl Prop {
-    pub fn new<H>(model: H, pos: Vector3<f32>, is_network: bool, this_script_check: bool, dynamic: bool) -> Option<Prop> where H: Hashable {
+    pub fn new<M>(model: M, pos: Vector3<f32>, is_network: bool, this_script_check: bool, dynamic: bool) -> Option<Prop> where M: Into<ModelHash> {
+        let model: ModelHash = model.into();
         let model = Model::from(model);
         if model.is_in_cd_image() && model.is_valid() {
             model.request_and_wait();
@@ -103,4 +103,54 @@ impl ScaleformResult for bool {
 
 fn end_method_returnable() -> Handle {
     invoke!(Handle, 0xC50AA39A577AF886)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::backend::{self, FakeBackend};
+
+    use super::*;
+
+    #[test]
+    fn load_and_release() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.on(0x1D132D614DD86811, |context| {
+            *context.get_args().read::<&mut u32>() = 0;
+        });
+        backend::with(backend.clone(), || {
+            backend.returns(0x11FE353CF9733E6F, 0u32);
+            assert!(Scaleform::new("MISSING").is_none());
+            backend.returns(0x11FE353CF9733E6F, u32::MAX);
+            assert!(Scaleform::new("MISSING").is_none());
+            backend.returns(0x11FE353CF9733E6F, 3u32);
+            let scaleform = Scaleform::new("MP_BIG_MESSAGE_FREEMODE").unwrap();
+            assert!(scaleform.is_valid());
+        });
+        assert_eq!(backend.calls_to(0x11FE353CF9733E6F).len(), 3);
+        assert_eq!(backend.calls_to(0x1D132D614DD86811).len(), 1);
+    }
+
+    #[test]
+    fn render_and_methods() {
+        let backend = Arc::new(FakeBackend::new().allow_unknown(true));
+        backend.returns(0xC50AA39A577AF886, 9u32);
+        backend.returns(0x2DE7EFA66B906036, 42i32);
+        backend::with(backend.clone(), || {
+            let scaleform = Scaleform { handle: 3 };
+            scaleform.render(Vector2::new(0.5, 0.5), Vector2::new(1.0, 1.0), Rgba::new(255, 128, 0, 200));
+            let args = [ScaleformArg::I32(-1), ScaleformArg::Bool(true), ScaleformArg::Str(String::from("text"))];
+            assert_eq!(scaleform.invoke::<i32>("SET_DATA", &args), 42);
+        });
+        let half = 0.5f32.to_bits() as u64;
+        let one = 1.0f32.to_bits() as u64;
+        //Colors are passed as one word per component
+        assert_eq!(backend.calls_to(0x54972ADAF0294A93)[0].args, vec![3, half, half, one, one, 255, 128, 0, 200, 0]);
+        let hashes: Vec<u64> = backend.calls().iter().map(|c| c.hash).collect();
+        assert_eq!(hashes, vec![
+            0x54972ADAF0294A93, 0xF6E48914C7A8694E, 0xC3D0841A0CC546A6, 0xC58424BA936EB458,
+            0xBA7148484BD90365, 0xC50AA39A577AF886, 0x2DE7EFA66B906036, 0x1D132D614DD86811,
+        ]);
+        assert_eq!(backend.calls_to(0x2DE7EFA66B906036)[0].args, vec![9]);
+    }
+}
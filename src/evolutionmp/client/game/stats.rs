@@ -1,26 +1,33 @@
 use crate::{invoke, invoke_option};
-use crate::hash::{Hashable, Hash};
+use crate::hash::{Hash, StatHash};
 use std::marker::PhantomData;
 
 pub struct Stat<V> where V: StatValue {
-    hash: Hash,
+    hash: StatHash,
     _ty: PhantomData<V>
 }
 
 impl<V> Stat<V> where V: StatValue {
-    pub fn new<H>(hash: H) -> Stat<V> where H: Hashable {
+    pub fn new<H>(hash: H) -> Stat<V> where H: Into<StatHash> {
         Stat {
-            hash: hash.joaat(),
+            hash: hash.into(),
+            _ty: PhantomData
+        }
+    }
+
+    pub const fn from_hash(hash: StatHash) -> Stat<V> {
+        Stat {
+            hash,
             _ty: PhantomData
         }
     }
 
     pub fn get(&self, default: V) -> Option<V> {
-        V::read(self.hash, default)
+        V::read(self.hash.into(), default)
     }
 
     pub fn set(&self, value: V, save: bool) -> bool {
-        value.write(self.hash, save)
+        value.write(self.hash.into(), save)
     }
 }
 
@@ -47,7 +54,7 @@ impl StatValue for f32 {
     }
 
     fn write(&self, hash: Hash, save: bool) -> bool {
-        invoke!(bool, 0x11B5E6D2AE73F48E, hash, *self, save)
+        invoke!(bool, 0x4851997F37FE3B1E, hash, *self, save)
     }
 }
 
@@ -60,4 +67,47 @@ impl StatValue for bool {
     fn write(&self, hash: Hash, save: bool) -> bool {
         invoke!(bool, 0x4B33C4243DE0C432, hash, *self, save)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::backend::{self, FakeBackend};
+    use crate::hash::joaat;
+
+    use super::*;
+
+    //Reads the stat into its out argument when it is the wallet, failing otherwise
+    fn get_native(context: &mut crate::native::NativeCallContext) {
+        let mut args = context.get_args();
+        let hash = args.read::<Hash>();
+        let value = args.read::<&mut u32>();
+        let found = hash == joaat("MP0_WALLET_BALANCE");
+        if found {
+            *value = 1;
+        }
+        context.set_result(found);
+    }
+
+    #[test]
+    fn get_and_set() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.on(0x767FBC2AC802EF3D, get_native);
+        backend.on(0x11B5E6D2AE73F48E, get_native);
+        backend.returns(0xB3271D7AB655B441, true);
+        backend.returns(0x4851997F37FE3B1E, false);
+        backend::with(backend.clone(), || {
+            let wallet = Stat::<i32>::new("MP0_WALLET_BALANCE");
+            assert_eq!(wallet.get(-1), Some(1));
+            assert_eq!(Stat::<i32>::new("MP0_BANK_BALANCE").get(-1), None);
+            assert_eq!(Stat::<bool>::new("MP0_WALLET_BALANCE").get(false), Some(true));
+            assert!(wallet.set(500, true));
+            assert!(!Stat::<f32>::new("MP0_WALLET_BALANCE").set(0.5, false));
+        });
+        let wallet = joaat("MP0_WALLET_BALANCE").0 as u64;
+        assert_eq!(backend.calls_to(0x767FBC2AC802EF3D)[0].args[2], u32::MAX as u64);
+        assert_eq!(backend.calls_to(0xB3271D7AB655B441)[0].args, vec![wallet, 500, 1]);
+        assert_eq!(backend.calls_to(0x4851997F37FE3B1E)[0].args, vec![wallet, 0.5f32.to_bits() as u64, 0]);
+    }
+}
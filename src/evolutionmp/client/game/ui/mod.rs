@@ -5,10 +5,10 @@ use std::time::Instant;
 use cgmath::{Vector2, Vector3};
 use clipboard::{ClipboardContext, ClipboardProvider};
 
-use crate::{invoke, native};
-use crate::{bind_fn_ip, bind_field_ip, mem};
+use crate::{invoke, joaat, native, patches};
+use crate::{bind_fn, bind_field, mem};
 use crate::game::Rgba;
-use crate::hash::{Hashable, Hash};
+use crate::hash::{Hashable, Hash, LabelHash};
 use crate::win::input::{InputEvent, KeyboardEvent};
 use crate::client::native::alloc::RageVec;
 use std::ffi::{CStr, OsStr};
@@ -88,8 +88,8 @@ pub enum DynamicMenuAction {
     LowHi
 }
 
-bind_fn_ip!(GET_WARN_RESULT, "33 D2 33 C9 E8 ? ? ? ? 48 83 F8 04 0F 84", 5, (bool, u32) -> FrontendButtons);
-bind_field_ip!(ACTIVE_MENU_POOL, "0F B7 54 51 ?", -4, RageVec<UIMenu>);
+bind_fn!(GET_WARN_RESULT, (bool, u32) -> FrontendButtons);
+bind_field!(ACTIVE_MENU_POOL, RageVec<UIMenu>);
 
 pub fn hook() {
     info!("Hooking UI...");
@@ -103,14 +103,14 @@ pub fn init() {
     unsafe {
         //let no_slowmo = mem!("32 C0 F3 0F 11 09").expect("no_slowmo");
         //no_slowmo.nop(6);
-        let no_slowmo = mem!("38 51 64 74 19")
-           .expect("no_slowmo");
+        let vignette = mem!(VIGNETTE).expect("vignette");
+        patches::apply("no_vignette", &vignette, None, &[RET, NOP, NOP, NOP, NOP]);
 
-        no_slowmo.add(26).read_ptr(4).write_bytes(&[RET, NOP, NOP, NOP, NOP]); //No vignette
+        let vignetting_call = mem!(VIGNETTING_CALL).expect("vignetting call");
+        patches::apply("no_vignetting_call", &vignetting_call, None, &[NOP; 5]);
 
-        no_slowmo.add(8).nop(5); //Vignetting call patch
-
-        no_slowmo.add(34).write_bytes(&[0x31, 0xD2]); //Timescale override patch
+        let timescale = mem!(TIMESCALE_OVERRIDE).expect("timescale override");
+        patches::apply("timescale_override", &timescale, None, &[0x31, 0xD2]);
     }
 }
 
@@ -131,18 +131,21 @@ pub fn print_menus() {
 }
 
 pub fn show_loading_prompt(ty: LoadingPrompt, text: &str) {
+    let _frame = crate::arena::frame();
     invoke!((), 0xABA17D7CE615ADBF, "STRING");
     push_string(text);
     invoke!((), 0xBD12F8228410D9B4, ty as u32);
 }
 
 pub fn show_subtitle(text: &str, duration: i32, immediately: bool) {
+    let _frame = crate::arena::frame();
     invoke!((), 0xB87A37EEB7FAA67D, "STRING");
     push_string(text);
     invoke!((), 0x9D77056A530643F6, duration, immediately);
 }
 
 pub fn show_help(text: &str, looping: bool, beep: bool, duration: Option<u32>) {
+    let _frame = crate::arena::frame();
     invoke!((), 0x8509B634FBE7DA11, "STRING");
     push_string(text);
     invoke!((), 0x238FFE5C7B0498A6, 0, looping, beep, duration.unwrap_or(u32::MAX))
@@ -168,6 +171,7 @@ pub fn draw_text<T, P, S>(text: T, pos: P, color: Rgba, font: Font, scale: S)
 {
     let pos = pos.into();
     let pos = Vector2::new(pos.x / BASE_WIDTH, pos.y / BASE_HEIGHT);
+    let _frame = crate::arena::frame();
     set_text_font(font);
     set_text_scale(scale.into());
     set_text_color(color);
@@ -177,6 +181,7 @@ pub fn draw_text<T, P, S>(text: T, pos: P, color: Rgba, font: Font, scale: S)
 }
 
 pub fn get_text_width<T>(text: T, font: Font, scale: Vector2<f32>) -> f32 where T: AsRef<str> {
+    let _frame = crate::arena::frame();
     set_text_font(font);
     set_text_scale(scale);
     begin_text_command_width("CELL_EMAIL_BCON");
@@ -299,6 +304,8 @@ pub fn is_loading_prompt_visible() -> bool {
     invoke!(bool, 0xD422FCC5F239A915)
 }
 
+/// Adds a text component, which the game reads when the text command ends:
+/// the command should run in an `arena::frame` so the component outlives this call.
 pub fn push_string(value: &str) {
     for s in value.as_bytes().chunks(99).map(|c| unsafe { std::str::from_utf8_unchecked(c) }) {
         invoke!((), 0x6C188BE134E074AA, s)
@@ -406,7 +413,7 @@ fn end_text_command_width(unknown: bool) -> f32 {
 }
 
 pub fn prompt(title: &str, placeholder: &str, max_length: u32) -> Option<String> {
-    super::locale::set_translation("FMMC_KEY_TIP10", title);
+    super::locale::set_translation(joaat!(LabelHash: "FMMC_KEY_TIP10"), title);
     invoke!((), 0x00DC833F2568DBF6, 1u32, "FMMC_KEY_TIP10", "", placeholder, "", "", "", max_length);
     loop {
         match invoke!(u32, 0x0CF2B696BBF945AE) {
@@ -425,9 +432,9 @@ pub fn prompt(title: &str, placeholder: &str, max_length: u32) -> Option<String>
 }
 
 pub fn warn(title: &str, line1: &str, line2: &str, buttons: FrontendButtons, background: bool) -> FrontendButtons {
-    super::locale::set_translation("WNMC_TITLE", title);
-    super::locale::set_translation("WNMC_LINE1", line1);
-    super::locale::set_translation("WNMC_LINE2", line2);
+    super::locale::set_translation(joaat!(LabelHash: "WNMC_TITLE"), title);
+    super::locale::set_translation(joaat!(LabelHash: "WNMC_LINE1"), line1);
+    super::locale::set_translation(joaat!(LabelHash: "WNMC_LINE2"), line2);
     let buttons = buttons.bits;
     loop {
         super::script::wait(0);
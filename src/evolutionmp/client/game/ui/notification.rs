@@ -52,6 +52,7 @@ impl NotificationColor {
 }
 
 pub fn send_notification(text: &str, color: Option<NotificationColor>, flash: Option<NotificationFlash>, log: bool) -> Notification {
+    let _frame = crate::arena::frame();
     invoke!((), 0x202709F4C58A0424, "STRING");
     if let Some(color) = &color {
         color.apply();
@@ -64,6 +65,7 @@ pub fn send_notification(text: &str, color: Option<NotificationColor>, flash: Op
 }
 
 pub fn send_notification_award(text: &str, rp_bonus: u32, color_overlay: u32, texture: Texture) -> Notification {
+    let _frame = crate::arena::frame();
     invoke!((), 0x202709F4C58A0424, "STRING");
     super::push_string(text);
     invoke!(Notification, 0xAA295B6F28BD587D, texture, rp_bonus, color_overlay, "FM_GEN_UNLOCK")
@@ -5,7 +5,7 @@ use crate::game::ped::Ped;
 use crate::game::streaming::{Model, Resource};
 use crate::game::radio::RadioStation;
 use crate::game::worldprobe::ProbeEntity;
-use crate::hash::{Hashable, Hash};
+use crate::hash::{Hashable, Hash, ModelHash};
 use crate::native::vehicle::{
     RPM, WHEEL_SPEED, STEERING_SCALE, STEERING_ANGLE, NEXT_GEAR, CURRENT_GEAR, HIGH_GEAR, ALARM_TIME,
     CLUTCH, TURBO, BRAKE_POWER, TRAIN_TRACK_NODE, LIGHTS, FUEL_LEVEL, THROTTLE, THROTTLE_POWER,
@@ -169,7 +169,8 @@ pub struct Vehicle {
 }
 
 impl Vehicle {
-    pub fn new<H>(model: H, pos: Vector3<f32>, heading: f32, is_network: bool, this_script_check: bool) -> Option<Vehicle> where H: Hashable {
+    pub fn new<M>(model: M, pos: Vector3<f32>, heading: f32, is_network: bool, this_script_check: bool) -> Option<Vehicle> where M: Into<ModelHash> {
+        let model: ModelHash = model.into();
         let model = Model::from(model);
         if model.is_in_cd_image() && model.is_valid() && model.is_vehicle() {
             model.request_and_wait();
@@ -851,4 +852,88 @@ impl<'a> VehicleTowtruck<'a> {
     pub fn set_crane_uplift(&self, uplift: f32) {
         invoke!((), 0xFE54B92A344583CA, self.vehicle.handle, uplift)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::backend::{self, FakeBackend};
+    use crate::hash::joaat;
+
+    use super::*;
+
+    #[test]
+    fn spawn_checks_the_model() {
+        let backend = Arc::new(FakeBackend::new().allow_unknown(true));
+        backend.returns(0x35B9E0803292B641, true);
+        backend.returns(0xC0296A2EDF545E92, true);
+        backend.returns(0xAF35D0D2583051B0, 0x2A04u32);
+        backend::with(backend.clone(), || {
+            backend.returns(0x19AAC8F07BFEC53E, false);
+            assert_eq!(Vehicle::new("a_c_chop", Vector3::new(0.0, 0.0, 0.0), 0.0, false, true), None);
+            backend.returns(0x19AAC8F07BFEC53E, true);
+            let vehicle = Vehicle::new("adder", Vector3::new(1.0, 2.0, 3.0), 90.0, true, false);
+            assert_eq!(vehicle.map(|v| v.get_handle()), Some(0x2A04));
+        });
+        let create = backend.calls_to(0xAF35D0D2583051B0);
+        assert_eq!(create.len(), 1);
+        assert_eq!(create[0].args[0], joaat("adder").0 as u64);
+        assert_eq!(&create[0].args[4..], &[90.0f32.to_bits() as u64, 1, 0]);
+        //Both models are released once dropped
+        assert_eq!(backend.calls_to(0xE532F5D78798DAAB).len(), 2);
+    }
+
+    #[test]
+    fn out_args_and_optional_results() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.on(0xB64CF2CCA9D95F52, |context| {
+            let mut args = context.get_args();
+            let _vehicle = args.read::<Handle>();
+            *args.read::<&mut u32>() = 255;
+            *args.read::<&mut u32>() = 128;
+            *args.read::<&mut u32>() = 7;
+        });
+        backend.on(0xBB40DD2270B65366, |context| {
+            let mut args = context.get_args();
+            let _vehicle = args.read::<Handle>();
+            context.set_result(if args.read::<i32>() == -1 { 0x3301u32 } else { 0 });
+        });
+        backend::with(backend.clone(), || {
+            let vehicle = Vehicle::from_handle(0x2A04).unwrap();
+            assert_eq!(vehicle.get_primary_color_rgb(), (255, 128, 7));
+            assert_eq!(vehicle.get_passenger(-1).map(|p| p.get_handle()), Some(0x3301));
+            assert!(vehicle.get_passenger(0).is_none());
+        });
+        assert_eq!(backend.calls_to(0xBB40DD2270B65366)[1].args, vec![0x2A04, 0]);
+    }
+
+    #[test]
+    fn delete_clears_the_handle() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.on(0xAD738C3085FE7E11, |_| {});
+        backend.on(0xEA386986E786A54F, |context| {
+            *context.get_args().read::<&mut Handle>() = 0;
+        });
+        backend::with(backend.clone(), || {
+            let mut vehicle = Vehicle::from_handle(0x2A04).unwrap();
+            vehicle.delete();
+            assert_eq!(vehicle.get_handle(), 0);
+        });
+        assert_eq!(backend.calls_to(0xAD738C3085FE7E11)[0].args, vec![0x2A04, 0, 1]);
+    }
+
+    #[test]
+    fn classes() {
+        let backend = Arc::new(FakeBackend::new());
+        backend::with(backend.clone(), || {
+            backend.returns(0xDEDF1C8BD47C2200, 8u32);
+            assert!(matches!(VehicleClass::from("bati"), Some(VehicleClass::Motorcycle)));
+            backend.returns(0xDEDF1C8BD47C2200, 22u32);
+            assert!(VehicleClass::from("bati").is_none());
+        });
+        assert!(matches!(VehicleClass::from_handle(21), Some(VehicleClass::Train)));
+        assert!(!VehicleClass::Boat.has_custom_horns());
+        assert!(VehicleClass::Super.has_custom_horns());
+    }
+}
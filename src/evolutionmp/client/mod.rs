@@ -3,7 +3,6 @@ use std::path::Path;
 use std::sync::atomic::Ordering;
 
 use backtrace::{Backtrace, SymbolName};
-use detour::RawDetour;
 use winapi::ctypes::c_void;
 use winapi::shared::minwindef::{BOOL, DWORD, HINSTANCE, HLOCAL, HMODULE, LPVOID, MAX_PATH, TRUE};
 use winapi::shared::windef::{HHOOK, HMENU, HWND};
@@ -21,23 +20,25 @@ use wio::wide::FromWide;
 
 use game::GameState;
 
-use crate::{bind_field, bind_field_ip, LOG_PANIC, mem};
-use crate::client::pattern::RET;
+use crate::{bind_field, LOG_PANIC, mem};
+use crate::client::pattern::{NOP, RET};
 
 pub mod win;
 pub mod native;
+pub mod natives;
 pub mod runtime;
 pub mod events;
-pub mod mappings;
 pub mod game;
 pub mod pattern;
 pub mod registry;
 pub mod scripts;
 pub mod jni;
 pub mod console;
+pub mod detours;
+pub mod patches;
 
-bind_field_ip!(GAME_STATE, "83 3D ? ? ? ? ? 75 17 8B 43 20 25", 2, GameState, 5);
-bind_field_ip!(HEAP_SIZE, "83 C8 01 48 8D 0D ? ? ? ? 41 B1 01 45 33 C0", 17, u32);
+bind_field!(GAME_STATE, GameState);
+bind_field!(HEAP_SIZE, u32);
 
 unsafe fn print_address_info(addr: *mut c_void, line: Option<u32>, symbol_name: SymbolName) {
     let mut mbi = MEMORY_BASIC_INFORMATION::default();
@@ -130,10 +131,17 @@ extern "system" fn except(info: *mut EXCEPTION_POINTERS) -> LONG {
             error!(target: LOG_PANIC, "Script {} crashed with an exception", active_script.context.script_hash);
         }
         if native != 0 {
-            error!(target: LOG_PANIC, "Unhandled exception at 0x{:08X} caused by native invocation `0x{:016X}`: 0x{:08X} ({})", addr as u64, native, code, message);
+            error!(target: LOG_PANIC, "Unhandled exception at 0x{:08X} caused by native invocation `{}`: 0x{:08X} ({})", addr as u64, crate::nativedb::describe(native), code, message);
         } else {
             error!(target: LOG_PANIC, "Unhandled exception at 0x{:08X}: 0x{:08X} ({})", addr as u64, code, message);
         }
+        if crate::trace::enabled() {
+            let path = crate::launcher_dir().join(crate::trace::TRACE_FILE);
+            match crate::trace::dump_to_file(&path) {
+                Ok(count) => error!(target: LOG_PANIC, "Dumped {} last native calls to {}", count, path.display()),
+                Err(e) => error!(target: LOG_PANIC, "Unable to dump native calls to {}: {}", path.display(), e)
+            }
+        }
 
         let backtrace = Backtrace::new();
 
@@ -195,11 +203,8 @@ macro_rules! proc_detour {
                 let module = GetModuleHandleA(module.as_ptr());
                 let proc = CString::new($proc).unwrap();
                 let proc = GetProcAddress(module, proc.as_ptr());
-                let detour = RawDetour::new(proc as _, $repl as _)
-                    .expect(concat!("error detouring ", $proc));
-                detour.enable().expect(concat!("error enabling detour for ", $proc));
-                let trampoline = detour.trampoline() as *const ();
-                std::mem::forget(detour);
+                let trampoline = detours::install($proc, proc as _, $repl as _)
+                    .unwrap_or_else(|e| panic!("error detouring {}: {}", $proc, e));
                 std::mem::transmute(trampoline)
             };
         }
@@ -256,7 +261,11 @@ unsafe extern fn debug_w(text: LPWSTR) {
 
 unsafe fn initialize(window: &Window) {
     console::attach();
+    native::reload_hashes();
 
+    info!("Detected game version {}", *native::GAME_VERSION);
+    lazy_static::initialize(&native::BUILD);
+    native::prefetch_bindings();
     lazy_static::initialize(&GAME_STATE);
     info!("Hooking DirectX...");
     win::direct::hook();
@@ -274,13 +283,14 @@ unsafe fn initialize(window: &Window) {
         .nop(2); //Disable legals*/
     /*mem!("48 83 3D ? ? ? ? 00 88 05 ? ? ? ? 75 0B").expect("force offline")
         .add(8).nop(6);*/ // FIXME
-    let focus_pause = mem!("0F 95 05 ? ? ? ? E8 ? ? ? ? 48 85 C0").expect("focus pause");
-    focus_pause.add(3).read_ptr(4).write_bytes(&[0]);
-    focus_pause.nop(7);
-    bind_field!(DEVICE_LIMIT, "C7 05 ? ? ? ? 64 00 00 00 48 8B", 6, u32);
-    *DEVICE_LIMIT.as_mut() *= 15;
-    mem!("C6 80 F0 00 00 00 01 E8 ? ? ? ? E8").expect("no relative device sorting")
-        .add(12).nop(5);
+    let focus_pause_flag = mem!(FOCUS_PAUSE_FLAG).expect("focus pause flag");
+    patches::apply("focus_pause_flag", &focus_pause_flag, None, &[0]);
+    let focus_pause = mem!(FOCUS_PAUSE).expect("focus pause");
+    patches::apply("focus_pause", &focus_pause, Some("0F 95 05 ? ? ? ?"), &[NOP; 7]);
+    let device_limit = mem!(DEVICE_LIMIT).expect("device limit");
+    patches::apply("device_limit", &device_limit, Some("64 00 00 00"), &(100u32 * 15).to_le_bytes());
+    let relative_device_sorting = mem!(RELATIVE_DEVICE_SORTING).expect("no relative device sorting");
+    patches::apply("no_relative_device_sorting", &relative_device_sorting, Some("E8 ? ? ? ?"), &[NOP; 5]);
     /*mem!("48 85 C0 0F 84 ? ? ? ? 8B 48 50").expect("unlock objects")
         .nop(24);*/
 
@@ -309,6 +319,8 @@ unsafe fn initialize(window: &Window) {
     game::hook();
     game::init();
 
+    native::log_pattern_reports();
+
     scripts::init();
 }
 
@@ -327,9 +339,12 @@ fn attach() {
 
 #[cfg(target_os = "windows")]
 fn detach() {
+    detours::disable_all();
     unsafe {
         win::input::unhook();
     }
+    patches::restore_all();
+    native::save_pattern_cache();
 }
 
 #[repr(u32)]
@@ -0,0 +1,53 @@
+use std::sync::RwLock;
+
+use crate::dispatch::{Budget, CallHandle, Dispatcher, DrainStats};
+
+pub use crate::dispatch::DispatchError;
+
+lazy_static! {
+    static ref DISPATCHER: Dispatcher = Dispatcher::new();
+    static ref BUDGET: RwLock<Budget> = RwLock::new(Budget::default());
+}
+
+/// Runs a closure on the game script thread, from any thread.
+/// Calls made from the game thread itself run right away.
+pub fn submit<T, F>(call: F) -> CallHandle<T> where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+    DISPATCHER.submit(call)
+}
+
+/// Calls waiting for the next frame.
+pub fn pending() -> usize {
+    DISPATCHER.len()
+}
+
+pub fn budget() -> Budget {
+    *BUDGET.read().expect("lock poisoned")
+}
+
+pub fn set_budget(budget: Budget) {
+    *BUDGET.write().expect("lock poisoned") = budget;
+}
+
+/// Makes the current thread the game script thread, before the first drain.
+pub fn bind() {
+    DISPATCHER.bind()
+}
+
+/// Runs the queued calls the frame budget allows, called by the dispatch script every frame.
+pub fn drain() -> DrainStats {
+    DISPATCHER.drain(budget())
+}
+
+/// Fails every queued call, when scripts are going away.
+pub fn clear() -> usize {
+    DISPATCHER.clear()
+}
+
+/// Invokes a native on the game script thread, returning a `CallHandle` of its result.
+/// Arguments are moved to the game thread and evaluated there.
+#[macro_export]
+macro_rules! dispatch_invoke {
+    ($ret: ty, $hash: literal $(, $arg: expr)*) => {
+        $crate::native::dispatch::submit(move || $crate::invoke!($ret, $hash $(, $arg)*))
+    };
+}
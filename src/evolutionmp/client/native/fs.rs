@@ -13,31 +13,32 @@ use winapi::um::fileapi::INVALID_FILE_ATTRIBUTES;
 use winapi::um::winbase::{FILE_BEGIN, FILE_CURRENT, FILE_END};
 use winapi::um::winnt::FILE_ATTRIBUTE_DIRECTORY;
 
-use crate::{bind_field_ip, bind_fn, bind_fn_detour, bind_fn_detour_ip, class};
+use crate::{bind_field, bind_fn, bind_fn_detour, class};
 use crate::pattern::RageBox;
-
-bind_fn_detour_ip!(OPEN_PACK_FILES, "41 B0 01 BA 1B E6 DA 93 E8", -12, open_pack_files, () -> ());
-bind_fn_detour!(ADD_COLLISION, "48 8B FA 89 44 24 30 48 8B D9 E8 ? ? ? ? 0F", 10, add_collision, (*mut u8, &mut u32, &u32) -> ());
-bind_fn_detour_ip!(SOME_FN, "66 39 79 38 74 06 4C 8B 41 30 EB 07 4C 8D", 19, some_fn, (*mut u8, *mut u8, RagePath) -> u32);
-
-bind_fn!(GET_DEVICE, "41 B8 07 00 00 00 48 8B F1 E8", -0x1F, (RagePath, bool) -> Option<ManuallyDrop<Box<Device>>>);
-bind_fn!(MOUNT_GLOBAL, "41 8A F0 48 8B F9 E8 ? ? ? ? 33 DB 85 C0", -0x28, (RagePath, &Device, bool) -> bool);
-bind_fn!(UNMOUNT, "E8 ? ? ? ? 85 C0 75 23 48 83", -0x22, (RagePath) -> ());
-bind_fn!(PACK_FILE_INIT, "44 89 41 28 4C 89 41 38 4C 89 41 50 48 8D", -0x1E, (&mut PackFile) -> ());
-bind_fn_detour!(PACK_FILE_OPEN, "48 8D 68 98 48 81 EC 40 01 00 00 41 8B F9", -0x18, pack_file_open, (&mut PackFile, RagePath, bool, i32, u64) -> bool);
-bind_fn_detour!(PACK_FILE_MOUNT, "84 C0 74 1D 48 85 DB 74 0F 48", -0x1E, pack_file_mount, (&mut PackFile, RagePath) -> ());
-bind_fn!(RELATIVE_DEVICE_SET_PATH, "49 8B F9 48 8B D9 4C 8B CA", -0x17, (&mut RelativeDevice, RagePath, bool, Option<&Device>) -> ());
-bind_fn_detour!(RELATIVE_DEVICE_MOUNT, "44 8A 81 14 01 00 00 48 8B DA 48 8B F9 48 8B D1", -0xD, relative_device_mount, (&mut RelativeDevice, RagePath, bool) -> ());
-bind_fn!(KEY_STATE_INIT, "45 33 F6 48 89 85 30 02 00 00 48 8D 45 30 48", -12, (&mut KeyState, *const u8) -> ());
+use crate::version::{PACK_FILE_CAPACITY, RELATIVE_DEVICE_CAPACITY};
+
+bind_fn_detour!(OPEN_PACK_FILES, open_pack_files, () -> ());
+bind_fn_detour!(ADD_COLLISION, add_collision, (*mut u8, &mut u32, &u32) -> ());
+bind_fn_detour!(SOME_FN, some_fn, (*mut u8, *mut u8, RagePath) -> u32);
+
+bind_fn!(GET_DEVICE, (RagePath, bool) -> Option<ManuallyDrop<Box<Device>>>);
+bind_fn!(MOUNT_GLOBAL, (RagePath, &Device, bool) -> bool);
+bind_fn!(UNMOUNT, (RagePath) -> ());
+bind_fn!(PACK_FILE_INIT, (&mut PackFile) -> ());
+bind_fn_detour!(PACK_FILE_OPEN, pack_file_open, (&mut PackFile, RagePath, bool, i32, u64) -> bool);
+bind_fn_detour!(PACK_FILE_MOUNT, pack_file_mount, (&mut PackFile, RagePath) -> ());
+bind_fn!(RELATIVE_DEVICE_SET_PATH, (&mut RelativeDevice, RagePath, bool, Option<&Device>) -> ());
+bind_fn_detour!(RELATIVE_DEVICE_MOUNT, relative_device_mount, (&mut RelativeDevice, RagePath, bool) -> ());
+bind_fn!(KEY_STATE_INIT, (&mut KeyState, *const u8) -> ());
 
 //bind_fn_ip!(ORIGINAL_MOUNT, "48 81 EC E0 03 00 00 48 B8 63 6F 6D 6D", -0x1A, () -> ());
 //bind_fn_detour_ip!(INITIAL_MOUNT, "0F B7 05 ? ? ? ? 48 03 C3 44 88 34 38 66", 0x15, initial_mount, () -> ());
-bind_fn_detour!(INITIAL_MOUNT, "48 81 EC E0 03 00 00 48 B8 63 6F 6D 6D", -0x1A, initial_mount, () -> ());
+bind_fn_detour!(INITIAL_MOUNT, initial_mount, () -> ());
 
-bind_field_ip!(DEVICE_VTABLE, "48 21 35 ? ? ? ? 48 8B 74 24 38 48 8D 05", 15, DeviceVT);
-bind_field_ip!(PACK_FILE_VTABLE, "44 89 41 28 4C 89 41 38 4C 89 41 50 48 8D 05", 15, DeviceVT);
-bind_field_ip!(RELATIVE_DEVICE_VTABLE, "48 85 C0 74 11 48 83 63 08 00 48", 13, DeviceVT);
-bind_field_ip!(ENCRYPTING_DEVICE_VTABLE, "45 33 F6 48 89 85 30 02 00 00 48 8D 45 30 48", -4, DeviceVT);
+bind_field!(DEVICE_VTABLE, DeviceVT);
+bind_field!(PACK_FILE_VTABLE, DeviceVT);
+bind_field!(RELATIVE_DEVICE_VTABLE, DeviceVT);
+bind_field!(ENCRYPTING_DEVICE_VTABLE, DeviceVT);
 
 extern fn pack_file_mount(file: &mut PackFile, path: RagePath) {
     //info!("Mounting pack file \"{}\" to path: \"{}\"", file.get_name(), path);
@@ -666,12 +667,10 @@ impl<'a> Seek for DeviceOpenBulkGuard<'a> {
     }
 }
 
-const PACK_FILE_SIZE: usize = 368 + (0x650 - 0x590);
-
 #[repr(C)]
 pub struct PackFile {
     device: Device,
-    inner: [u8; PACK_FILE_SIZE],
+    inner: [u8; PACK_FILE_CAPACITY],
 }
 
 impl PackFile {
@@ -680,7 +679,7 @@ impl PackFile {
             device: Device {
                 v_table: unsafe { std::mem::transmute(PACK_FILE_VTABLE.cloned()) }
             },
-            inner: [0; PACK_FILE_SIZE],
+            inner: [0; PACK_FILE_CAPACITY],
         };
         PACK_FILE_INIT(&mut pack_file);
         if PACK_FILE_OPEN(&mut pack_file, archive.as_ref().into(), true, ty, 0) {
@@ -720,12 +719,10 @@ impl AsDevice for PackFile {
     }
 }
 
-const RELATIVE_DEVICE_SIZE: usize = 272;
-
 #[repr(C)]
 pub struct RelativeDevice {
     device: Device,
-    inner: [u8; RELATIVE_DEVICE_SIZE],
+    inner: [u8; RELATIVE_DEVICE_CAPACITY],
 }
 
 impl AsDevice for RelativeDevice {
@@ -750,7 +747,7 @@ impl RelativeDevice {
             device: Device {
                 v_table: unsafe { std::mem::transmute(RELATIVE_DEVICE_VTABLE.cloned()) }
             },
-            inner: [0; RELATIVE_DEVICE_SIZE],
+            inner: [0; RELATIVE_DEVICE_CAPACITY],
         }
     }
 
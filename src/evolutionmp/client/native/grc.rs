@@ -1,11 +1,11 @@
 use std::ops::Deref;
 use std::os::raw::c_char;
 
-use crate::bind_field_ip;
+use crate::bind_field;
 use crate::pattern::RageBox;
 
-bind_field_ip!(TEXTURE_FACTORY, "84 DB 48 0F 45 C2 48 89 05", 9, TextureFactory);
-bind_field_ip!(MISSING_TEXTURE, "45 33 C0 48 8B CF FF 50 20 48 8D 15", 22, Texture);
+bind_field!(TEXTURE_FACTORY, TextureFactory);
+bind_field!(MISSING_TEXTURE, Texture);
 
 enum D3D11Resource {}
 
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::native::{NATIVE_TABLE, NATIVES, NativeCallContext, NativeFunction};
+
+pub use crate::hook::{Hook, HookAction, HookId, PostHook, PreHook, ReplaceHook};
+use crate::hook::Hooks;
+
+/// Natives that can be hooked in the game's native table at the same time,
+/// hooks of other natives only applying to our own calls.
+pub const MAX_TABLE_HOOKS: usize = 64;
+
+//Native table entry swapped for a trampoline
+struct Installed {
+    slot: usize,
+    target: u64,
+    original: NativeFunction,
+}
+
+static HOOKED: AtomicUsize = AtomicUsize::new(0);
+//Hash a trampoline dispatches, 0 when free
+static SLOTS: [AtomicU64; MAX_TABLE_HOOKS] = [const { AtomicU64::new(0) }; MAX_TABLE_HOOKS];
+
+lazy_static! {
+    static ref HOOKS: RwLock<Hooks> = RwLock::new(Hooks::new());
+    static ref INSTALLED: Mutex<HashMap<u64, Installed>> = Mutex::new(HashMap::new());
+}
+
+extern fn trampoline<const SLOT: usize>(context: *mut NativeCallContext) {
+    let hash = SLOTS[SLOT].load(Ordering::Acquire);
+    let context = unsafe { &mut *context };
+    //Natives missing from our handlers, like unmapped ones, still reach the handler the trampoline replaced
+    let handler = super::get_handler_opt(hash)
+        .or_else(|| INSTALLED.lock().expect("lock poisoned").get(&hash).map(|i| i.original));
+    if let Some(handler) = handler {
+        call(hash, handler, context);
+    }
+}
+
+macro_rules! trampolines {
+    ($($slot:literal)*) => {
+        [$(trampoline::<$slot> as NativeFunction),*]
+    };
+}
+
+static TRAMPOLINES: [NativeFunction; MAX_TABLE_HOOKS] = trampolines!(
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+    32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63
+);
+
+/// Hooks a native, lower priorities running first and hooks of the same priority in the order they were added.
+/// Only the first replacement of a native runs.
+pub fn add(hash: u64, priority: i32, hook: Hook) -> HookId {
+    let mut hooks = HOOKS.write().expect("lock poisoned");
+    let first = !hooks.is_hooked(hash);
+    let id = hooks.add(hash, priority, hook);
+    HOOKED.store(hooks.len(), Ordering::Release);
+    //Installed under the lock, or a concurrent `remove` could uninstall before we install
+    if first {
+        install(hash);
+    }
+    id
+}
+
+pub fn pre<F>(hash: u64, priority: i32, hook: F) -> HookId
+    where F: Fn(&mut NativeCallContext) -> HookAction + Send + Sync + 'static
+{
+    add(hash, priority, Hook::Pre(Arc::new(hook)))
+}
+
+pub fn replace<F>(hash: u64, priority: i32, hook: F) -> HookId
+    where F: Fn(&mut NativeCallContext, NativeFunction) + Send + Sync + 'static
+{
+    add(hash, priority, Hook::Replace(Arc::new(hook)))
+}
+
+pub fn post<F>(hash: u64, priority: i32, hook: F) -> HookId
+    where F: Fn(&mut NativeCallContext) + Send + Sync + 'static
+{
+    add(hash, priority, Hook::Post(Arc::new(hook)))
+}
+
+/// Removes a hook, restoring the native once it has none left.
+pub fn remove(id: HookId) -> bool {
+    let mut hooks = HOOKS.write().expect("lock poisoned");
+    match hooks.remove(id) {
+        Some(hash) => {
+            HOOKED.store(hooks.len(), Ordering::Release);
+            if !hooks.is_hooked(hash) {
+                uninstall(hash);
+            }
+            true
+        }
+        None => false
+    }
+}
+
+/// Removes every hook of a native, returning how many there were.
+pub fn clear(hash: u64) -> usize {
+    let mut hooks = HOOKS.write().expect("lock poisoned");
+    let removed = hooks.clear(hash);
+    if removed > 0 {
+        HOOKED.store(hooks.len(), Ordering::Release);
+        uninstall(hash);
+    }
+    removed
+}
+
+/// Hooked natives, sorted.
+pub fn hooked() -> Vec<u64> {
+    HOOKS.read().expect("lock poisoned").hooked()
+}
+
+/// Calls a native through its hooks, called by `invoke!`, the Java runtime and the game's native table.
+pub fn call(hash: u64, handler: NativeFunction, context: &mut NativeCallContext) {
+    if HOOKED.load(Ordering::Acquire) == 0 {
+        return handler(context);
+    }
+    crate::hook::call(&HOOKS, hash, handler, context);
+}
+
+//Points the game's table entry of the native to a trampoline, so script VM calls get hooked too.
+//Called with `HOOKS` locked for writing, which serializes it with `uninstall`
+fn install(hash: u64) {
+    let mut installed = INSTALLED.lock().expect("lock poisoned");
+    if installed.contains_key(&hash) {
+        return;
+    }
+    let slot = match SLOTS.iter().position(|s| s.load(Ordering::Acquire) == 0) {
+        Some(slot) => slot,
+        None => {
+            warn!("No trampoline left to hook {} in the native table, only our own calls are hooked",
+                  crate::nativedb::describe(hash));
+            return;
+        }
+    };
+    let target = NATIVES.resolve(hash);
+    SLOTS[slot].store(hash, Ordering::Release);
+    match unsafe { NATIVE_TABLE.replace_handler(target, TRAMPOLINES[slot]) } {
+        Some(original) => {
+            installed.insert(hash, Installed { slot, target, original });
+        }
+        None => {
+            SLOTS[slot].store(0, Ordering::Release);
+            warn!("{} is missing from the native table, only our own calls are hooked", crate::nativedb::describe(hash));
+        }
+    }
+}
+
+fn uninstall(hash: u64) {
+    if let Some(installed) = INSTALLED.lock().expect("lock poisoned").remove(&hash) {
+        unsafe { NATIVE_TABLE.replace_handler(installed.target, installed.original) };
+        SLOTS[installed.slot].store(0, Ordering::Release);
+    }
+}
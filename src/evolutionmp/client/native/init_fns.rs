@@ -1,7 +1,5 @@
-use std::collections::HashMap;
-
-use crate::{bind_fn_detour, bind_fn_detour_ip, class};
-use crate::hash::{Hash, Hashable};
+use crate::{bind_fn_detour, class};
+use crate::hash::InitFnHash;
 use crate::win::thread::seh;
 use crate::native::alloc::{RageVec, ChainedBox};
 use winapi::vc::excpt::EXCEPTION_EXECUTE_HANDLER;
@@ -32,16 +30,12 @@ struct InitFnData {
     shutdown_order: u32,
     init_mask: InitFnMask,
     shutdown_mask: InitFnMask,
-    hash: Hash
+    hash: InitFnHash
 }
 
 impl InitFnData {
     fn get_name(&self) -> String {
-        if let Some(name) = FN_MAP.get(&self.hash) {
-            String::from(*name)
-        } else {
-            format!("0x{:08X}", self.hash.0)
-        }
+        format!("{}", self.hash)
     }
 
     unsafe fn try_init(&self, mask: InitFnMask) {
@@ -105,7 +99,7 @@ class!(UpdateFn @UpdateFnVT {
 
     flag: bool,
     float: f32,
-    hash: Hash,
+    hash: InitFnHash,
     next: Option<ChainedBox<UpdateFn>>,
     child: Option<ChainedBox<UpdateFn>>
 });
@@ -119,11 +113,7 @@ impl UpdateFn {
     }
 
     fn get_name(&self) -> String {
-        if let Some(name) = FN_MAP.get(&self.hash) {
-            String::from(*name)
-        } else {
-            format!("0x{:08X}", self.hash.0)
-        }
+        format!("{}", self.hash)
     }
     extern fn run_group(&mut self) {
         let name = self.get_name();
@@ -197,14 +187,10 @@ impl GameSkeleton {
     }
 }
 
-lazy_static! {
-    pub static ref FN_MAP: HashMap<Hash, &'static str> = KNOWN_INIT_FNS.iter().map(|f| (f.joaat(), *f)).collect();
-}
-
-bind_fn_detour_ip!(UNK_U8, "E8 ? ? ? ? 84 C0 74 ? 66 0F 6E 35 ? ? ?", 0, unk_u8, () -> u8);
-bind_fn_detour_ip!(RUN_INIT, "BA 04 00 00 00 E8 ? ? ? ? E8 ? ? ? ? E8", 5, GameSkeleton::init, (&mut GameSkeleton, InitFnMask) -> ());
-bind_fn_detour_ip!(RUN_UPDATE, "48 8D 0D ? ? ? ? BA 01 00 00 00 E8 ? ? ? ? E8 ? ? ? ?", 12, GameSkeleton::update, (&mut GameSkeleton, u32) -> ());
-bind_fn_detour!(RUN_UPDATE_GROUP, "40 53 48 83 EC 20 48 8B 59 20 EB 0D 48 8B 03 48", 0, UpdateFn::run_group, (&mut UpdateFn) -> ());
+bind_fn_detour!(UNK_U8, unk_u8, () -> u8);
+bind_fn_detour!(RUN_INIT, GameSkeleton::init, (&mut GameSkeleton, InitFnMask) -> ());
+bind_fn_detour!(RUN_UPDATE, GameSkeleton::update, (&mut GameSkeleton, u32) -> ());
+bind_fn_detour!(RUN_UPDATE_GROUP, UpdateFn::run_group, (&mut UpdateFn) -> ());
 
 extern fn unk_u8() -> u8 { // Seems to be called every frame
     0
@@ -213,300 +199,7 @@ extern fn unk_u8() -> u8 { // Seems to be called every frame
 pub fn hook() {
     info!("Hooking init functions...");
     lazy_static::initialize(&UNK_U8);
-    lazy_static::initialize(&FN_MAP);
     //lazy_static::initialize(&RUN_INIT);
     /*lazy_static::initialize(&RUN_UPDATE);
     lazy_static::initialize(&RUN_UPDATE_GROUP);*/
 }
-
-static KNOWN_INIT_FNS: [&'static str; 289] = [
-    "AmbientLights",
-    "AnimBlackboard",
-    "Audio",
-    "BackgroundScripts",
-    "CActionManager",
-    "CAgitatedManager",
-    "CAmbientAnimationManager",
-    "CAmbientAudioManager",
-    "CAmbientModelSetManager",
-    "CAnimBlackboard",
-    "CAppDataMgr",
-    "CAssistedMovementRouteStore",
-    "CBoatChaseDirector",
-    "CBuses",
-    "CBusySpinner",
-    "CCheat",
-    "CCheckCRCs",
-    "CClipDictionaryStoreInterface",
-    "CClock",
-    "CCombatDirector",
-    "CCombatInfoMgr",
-    "CCompEntity",
-    "CConditionalAnimManager",
-    "CContentExport",
-    "CContentSearch",
-    "CControl",
-    "CControlMgr",
-    "CControllerLabelMgr",
-    "CCover",
-    "CCoverFinder",
-    "CCredits",
-    "CCrimeInformationManager",
-    "CCullZones",
-    "CDLCScript",
-    "CDecoratorInterface",
-    "CDispatchData",
-    "CEventDataManager",
-    "CExpensiveProcessDistributer",
-    "CExplosionManager",
-    "CExtraContent",
-    "CExtraContentWrapper",
-    "CExtraContentWrapper::Shutdown",
-    "CExtraContentWrapper::ShutdownStart",
-    "CExtraMetadataMgr",
-    "CExtraMetadataMgr::ClassInit",
-    "CExtraMetadataMgr::ClassShutdown",
-    "CExtraMetadataMgr::ShutdownDLCMetaFiles",
-    "CFlyingVehicleAvoidanceManager",
-    "CFocusEntityMgr",
-    "CFrontendStatsMgr",
-    "CGameLogic",
-    "CGameSituation",
-    "CGameStreamMgr",
-    "CGameWorld",
-    "CGameWorldHeightMap",
-    "CGameWorldWaterHeight",
-    "CGarages",
-    "CGenericGameStorage",
-    "CGestureManager",
-    "CGps",
-    "CGtaAnimManager",
-    "CHandlingDataMgr",
-    "CInstanceListAssetLoader::Init",
-    "CInstanceListAssetLoader::Shutdown",
-    "CIplCullBox",
-    "CJunctions",
-    "CLODLightManager",
-    "CLODLights",
-    "CLadderMetadataManager",
-    "CLoadingScreens",
-    "CMapAreas",
-    "CMapZoneManager",
-    "CMessages",
-    "CMiniMap",
-    "CModelInfo",
-    "CModelInfo::Init",
-    "CMovieMeshManager",
-    "CMultiplayerGamerTagHud",
-    "CNetRespawnMgr",
-    "CNetwork",
-    "CNetworkTelemetry",
-    "CNewHud",
-    "CObjectPopulationNY",
-    "COcclusion",
-    "CParaboloidShadow",
-    "CPathFind",
-    "CPathServer::InitBeforeMapLoaded",
-    "CPathServer::InitSession",
-    "CPathServer::ShutdownSession",
-    "CPathZoneManager",
-    "CPatrolRoutes",
-    "CPauseMenu",
-    "CPed",
-    "CPedAILodManager",
-    "CPedGeometryAnalyser",
-    "CPedModelInfo",
-    "CPedPopulation",
-    "CPedPopulation::ResetPerFrameScriptedMu",
-    "CPedPopulation::ResetPerFrameScriptedMultipiers",
-    "CPedPropsMgr",
-    "CPedVariationPack",
-    "CPedVariationStream",
-    "CPerformance",
-    "CPhoneMgr",
-    "CPhotoManager",
-    "CPhysics",
-    "CPickupDataManager",
-    "CPickupManager",
-    "CPlantMgr",
-    "CPlayStats",
-    "CPlayerSwitch",
-    "CPopCycle",
-    "CPopZones",
-    "CPopulationStreaming",
-    "CPopulationStreamingWrapper",
-    "CPortal",
-    "CPortalTracker",
-    "CPostScan",
-    "CPrecincts",
-    "CPrioritizedClipSetRequestManager",
-    "CPrioritizedClipSetStreamer",
-    "CProcObjectMan",
-    "CProceduralInfo",
-    "CProfileSettings",
-    "CRandomEventManager",
-    "CRecentlyPilotedAircraft",
-    "CRenderPhaseCascadeShadowsInterface",
-    "CRenderTargetMgr",
-    "CRenderThreadInterface",
-    "CRenderer",
-    "CReportMenu",
-    "CRestart",
-    "CRiots",
-    "CRoadBlock",
-    "CScaleformMgr",
-    "CScenarioActionManager",
-    "CScenarioManager",
-    "CScenarioManager::ResetExclusiveScenari",
-    "CScenarioManager::ResetExclusiveScenarioGroup",
-    "CScenarioPointManager",
-    "CScenarioPointManagerInitSession",
-    "CScene",
-    "CSceneStreamerMgr::PreScanUpdate",
-    "CScriptAreas",
-    "CScriptCars",
-    "CScriptDebug",
-    "CScriptEntities",
-    "CScriptHud",
-    "CScriptPedAIBlips",
-    "CScriptPeds",
-    "CScriptedGunTaskMetadataMgr",
-    "CShaderHairSort",
-    "CShaderLib",
-    "CSituationalClipSetStreamer",
-    "CSky",
-    "CSlownessZonesManager",
-    "CSprite2d",
-    "CStaticBoundsStore",
-    "CStatsMgr",
-    "CStreaming",
-    "CStreamingRequestList",
-    "CStuntJumpManager",
-    "CTVPlaylistManager",
-    "CTacticalAnalysis",
-    "CTask",
-    "CTaskClassInfoManager",
-    "CTaskRecover",
-    "CTexLod",
-    "CText",
-    "CThePopMultiplierAreas",
-    "CTheScripts",
-    "CTimeCycle",
-    "CTrafficLights",
-    "CTrain",
-    "CTuningManager",
-    "CUserDisplay",
-    "CVehicleAILodManager",
-    "CVehicleChaseDirector",
-    "CVehicleCombatAvoidanceArea",
-    "CVehicleDeformation",
-    "CVehicleMetadataMgr",
-    "CVehicleModelInfo",
-    "CVehiclePopulation",
-    "CVehiclePopulation::ResetPerFrameScript",
-    "CVehiclePopulation::ResetPerFrameScriptedMultipiers",
-    "CVehicleRecordingMgr",
-    "CVehicleVariationInstance",
-    "CVisualEffects",
-    "CWarpManager",
-    "CWaypointRecording",
-    "CWeaponManager",
-    "CWitnessInformationManager",
-    "CWorldPoints",
-    "CZonedAssetManager",
-    "Common",
-    "CreateFinalScreenRenderPhaseList",
-    "Credits",
-    "CutSceneManager",
-    "CutSceneManagerWrapper",
-    "FacialClipSetGroupManager",
-    "FireManager",
-    "FirstPersonProp",
-    "FirstPersonPropCam",
-    "Game",
-    "GenericGameStoragePhotoGallery",
-    "INSTANCESTORE",
-    "ImposedTxdCleanup",
-    "InitSystem",
-    "Kick",
-    "LightEntityMgr",
-    "Lights",
-    "MeshBlendManager",
-    "Misc",
-    "NewHud",
-    "Occlusion",
-    "PauseMenu",
-    "Ped",
-    "PedHeadShotManager",
-    "PedModelInfo",
-    "PedPopulation",
-    "PlantsMgr::UpdateBegin",
-    "PlantsMgr::UpdateEnd",
-    "Population",
-    "PostFX",
-    "PostFx",
-    "Pre-vis",
-    "Prioritized",
-    "Proc",
-    "ProcessAfterCameraUpdate",
-    "ProcessAfterMovement",
-    "ProcessPedsEarlyAfterCameraUpdate",
-    "Render",
-    "ResetSceneLights",
-    "Run",
-    "Script",
-    "ScriptHud",
-    "ShaderLib::Update",
-    "Situational",
-    "SocialClubMenu",
-    "Streaming",
-    "UI3DDrawManager",
-    "UIWorldIconManager",
-    "Update",
-    "VehPopulation",
-    "VideoPlayback",
-    "VideoPlaybackThumbnailManager",
-    "VideoPlaybackThumbnails",
-    "Viewport",
-    "ViewportSystemInit",
-    "ViewportSystemInitLevel",
-    "ViewportSystemShutdown",
-    "ViewportSystemShutdownLevel",
-    "Visibility",
-    "Visual",
-    "WarningScreen",
-    "Water",
-    "WaterHeightSim",
-    "World",
-    "audNorthAudioEngine",
-    "audNorthAudioEngineDLC",
-    "cStoreScreenMgr",
-    "camManager",
-    "decorators",
-    "fwAnimDirector",
-    "fwClipSetManager",
-    "fwClothStore",
-    "fwDrawawableStoreWrapper",
-    "fwDwdStore",
-    "fwDwdStoreWrapper",
-    "fwExpressionSetManager",
-    "fwFacialClipSetGroupManager",
-    "fwFragmentStoreWrapper",
-    "fwMapTypesStore",
-    "fwMetaDataStore",
-    "fwTimer",
-    "fwTxdStore",
-    "perfClearingHouse",
-    "strStreamingEngine::SubmitDeferredAsyncPlacementRequests",
-    "CAnimSceneManager",
-    "CTextInputBox",
-    "CMultiplayerChat",
-    "CCreditsText",
-    "CReplayMgr",
-    "CReplayCoordinator",
-    "CMousePointer",
-    "CVideoEditorUI",
-    "CVideoEditorInterface",
-    "VideoRecording",
-    "WatermarkRenderer",
-];
\ No newline at end of file
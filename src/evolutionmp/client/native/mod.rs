@@ -1,21 +1,37 @@
 
 use std::collections::HashMap;
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
 use std::os::raw::c_char;
-use std::sync::atomic::AtomicU64;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 
-use cgmath::{Deg, Euler, Quaternion, Vector2, Vector3};
+use cgmath::Vector3;
+use winapi::um::libloaderapi::GetModuleHandleA;
+use winapi::um::verrsrc::VS_FIXEDFILEINFO;
+use winapi::um::winver::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW};
+use wio::wide::ToWide;
 
 
 use crate::client::native::pool::{CEntity, Native};
-use crate::client::pattern::{MemoryRegion, RageBox};
-use crate::game::{Handle, Rgb, Rgba};
+use crate::client::pattern::{MemoryRegion, Pattern, PatternSet, RageBox};
 use crate::game::ui::CursorSprite;
-use crate::hash::Hash;
-use crate::native::pool::Handleable;
+use crate::hash::dictionary::{self, HASHES_DIR};
+use crate::patch::PatchMemory;
+use crate::patches::ProcessMemory;
+use crate::signature::{CACHE_FILE, Fingerprint, Image, PatternCache, PatternReport, Resolution};
+use crate::backend::NativeBackend;
+use crate::crossmap::{Crossmap, CROSSMAP_FILE};
+use crate::manifest::{Manifest, ManifestSection, MANIFEST_FILE, Target};
+use crate::nativetable::{self, Registration, TableError};
+use crate::signature::bindings::{Binding, BINDINGS, Step};
+use crate::registry::Registry;
+use crate::version::{Build, find_build, GameVersion, VersionError};
+
+pub use crate::stack::{NativeCallContext, NativeFunction, NativeStackReader, NativeStackValue, NativeStackWriter};
 
 
 
@@ -23,7 +39,6 @@ use crate::native::pool::Handleable;
 
 pub mod vehicle;
 pub mod pool;
-pub mod object_hashes;
 pub mod fs;
 pub mod alloc;
 pub mod script;
@@ -31,7 +46,8 @@ pub mod streaming;
 pub mod grc;
 pub mod assets;
 pub mod init_fns;
-pub mod vtables;
+pub mod hooks;
+pub mod dispatch;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -71,26 +87,11 @@ impl<T> std::ops::DerefMut for ThreadSafe<T> {
 
 #[macro_export]
 macro_rules! bind_fn_detour {
-    ($name:ident,$pattern:literal,$offset:literal,$detour:path,($($arg:ty),*) -> $ret:ty) => {
+    ($name:ident,$detour:path,($($arg:ty),*) -> $ret:ty) => {
         lazy_static::lazy_static! {
             pub static ref $name: extern fn($($arg),*) -> $ret = unsafe {
-                let d = $crate::mem!($pattern)
-                .expect(concat!("failed to find call for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).detour($detour as _);
-                std::mem::transmute(d)
-            };
-        }
-    };
-}
-
-#[macro_export]
-macro_rules! bind_fn_detour_ip {
-    ($name:ident,$pattern:literal,$offset:literal,$detour:path,($($arg:ty),*) -> $ret:ty) => {
-        lazy_static::lazy_static! {
-            pub static ref $name: extern fn($($arg),*) -> $ret = unsafe {
-                let d = $crate::mem!($pattern)
-                .expect(concat!("failed to find call for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).detour_ip($detour as _);
+                let d = $crate::native::bind_or_panic(&$crate::signature::bindings::$name)
+                    .detour(stringify!($name), $detour as _);
                 std::mem::transmute(d)
             };
         }
@@ -136,43 +137,20 @@ macro_rules! class {
 
 #[macro_export]
 macro_rules! mem {
-    ($pat:literal) => {{
-        let mut cache = crate::native::PATTERN_CACHE.lock().expect("mutex poisoned");
-        if let Some(region) = cache.get($pat).cloned() {
-            region
-        } else {
-            let region = $crate::pattern::Pattern::from($pat).find();
-            cache.insert($pat, region.clone());
-            region
-        }
-    }};
-}
-
-#[macro_export]
-macro_rules! bind_fn {
-    ($name:ident,$pattern:literal,$offset:literal,($($arg:ty),*) -> $ret:ty) => {
-        lazy_static::lazy_static! {
-            pub static ref $name: extern fn($($arg),*) -> $ret = unsafe {
-                let ptr = $crate::mem!($pattern)
-                .expect(concat!("failed to bind call for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).as_ptr();
-                std::mem::transmute(ptr)
-            };
-        }
+    ($name:ident) => {
+        $crate::native::bind(&$crate::signature::bindings::$name)
+    };
+    ($pat:literal) => {
+        $crate::native::find_pattern($pat)
     };
 }
 
 #[macro_export]
-macro_rules! bind_fn_ip {
-    ($name:ident,$pattern:literal,$offset:expr,($($arg:ty),*) -> $ret:ty) => {
-        bind_fn_ip!($name,$pattern,$offset,($($arg),*) -> $ret,4);
-    };
-    ($name:ident,$pattern:literal,$offset:expr,($($arg:ty),*) -> $ret:ty,$ptr_len:literal) => {
+macro_rules! bind_fn {
+    ($name:ident,($($arg:ty),*) -> $ret:ty) => {
         lazy_static::lazy_static! {
             pub static ref $name: extern fn($($arg),*) -> $ret = unsafe {
-                let ptr = $crate::mem!($pattern)
-                    .expect(concat!("failed to bind call for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).read_ptr($ptr_len).as_ptr();
+                let ptr = $crate::native::bind_or_panic(&$crate::signature::bindings::$name).as_ptr();
                 std::mem::transmute(ptr)
             };
         }
@@ -181,15 +159,12 @@ macro_rules! bind_fn_ip {
 
 #[macro_export]
 macro_rules! bind_inner_field {
-    ($host:ident,$pattern:literal,$(($pat_offset:literal, $name:ident,$ty:ty,$offset:literal)),*) => {
+    ($host:ident,$binding:ident,$(($name:ident,$ty:ty,$offset:literal)),*) => {
         lazy_static::lazy_static! {
             $(
                 pub static ref $name: crate::native::NativeField<$host, $ty> = {
                     let pattern = unsafe {
-                        $crate::mem!($pattern)
-                            .expect(concat!("failed to find field pattern `", $pattern, "` for ", stringify!($host)))
-                            .offset($pat_offset)
-                            .get_box::<i32>()
+                        $crate::native::bind_or_panic(&$crate::signature::bindings::$binding).get_box::<i32>()
                     };
                     let offset = *pattern + $offset;
                     trace!("Got offset for {}.{}: 0x{:X} (0x{:X} + {})", stringify!($host), stringify!($name), offset, *pattern, $offset);
@@ -202,45 +177,294 @@ macro_rules! bind_inner_field {
 
 #[macro_export]
 macro_rules! bind_field {
-    ($name:ident,$pattern:literal,$offset:literal,$ty:ty) => {
+    ($name:ident,$ty:ty) => {
         lazy_static::lazy_static! {
             pub static ref $name: crate::pattern::RageBox<$ty> = unsafe {
-                $crate::mem!($pattern)
-                .expect(concat!("failed to bind field for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).get_box()
+                $crate::native::bind_or_panic(&$crate::signature::bindings::$name).get_box()
             };
         }
     };
 }
 
-#[macro_export]
-macro_rules! bind_field_ip {
-    ($name:ident,$pattern:literal,$offset:expr,$ty:ty) => {
-        bind_field_ip!($name,$pattern,$offset,$ty,4);
-    };
-    ($name:ident,$pattern:literal,$offset:expr,$ty:ty,$ptr_len:literal) => {
-        lazy_static::lazy_static! {
-            pub static ref $name: crate::pattern::RageBox<$ty> = unsafe {
-                $crate::mem!($pattern)
-                    .expect(concat!("failed to bind field for ", stringify!($name), " at ", $pattern))
-                    .offset($offset).read_ptr($ptr_len).get_box()
-            };
+lazy_static! {
+    pub static ref NATIVES: Natives = Natives::new();
+    pub static ref PATTERN_CACHE: Mutex<HashMap<&'static str, Resolution<MemoryRegion>>> = Mutex::new(HashMap::new());
+    pub static ref GAME_VERSION: GameVersion = detect_version().unwrap_or_else(|e| panic!("{}", e));
+    pub static ref BUILD: &'static Build = find_build(&GAME_VERSION).unwrap_or_else(|e| panic!("{}", e));
+    pub static ref MANIFEST: ManifestSection = load_manifest();
+    static ref PERSISTENT_CACHE: Mutex<Option<PersistentCache>> = Mutex::new(load_pattern_cache());
+}
+
+/// Pattern matches kept across launches as RVAs into the game executable.
+struct PersistentCache {
+    base: u64,
+    size: u64,
+    cache: PatternCache,
+}
+
+impl PersistentCache {
+    fn get(&self, pattern: &str) -> Option<Resolution<MemoryRegion>> {
+        let resolution = self.cache.get(pattern)?.clone();
+        Some(resolution.map(|rva| MemoryRegion {
+            base: (self.base + rva) as *mut u8,
+            size: self.size.saturating_sub(rva) as usize,
+        }))
+    }
+
+    //Matches in other modules can't be expressed as RVAs of the executable and are never persisted
+    fn insert(&mut self, pattern: &str, resolution: &Resolution<MemoryRegion>) {
+        let rvas = resolution.as_ref().map(|r| (r.base as u64).wrapping_sub(self.base));
+        if rvas.clone().into_hits().iter().all(|rva| *rva < self.size) {
+            self.cache.insert(pattern, rvas);
+        }
+    }
+}
+
+fn load_pattern_cache() -> Option<PersistentCache> {
+    let base = unsafe { GetModuleHandleA(std::ptr::null()) } as u64;
+    let mut headers = vec![0; 0x1000];
+    if !ProcessMemory.read(base, &mut headers) {
+        return None;
+    }
+    let image = match Image::from_dump(headers) {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Unable to read game image headers: {}", e);
+            return None;
         }
     };
+    let mut cache = PatternCache::load(crate::launcher_dir().join(CACHE_FILE), Fingerprint::of(&image));
+    let size = image.size_of_image as u64;
+    //Hits outside the image would read memory of another module
+    let read = |rva: u64, buf: &mut [u8]| rva.checked_add(buf.len() as u64).map_or(false, |end| end <= size)
+        && ProcessMemory.read(base + rva, buf);
+    for pattern in cache.retain_valid(read) {
+        warn!("Dropping stale cached match of `{}`", pattern);
+    }
+    info!("Reusing {} cached patterns for image {}", cache.len(), cache.fingerprint());
+    Some(PersistentCache { base, size, cache })
+}
+
+/// Writes pattern matches found since the last save to the cache file.
+pub fn save_pattern_cache() {
+    let mut persistent = PERSISTENT_CACHE.lock().expect("mutex poisoned");
+    if let Some(p) = persistent.as_mut().filter(|p| p.cache.is_dirty()) {
+        let path = crate::launcher_dir().join(CACHE_FILE);
+        if let Err(e) = p.cache.save(&path) {
+            error!("Unable to save {}: {}", path.display(), e);
+        }
+    }
 }
 
-lazy_static! {
-    pub static ref OBJECT_HASHES: HashMap<i32, &'static str> = object_hashes::HASHES.iter().cloned().collect::<_>();
-    pub static ref NATIVES: Natives = Natives::new();
-    pub static ref PATTERN_CACHE: Mutex<HashMap<&'static str, Option<MemoryRegion>>> = Mutex::new(HashMap::new());
+/// Rebuilds the hash dictionary from the built-in tables and the name lists in the launcher directory.
+pub fn reload_hashes() {
+    let mut dictionary = dictionary::builtin();
+    for e in dictionary.load_dir(crate::launcher_dir().join(HASHES_DIR)) {
+        warn!("Ignoring hash list: {}", e);
+    }
+    info!("Loaded {} hash names", dictionary.len());
+    dictionary::replace(dictionary);
 }
 
-bind_fn!(SET_VECTOR_RESULTS, "83 79 18 ? 48 8B D1 74 4A FF 4A 18", 0, (&mut NativeCallContext) -> ());
-bind_fn!(GET_SCRIPT_ENTITY, "44 8B C1 49 8B 41 08 41 C1 F8 08 41 38 0C 00", -12, (u32) -> RageBox<CEntity>);
+/// Reads the version of the running executable, falling back to the one the installer registered.
+pub fn detect_version() -> Result<GameVersion, VersionError> {
+    let exe = std::env::current_exe().map_err(|_| VersionError::Undetected)?;
+    if let Some(version) = unsafe { file_version(&exe) } {
+        return Ok(version);
+    }
+    let registry = Registry::read().ok_or(VersionError::Undetected)?;
+    registry.patch_version().or(registry.game_version())
+        .ok_or(VersionError::Undetected)?
+        .parse()
+}
+
+unsafe fn file_version(path: &Path) -> Option<GameVersion> {
+    let path = path.as_os_str().to_wide_null();
+    let size = GetFileVersionInfoSizeW(path.as_ptr(), std::ptr::null_mut());
+    if size == 0 {
+        return None;
+    }
+    let mut data = vec![0u8; size as usize];
+    if GetFileVersionInfoW(path.as_ptr(), 0, size, data.as_mut_ptr() as _) == 0 {
+        return None;
+    }
+    let root = "\\".to_wide_null();
+    let mut info = std::ptr::null_mut();
+    let mut len = 0;
+    if VerQueryValueW(data.as_ptr() as _, root.as_ptr(), &mut info, &mut len) == 0 || info.is_null() {
+        return None;
+    }
+    let info = &*(info as *const VS_FIXEDFILEINFO);
+    Some(GameVersion::new(
+        (info.dwFileVersionMS >> 16) as u16, info.dwFileVersionMS as u16,
+        (info.dwFileVersionLS >> 16) as u16, info.dwFileVersionLS as u16
+    ))
+}
 
-bind_field!(EXPANDED_RADAR, "33 C0 0F 57 C0 ? 0D", 7, bool);
-bind_field!(REVEAL_FULL_MAP, "33 C0 0F 57 C0 ? 0D", 30, bool);
-bind_field!(CURSOR_SPRITE, "74 11 8B D1 48 8D 0D ? ? ? ? 45 33 C0", 0, CursorSprite);
+fn load_manifest() -> ManifestSection {
+    let section = load_manifest_section();
+    if let Some(strict) = section.strict_patterns {
+        STRICT_PATTERNS.store(strict, Ordering::SeqCst);
+    }
+    section
+}
+
+fn load_manifest_section() -> ManifestSection {
+    let path = crate::launcher_dir().join(MANIFEST_FILE);
+    match Manifest::load(&path) {
+        Ok(mut manifest) => {
+            for e in manifest.prune_invalid() {
+                warn!("Ignoring manifest entry: {}", e);
+            }
+            let mut section = BUILD.overrides();
+            section.merge(&manifest.select(Some(&GAME_VERSION.build.to_string())));
+            section
+        }
+        Err(e) => {
+            error!("Unable to load {}: {}", path.display(), e);
+            BUILD.overrides()
+        }
+    }
+}
+
+fn load_crossmap() -> Crossmap {
+    let path = crate::launcher_dir().join(CROSSMAP_FILE);
+    match Crossmap::load(&path) {
+        Ok(Some(crossmap)) if crossmap.build != GAME_VERSION.build => {
+            warn!("{} is made for build {}, using the built-in crossmap", path.display(), crossmap.build);
+            Crossmap::builtin()
+        }
+        Ok(Some(mut crossmap)) => {
+            for e in crossmap.prune_invalid() {
+                warn!("Ignoring crossmap entry: {}", e);
+            }
+            info!("Loaded {} native mappings from {}", crossmap.len(), path.display());
+            crossmap
+        }
+        Ok(None) => Crossmap::builtin(),
+        Err(e) => {
+            error!("Unable to load {}: {}", path.display(), e);
+            Crossmap::builtin()
+        }
+    }
+}
+
+/// When set, patterns with more than one match are refused instead of binding the lowest hit.
+/// Set from `strict_patterns` in the manifest or from the console.
+pub static STRICT_PATTERNS: AtomicBool = AtomicBool::new(false);
+
+pub unsafe fn find_pattern(pattern: &'static str) -> Option<MemoryRegion> {
+    let compiled = Pattern::from(pattern);
+    let mut cache = PATTERN_CACHE.lock().expect("mutex poisoned");
+    let resolution = cache.entry(pattern)
+        .or_insert_with(|| resolve_cached(pattern, &compiled));
+    select_match(pattern, resolution.clone()).map(|region| region.target(&compiled))
+}
+
+unsafe fn resolve_cached(pattern: &str, compiled: &Pattern) -> Resolution<MemoryRegion> {
+    let mut persistent = PERSISTENT_CACHE.lock().expect("mutex poisoned");
+    if let Some(resolution) = persistent.as_ref().and_then(|p| p.get(pattern)) {
+        return resolution;
+    }
+    let resolution = compiled.resolve();
+    if let Some(p) = persistent.as_mut() {
+        p.insert(pattern, &resolution);
+    }
+    resolution
+}
+
+/// Resolves every pattern that is not cached yet in a single pass over the game image.
+pub unsafe fn prefetch<I>(patterns: I) where I: IntoIterator<Item=&'static str> {
+    let mut cache = PATTERN_CACHE.lock().expect("mutex poisoned");
+    let mut persistent = PERSISTENT_CACHE.lock().expect("mutex poisoned");
+    let mut pending = patterns.into_iter()
+        .filter(|p| !cache.contains_key(p))
+        .collect::<Vec<_>>();
+    pending.sort();
+    pending.dedup();
+    pending.retain(|p| match persistent.as_ref().and_then(|c| c.get(p)) {
+        Some(resolution) => {
+            cache.insert(*p, resolution);
+            false
+        }
+        None => true
+    });
+    let set = pending.iter().collect::<PatternSet>();
+    for (pattern, resolution) in pending.into_iter().zip(set.resolve()) {
+        if let Some(p) = persistent.as_mut() {
+            p.insert(pattern, &resolution);
+        }
+        cache.insert(pattern, resolution);
+    }
+}
+
+/// Resolves a binding, preferring the manifest over the compiled-in pattern and steps.
+pub unsafe fn bind(binding: &'static Binding) -> Option<MemoryRegion> {
+    locate(&MANIFEST.binding(binding))
+}
+
+pub unsafe fn locate(target: &Target<'static>) -> Option<MemoryRegion> {
+    let region = find_pattern(target.pattern)?;
+    let region = target.steps.iter().fold(region, |region, step| match *step {
+        Step::Add(offset) => region.offset(offset),
+        Step::Relative(len) => region.read_ptr(len as usize),
+        Step::Call => region.add(1).read_ptr(4)
+    });
+    Some(region)
+}
+
+pub unsafe fn bind_or_panic(binding: &'static Binding) -> MemoryRegion {
+    bind(binding).unwrap_or_else(|| panic!("failed to bind {}", binding))
+}
+
+/// Resolves the patterns of all registered bindings up front.
+pub unsafe fn prefetch_bindings() {
+    prefetch(BINDINGS.iter().map(|b| MANIFEST.binding(b).pattern));
+    save_pattern_cache();
+}
+
+fn select_match(pattern: &str, resolution: Resolution<MemoryRegion>) -> Option<MemoryRegion> {
+    match resolution {
+        Resolution::Ambiguous(_) if STRICT_PATTERNS.load(Ordering::SeqCst) => {
+            error!("Refusing to bind {}", make_report(pattern, &resolution));
+            None
+        }
+        Resolution::Ambiguous(_) => {
+            warn!("Binding lowest match of {}", make_report(pattern, &resolution));
+            resolution.first()
+        }
+        other => other.first()
+    }
+}
+
+fn make_report(pattern: &str, resolution: &Resolution<MemoryRegion>) -> PatternReport {
+    PatternReport::new(pattern, resolution.as_ref().map(|r| r.base as u64))
+}
+
+pub fn pattern_reports() -> Vec<PatternReport> {
+    let cache = PATTERN_CACHE.lock().expect("mutex poisoned");
+    let mut reports = cache.iter()
+        .map(|(pattern, resolution)| make_report(pattern, resolution))
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| a.pattern.cmp(&b.pattern));
+    reports
+}
+
+pub fn log_pattern_reports() {
+    let reports = pattern_reports();
+    let resolved = reports.iter().filter(|r| r.is_ok()).count();
+    info!("Resolved {} out of {} patterns", resolved, reports.len());
+    for report in reports.iter().filter(|r| !r.is_ok()) {
+        warn!("Pattern {}", report);
+    }
+}
+
+bind_fn!(SET_VECTOR_RESULTS, (&mut NativeCallContext) -> ());
+bind_fn!(GET_SCRIPT_ENTITY, (u32) -> RageBox<CEntity>);
+
+bind_field!(EXPANDED_RADAR, bool);
+bind_field!(REVEAL_FULL_MAP, bool);
+bind_field!(CURSOR_SPRITE, CursorSprite);
 
 pub(crate) fn hook() {
     alloc::hook();
@@ -261,6 +485,7 @@ pub(crate) fn hook() {
 pub(crate) fn init() {
     info!("Initializing natives...");
     lazy_static::initialize(&NATIVES);
+    crate::backend::set_default(TableBackend);
     vehicle::init();
     crate::events::init();
     fs::init();
@@ -271,7 +496,7 @@ pub fn get_handler_opt(hash: u64) -> Option<NativeFunction> {
 }
 
 pub fn get_handler(hash: u64) -> NativeFunction {
-    get_handler_opt(hash).expect(&format!("Missing native handler for 0x{:016X}", hash))
+    get_handler_opt(hash).unwrap_or_else(|| panic!("Missing native handler for {}", crate::nativedb::describe(hash)))
 }
 
 #[repr(C, packed(1))]
@@ -283,15 +508,14 @@ struct PtrXorU64 {
 impl PtrXorU64 {
     fn get(&self) -> u64 {
         let addr = self as *const Self as u64;
-        let mask = (addr ^ self.next) as u32 as u64;
-        let value = ((mask << 32) | mask) ^ self.prev;
-        (value) as _
+        nativetable::decode_u64(addr, self.prev, self.next)
     }
 
     fn set(&mut self, value: u64) {
         let addr = self as *const Self as u64;
-        self.prev = (addr << 32) ^ (addr as u32 as u64) ^ value;
-        self.next = 0;
+        let (prev, next) = nativetable::encode_u64(addr, value, 0);
+        self.prev = prev;
+        self.next = next;
     }
 }
 
@@ -304,7 +528,7 @@ struct PtrXorU32 {
 impl PtrXorU32 {
     fn get(&self) -> u32 {
         let addr = self as *const Self as u64;
-        addr as u32 ^ self.next ^ self.prev
+        nativetable::decode_u32(addr, self.prev, self.next)
     }
 }
 
@@ -329,6 +553,21 @@ impl NativeTable {
         let group = &self.groups[(hash & 0xFF) as usize];
         group.find(hash)
     }
+
+    /// Swaps the handler of a native, returning the one it replaced.
+    pub unsafe fn replace_handler(&self, hash: u64, handler: NativeFunction) -> Option<NativeFunction> {
+        let mut group = Some(&*self.groups[(hash & 0xFF) as usize]);
+        while let Some(current) = group {
+            if let Some(index) = (0..current.len()).find(|i| current.get_hash(*i) == hash) {
+                let entry = (std::ptr::addr_of!(current.handlers) as *mut NativeFunction).add(index);
+                let original = entry.read_unaligned();
+                entry.write_unaligned(handler);
+                return Some(original);
+            }
+            group = current.get_next_group();
+        }
+        None
+    }
 }
 
 impl NativeGroup {
@@ -391,183 +630,8 @@ impl<'a> Iterator for NativeGroupIterator<'a> {
     }
 }
 
-pub struct NativeStackReader<'a> {
-    stack: &'a [u64],
-    pos: usize,
-}
-
-impl<'a> NativeStackReader<'a> {
-    pub fn new(stack: &'a [u64]) -> NativeStackReader<'a> {
-        NativeStackReader {
-            stack,
-            pos: 0,
-        }
-    }
-
-    pub fn len(&self) -> usize {
-        self.stack.len()
-    }
-
-    pub fn pos(&self) -> usize {
-        self.pos
-    }
-
-    pub fn is_null(&self) -> bool {
-        self.stack[self.pos] == 0
-    }
-
-    pub fn read_u64(&mut self) -> u64 {
-        let pos = self.pos;
-        self.pos += 1;
-        self.stack[pos]
-    }
-
-    pub unsafe fn read_ptr<T>(&mut self) -> T where T: Sized {
-        let pos = self.pos;
-        self.pos += 1;
-        self.stack[pos..].as_ptr().cast::<T>().read()
-    }
-
-    pub fn read<T>(&mut self) -> T where T: NativeStackValue {
-        T::read_from_stack(self)
-    }
-
-    pub fn read_option<T>(&mut self) -> Option<T> where T: NativeStackValue {
-        if self.is_null() {
-            self.pos += 1;
-            None
-        } else {
-            Some(self.read())
-        }
-    }
-}
-
-pub struct NativeStackWriter<'a> {
-    stack: &'a mut [u64],
-    pos: usize,
-}
-
-impl<'a> NativeStackWriter<'a> {
-    pub fn new(stack: &'a mut [u64]) -> NativeStackWriter<'a> {
-        NativeStackWriter {
-            stack,
-            pos: 0,
-        }
-    }
-
-    pub fn len(&self) -> usize {
-        self.stack.len()
-    }
-
-    pub fn pos(&self) -> usize {
-        self.pos
-    }
-
-    pub fn write_u64(&mut self, raw: u64) -> usize {
-        self.stack[self.pos] = raw;
-        self.pos += 1;
-        1
-    }
-
-    pub unsafe fn write_ptr<T>(&mut self, value: T) -> usize where T: Sized {
-        let pos = self.pos;
-        self.pos += 1;
-        self.stack[pos..].as_mut_ptr().cast::<T>().write(value);
-        1
-    }
-
-    pub fn write<T>(&mut self, value: T) -> usize where T: NativeStackValue {
-        let pos = self.pos;
-        value.write_to_stack(self);
-        self.pos - pos
-    }
-
-    pub fn write_option<T>(&mut self, value: Option<T>) -> usize where T: NativeStackValue {
-        if let Some(value) = value {
-            self.write(value)
-        } else {
-            self.write_u64(0)
-        }
-    }
-}
-
-pub trait NativeStackValue {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self where Self: Sized {
-        let size = std::mem::size_of::<Self>();
-        if size <= 8 {
-            unsafe {
-                stack.read_ptr::<Self>()
-            }
-        } else {
-            panic!(
-                "Cannot read value of type `{}` from stack as it exceeds default reader's size limits ({} bytes)",
-                std::any::type_name::<Self>(),
-                size
-            )
-        }
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) where Self: Sized {
-        let size = std::mem::size_of::<Self>();
-        if size <= 8 {
-            unsafe {
-                stack.write_ptr(self);
-            }
-        } else {
-            panic!(
-                "Cannot write value of type `{}` to stack as it exceeds default writer's size limits ({} bytes)",
-                std::any::type_name::<Self>(),
-                size
-            )
-        }
-    }
-}
-
-#[repr(C)]
-pub struct NativeCallContext<'arg, 'ret> {
-    returns: &'ret mut [u64; 3],
-    arg_count: u32,
-    args: &'arg mut [u64; 32],
-    data_count: u32,
-    data: [u32; 48],
-}
-
-impl<'arg, 'ret> NativeCallContext<'arg, 'ret> {
-    pub fn new(args: &'arg mut [u64; 32], returns: &'ret mut [u64; 3], arg_count: u32) -> NativeCallContext<'arg, 'ret> {
-        NativeCallContext {
-            returns,
-            arg_count,
-            args,
-            data_count: 0,
-            data: [0; 48],
-        }
-    }
-
-    pub fn get_args(&self) -> NativeStackReader {
-        NativeStackReader::new(&*self.args)
-    }
 
-    pub fn push_arg<A>(&mut self, arg: A) -> usize where A: NativeStackValue {
-        let i = self.arg_count as usize;
-        let mut writer = NativeStackWriter::new(&mut self.args[i..]);
-        let len = writer.write(arg);
-        self.arg_count += len as u32;
-        len
-    }
-
-    pub fn get_result<R>(&mut self) -> R where R: NativeStackValue {
-        SET_VECTOR_RESULTS(self);
-        let mut reader = NativeStackReader::new(&*self.returns);
-        reader.read()
-    }
-
-    pub fn set_result<R>(&mut self, result: R) -> usize where R: NativeStackValue {
-        let mut writer = NativeStackWriter::new(&mut *self.returns);
-        writer.write(result)
-    }
-}
-
-pub type NativeFunction = extern fn(*mut NativeCallContext);
+bind_field!(NATIVE_TABLE, NativeTable);
 
 pub struct Natives {
     mappings: HashMap<u64, u64>,
@@ -577,9 +641,8 @@ pub struct Natives {
 
 impl Natives {
     pub fn new() -> Natives {
-        bind_field_ip!(NATIVE_TABLE, "76 32 48 8B 53 40", 9, NativeTable);
-
-        let mappings = crate::mappings::MAPPINGS.iter().cloned().collect::<HashMap<_, _>>();
+        let crossmap = load_crossmap();
+        let mappings = crossmap.to_map();
         let mut handlers = HashMap::with_capacity(mappings.len());
 
         for group in NATIVE_TABLE.groups.iter() {
@@ -589,6 +652,14 @@ impl Natives {
             }
         }
 
+        let missing = crossmap.missing_targets(|hash| handlers.contains_key(&hash));
+        if !missing.is_empty() {
+            warn!("{} mapped natives are missing from the native table", missing.len());
+            for hash in missing {
+                debug!("Missing native 0x{:016X}", hash);
+            }
+        }
+
         let mut mapped_handlers = handlers.clone();
         for (from, to) in mappings.iter() {
             if let Some(handler) = handlers.get(to).cloned() {
@@ -603,24 +674,98 @@ impl Natives {
         //let hash = self.mappings.get(&hash).cloned().unwrap_or(hash);
         self.mapped_handlers.get(&hash).cloned()
     }
+
+    /// Hash of a native in the game's native table.
+    pub fn resolve(&self, hash: u64) -> u64 {
+        self.mappings.get(&hash).cloned().unwrap_or(hash)
+    }
+}
+
+/// Registrations of the game's native table, read the way the offline decoder reads a dump.
+pub fn native_registrations() -> Result<Vec<Registration>, TableError> {
+    let table = &**NATIVE_TABLE as *const NativeTable as u64;
+    nativetable::decode(&ProcessMemory, table)
+}
+
+/// Writes every native of the game's native table with the RVA of its handler, returning how many there were.
+pub fn dump_native_table<P>(path: P) -> Result<usize, Box<dyn std::error::Error>> where P: AsRef<Path> {
+    let base = unsafe { GetModuleHandleA(std::ptr::null()) } as u64;
+    let mut headers = vec![0; 0x1000];
+    if !ProcessMemory.read(base, &mut headers) {
+        return Err(Box::new(TableError::Unreadable(base)));
+    }
+    let image = Image::from_dump(headers)?;
+    let registrations = native_registrations()?;
+    std::fs::write(path, nativetable::to_text(&registrations, base, image.size_of_image as u64))?;
+    Ok(registrations.len())
+}
+
+/// Natives of the game's native table, through their hooks.
+pub struct TableBackend;
+
+impl NativeBackend for TableBackend {
+    fn contains(&self, hash: u64) -> bool {
+        get_handler_opt(hash).is_some()
+    }
+
+    fn call(&self, hash: u64, context: &mut NativeCallContext) -> bool {
+        match get_handler_opt(hash) {
+            Some(handler) => {
+                call_handler(hash, handler, context);
+                true
+            }
+            None => false
+        }
+    }
+}
+
+/// Calls a handler of the native table through the hooks of its native.
+pub fn call_handler(hash: u64, handler: NativeFunction, context: &mut NativeCallContext) {
+    hooks::call(hash, handler, context);
+    SET_VECTOR_RESULTS(context); //Flush all &mut NativeVector3 args
 }
 
 pub(crate) static CURRENT_NATIVE: AtomicU64 = AtomicU64::new(0);
 
+/// Whether native calls should be timed, for the tracer or the profiler.
+pub fn timing_enabled() -> bool {
+    crate::trace::enabled() || crate::profile::enabled()
+}
+
+/// Hands a finished call to the tracer and the profiler, with the script it was made from.
+pub fn record_call(hash: u64, context: &NativeCallContext, duration: Duration) {
+    let script = unsafe { script::get_active_thread().as_ref() }.map_or(0, |t| t.context.script_hash.0);
+    crate::trace::record(hash, script, context.args(), context.returns(), duration);
+    crate::profile::record(hash, script, duration);
+}
+
 #[macro_export]
 macro_rules! invoke {
     ($ret: ty, $hash: literal $(, $arg: expr)*) => {{
         lazy_static! {
-            static ref HANDLER: $crate::native::NativeFunction = $crate::native::get_handler($hash);
+            static ref HANDLER: Option<$crate::native::NativeFunction> = $crate::native::get_handler_opt($hash);
         }
+        //Frees the strings marshalled for the call once it returned
+        let _frame = $crate::arena::frame();
         let mut args = [0; 32];
         let mut result = [0; 3];
         let mut context = $crate::native::NativeCallContext::new(&mut args, &mut result, 0);
         $(context.push_arg($arg);)*
         use std::sync::atomic::Ordering;
         $crate::native::CURRENT_NATIVE.store($hash, Ordering::SeqCst);
-        HANDLER(&mut context);
+        let start = if $crate::native::timing_enabled() { Some(std::time::Instant::now()) } else { None };
+        //The table handler is only looked up once per call site, backends set by tests going first
+        if $crate::backend::is_overridden() {
+            $crate::backend::call($hash, &mut context);
+        } else if let Some(handler) = *HANDLER {
+            $crate::native::call_handler($hash, handler, &mut context);
+        } else {
+            $crate::backend::call($hash, &mut context);
+        }
         $crate::native::CURRENT_NATIVE.store(0, Ordering::SeqCst);
+        if let Some(start) = start {
+            $crate::native::record_call($hash, &context, start.elapsed());
+        }
         context.get_result::<$ret>()
     }};
 }
@@ -636,161 +781,6 @@ macro_rules! invoke_option {
     };
 }
 
-impl NativeStackValue for &str {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        let c_str = unsafe { CStr::from_ptr(stack.read_u64() as *mut _) };
-        c_str.to_str().expect(&format!("Failed to read C string: {:?}", c_str))
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        let native = CString::new(self).expect("Failed to write C string");
-        stack.write_u64(native.into_raw() as u64);
-    }
-}
-
-impl NativeStackValue for String {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        <&str as NativeStackValue>::read_from_stack(stack).to_owned()
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        self.as_str().write_to_stack(stack);
-    }
-}
-
-impl NativeStackValue for Quaternion<f32> {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        let x = stack.read();
-        let y = stack.read();
-        let z = stack.read();
-        Euler::<Deg<f32>>::new(x, y, z).into()
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        let euler = Euler::from(self);
-        stack.write(Deg::from(euler.x));
-        stack.write(Deg::from(euler.y));
-        stack.write(Deg::from(euler.z));
-    }
-}
-
-impl<T> NativeStackValue for Vector3<T> where T: NativeStackValue + Copy + Clone {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        let x = stack.read();
-        let y = stack.read();
-        let z = stack.read();
-        Vector3::new(x, y, z)
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self.x);
-        stack.write(self.y);
-        stack.write(self.z);
-    }
-}
-
-impl<T> NativeStackValue for Vector2<T> where T: NativeStackValue + Copy + Clone {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        let x = stack.read();
-        let y = stack.read();
-        Vector2::new(x, y)
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self.x);
-        stack.write(self.y);
-    }
-}
-
-impl<H> NativeStackValue for H where H: Handleable + Sized {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        H::from_handle(stack.read::<Handle>()).expect("got zero handle")
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self.get_handle());
-    }
-}
-
-impl<H> NativeStackValue for Option<H> where H: Handleable + Sized {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        H::from_handle(stack.read::<Handle>())
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        let handle = self.expect("cannot pass invalid handle as native arg").get_handle();
-        stack.write(handle);
-    }
-}
-
-impl NativeStackValue for Rgba {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        panic!("Reading Rgba color from return stack is not possible")
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self.r as u32);
-        stack.write(self.g as u32);
-        stack.write(self.b as u32);
-        stack.write(self.a as u32);
-    }
-}
-
-impl NativeStackValue for Rgb {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        let r = stack.read::<u32>();
-        let g = stack.read::<u32>();
-        let b = stack.read::<u32>();
-        Rgb::new(r as u8, g as u8, b as u8)
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self.r as u32);
-        stack.write(self.g as u32);
-        stack.write(self.b as u32);
-    }
-}
-
-impl NativeStackValue for u8 {}
-
-impl NativeStackValue for &mut u8 {}
-
-impl NativeStackValue for i32 {}
-
-impl NativeStackValue for &mut i32 {}
-
-impl NativeStackValue for u32 {}
-
-impl NativeStackValue for &mut u32 {}
-
-impl NativeStackValue for f32 {}
-
-impl NativeStackValue for &mut f32 {}
-
-impl NativeStackValue for Deg<f32> {}
-
-impl NativeStackValue for &mut Deg<f32> {}
-
-impl NativeStackValue for bool {
-    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
-        stack.read::<u32>() as u8 == 1
-    }
-
-    fn write_to_stack(self, stack: &mut NativeStackWriter) {
-        stack.write(self as u32);
-    }
-}
-
-impl NativeStackValue for u64 {}
-
-impl NativeStackValue for &mut u64 {}
-
-impl NativeStackValue for () {}
-
-impl NativeStackValue for Hash {}
-
-impl NativeStackValue for &mut Hash {}
-
 pub struct NativeField<A, T> where A: Addressable, T: Sized {
     offset: i32,
     _ty_a: PhantomData<A>,
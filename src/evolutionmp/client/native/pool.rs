@@ -4,7 +4,7 @@ use cgmath::{Vector3, Zero};
 use jni_dynamic::JNIEnv;
 use jni_dynamic::objects::JClass;
 
-use crate::{bind_field_ip, bind_fn, bind_fn_ip};
+use crate::{bind_field, bind_fn};
 use crate::game::camera::Camera;
 use crate::game::entity::Entity;
 use crate::game::Handle;
@@ -14,6 +14,8 @@ use crate::game::prop::Prop;
 use crate::game::vehicle::Vehicle;
 use crate::native::ThreadSafe;
 
+pub use crate::handle::Handleable;
+
 pub enum CCamera {}
 pub enum CBlip {}
 pub enum CParticle {}
@@ -24,18 +26,18 @@ pub enum CPed {}
 pub enum CProp {}
 pub enum CPickup {}
 
-bind_fn_ip!(PARTICLE_ADDRESS, "74 21 48 8B 48 20 48 85 C9 74 18 48 8B D6 E8", -10, (Handle) -> *mut u8);
-bind_fn_ip!(ENTITY_ADDRESS, "E8 ? ? ? ? 48 8B D8 48 85 C0 74 2E 48 83 3D", 1, (Handle) -> *mut u8);
-bind_fn_ip!(PLAYER_ADDRESS, "B2 01 E8 ? ? ? ? 48 85 C0 74 1C 8A 88", 3, (Handle) -> *mut u8);
-bind_fn!(ENTITY_ADD_TO_POOL, "48 89 5C 24 ? 48 89 74 24 ? 57 48 83 EC 20 8B 15 ? ? ? ? 48 8B F9 48 83 C1 10 33 DB", 0, (*mut u8) -> Handle);
+bind_fn!(PARTICLE_ADDRESS, (Handle) -> *mut u8);
+bind_fn!(ENTITY_ADDRESS, (Handle) -> *mut u8);
+bind_fn!(PLAYER_ADDRESS, (Handle) -> *mut u8);
+bind_fn!(ENTITY_ADD_TO_POOL, (*mut u8) -> Handle);
 //bind_fn!(ENTITY_ADD_TO_POOL, "48 F7 F9 49 8B 48 08 48 63 D0 C1 E0 08 0F B6 1C 11 03 D8", -0x68, (*mut u8) -> Handle);
-bind_fn!(ENTITY_POS, "48 8B DA E8 ? ? ? ? F3 0F 10 44 24", -6, (*mut u8, &mut Vector3<f32>) -> u64);
+bind_fn!(ENTITY_POS, (*mut u8, &mut Vector3<f32>) -> u64);
 
-bind_field_ip!(PED, "48 8B 05 ? ? ? ? 41 0F BF C8 0F BF 40 10", 3, Option<Box<GenericPool<Ped>>>);
-bind_field_ip!(PROP, "48 8B 05 ? ? ? ? 8B 78 10 85 FF", 3, Option<Box<GenericPool<Prop>>>);
-bind_field_ip!(GLOBAL, "4C 8B 0D ? ? ? ? 44 8B C1 49 8B 41 08", 3, Option<Box<GlobalPool>>);
-bind_field_ip!(VEHICLE, "48 8B 05 ? ? ? ? F3 0F 59 F6 48 8B 08", 3, Option<Box<Box<VehiclePool>>>);
-bind_field_ip!(PICKUP, "4C 8B 05 ? ? ? ? 40 8A F2 8B E9", 3, Option<Box<GenericPool<Pickup>>>);
+bind_field!(PED, Option<Box<GenericPool<Ped>>>);
+bind_field!(PROP, Option<Box<GenericPool<Prop>>>);
+bind_field!(GLOBAL, Option<Box<GlobalPool>>);
+bind_field!(VEHICLE, Option<Box<Box<VehiclePool>>>);
+bind_field!(PICKUP, Option<Box<GenericPool<Pickup>>>);
 
 pub(crate) fn hook() {
     info!("Hooking pools...");
@@ -245,30 +247,6 @@ pub trait Native: Handleable {
     type Repr;
 }
 
-pub trait Handleable {
-    fn from_handle(handle: Handle) -> Option<Self> where Self: Sized;
-    fn get_handle(&self) -> Handle;
-}
-
-#[macro_export]
-macro_rules! impl_handle {
-    ($ty:ident) => {
-        impl crate::native::pool::Handleable for $ty {
-            fn from_handle(handle: crate::game::Handle) -> Option<Self> where Self: Sized {
-                if handle == 0 || handle == u32::MAX {
-                    None
-                } else {
-                    Some($ty { handle })
-                }
-            }
-
-            fn get_handle(&self) -> crate::game::Handle {
-                self.handle
-            }
-        }
-    };
-}
-
 #[macro_export]
 macro_rules! impl_native {
     ($ty:ident,$repr:ident) => {
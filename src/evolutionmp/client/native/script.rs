@@ -5,9 +5,9 @@ use std::os::raw::c_char;
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Mutex;
 
-use crate::{bind_field, bind_field_ip, bind_fn, bind_fn_detour, bind_fn_detour_ip, class};
+use crate::{bind_field, bind_fn, bind_fn_detour, class};
 use crate::events::ScriptEvent;
-use crate::hash::{Hash, Hashable};
+use crate::hash::{Hash, Hashable, VTableHash};
 use crate::native::alloc::RageVec;
 use crate::native::ThreadSafe;
 use crate::runtime::Script;
@@ -26,28 +26,28 @@ pub fn run<S>(name: &str, script: S) where S: Script + 'static {
     event_senders.push(sender);
 }
 
-bind_field!(SCRIPT_TLS_OFFSET, "48 8B 04 D0 4A 8B 14 00 48 8B 01 F3 44 0F 2C 42 20", -4, u32);
+bind_field!(SCRIPT_TLS_OFFSET, u32);
 
-bind_field_ip!(THREAD_COLLECTION, "48 8B C8 EB 03 49 8B CD 48 8B 05", 11, RageVec<ManuallyDrop<Box<ScriptThread>>>);
-bind_field_ip!(THREAD_COUNT, "FF 0D ? ? ? ? 48 8B D9 75", 2, u32);
-bind_field_ip!(SCRIPT_MANAGER, "74 17 48 8B C8 E8 ? ? ? ? 48 8D 0D", 13, ScriptManager);
+bind_field!(THREAD_COLLECTION, RageVec<ManuallyDrop<Box<ScriptThread>>>);
+bind_field!(THREAD_COUNT, u32);
+bind_field!(SCRIPT_MANAGER, ScriptManager);
 
-bind_fn!(SCRIPT_THREAD_INIT, "83 89 ? 01 00 00 FF 83 A1 ? 01 00 00 F0", 0, (&mut ScriptThread) -> ());
-bind_fn!(SCRIPT_THREAD_KILL, "48 83 EC 20 48 83 B9 ? 01 00 00 00 48 8B D9 74 14", -6, (&mut ScriptThread) -> ());
-bind_fn!(SCRIPT_THREAD_TICK, "80 B9 ? 01 00 00 00 8B FA 48 8B D9 74 05", -0xF, (&mut ScriptThread, u32) -> RageThreadState);
+bind_fn!(SCRIPT_THREAD_INIT, (&mut ScriptThread) -> ());
+bind_fn!(SCRIPT_THREAD_KILL, (&mut ScriptThread) -> ());
+bind_fn!(SCRIPT_THREAD_TICK, (&mut ScriptThread, u32) -> RageThreadState);
 
-bind_fn_detour_ip!(SCRIPT_POST_INIT, "BA 2F 7B 2E 30 41 B8 0A", 11, script_post_init, (&(), Hash, u32) -> *mut u8);
-bind_fn_detour!(SCRIPT_STARTUP, "83 FB FF 0F 84 D6 00 00 00", -0x37, script_startup, () -> ());
-bind_fn_detour!(SCRIPT_RESET, "48 63 18 83 FB FF 0F 84 D6", -0x34, script_reset, () -> ());
-bind_fn_detour!(SCRIPT_RUN, "48 83 EC 20 80 B9 ? 01 00 00 00 8B FA", -0xB, script_run, (&'static mut ScriptThread, u32) -> RageThreadState);
-bind_fn_detour!(SCRIPT_ACCESS, "74 3C 48 8B 01 FF 50 10 84 C0", -0x1A, script_access, (&'static mut ScriptThread, *mut ()) -> bool);
+bind_fn_detour!(SCRIPT_POST_INIT, script_post_init, (&(), VTableHash, u32) -> *mut u8);
+bind_fn_detour!(SCRIPT_STARTUP, script_startup, () -> ());
+bind_fn_detour!(SCRIPT_RESET, script_reset, () -> ());
+bind_fn_detour!(SCRIPT_RUN, script_run, (&'static mut ScriptThread, u32) -> RageThreadState);
+bind_fn_detour!(SCRIPT_ACCESS, script_access, (&'static mut ScriptThread, *mut ()) -> bool);
 
 
 /**
     Probably something related to pool allocation/offsets ?
  */
-unsafe extern fn script_post_init(arg: &(), ty: Hash, p3: u32) -> *mut u8 {
-    let fn_name = crate::native::vtables::V_TABLES.get(&ty).map(|f| format!("{} ({})", f, ty)).unwrap_or_else(|| format!("({})", ty));
+unsafe extern fn script_post_init(arg: &(), ty: VTableHash, p3: u32) -> *mut u8 {
+    let fn_name = ty.name().map(|f| format!("{} ({})", f, ty.0)).unwrap_or_else(|| format!("({})", ty.0));
     let result = SCRIPT_POST_INIT(arg, ty, p3);
     if fn_name.contains("phMaterialMgr") {
         info!("called post_init on {:p}, {}, {} -> {:p}", arg, fn_name, p3, result);
@@ -88,6 +88,11 @@ unsafe extern fn script_reset() {
 
     info!("Now resetting owned scripts");
 
+    let dropped = super::dispatch::clear();
+    if dropped > 0 {
+        info!("Dropped {} dispatched native calls", dropped);
+    }
+
     let mut loaded_scripts = LOADED_SCRIPTS.lock().unwrap();
 
     for script in loaded_scripts.iter_mut() {
@@ -96,6 +101,8 @@ unsafe extern fn script_reset() {
 }
 
 unsafe extern fn script_run(script: &'static mut ScriptThread, ops: u32) -> RageThreadState {
+    //Scripts running before the dispatch one may already wait for dispatched calls
+    super::dispatch::bind();
     let mut loaded_scripts = LOADED_SCRIPTS.lock().unwrap();
     if let Some(s) = loaded_scripts.iter_mut().find(|s| s.context.id == script.context.id) {
         s.run(ops);
@@ -0,0 +1,4 @@
+// Typed wrappers of every native of the database, generated by build.rs:
+// `natives::entity::get_entity_coords(entity, alive)` instead of `invoke!(Vector3<f32>, 0x3FEF770D40960D5A, entity, alive)`
+
+include!(concat!(env!("OUT_DIR"), "/natives.rs"));
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use winapi::um::memoryapi::ReadProcessMemory;
+use winapi::um::processthreadsapi::GetCurrentProcess;
+
+use crate::pattern::{MemoryRegion, Pattern};
+use crate::patch::{Patch, PatchError, PatchMemory, Patches};
+
+lazy_static! {
+    static ref PATCHES: Mutex<Patches> = Mutex::new(Patches::new());
+}
+
+/// Memory of the game process itself.
+pub struct ProcessMemory;
+
+impl PatchMemory for ProcessMemory {
+    fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+        unsafe {
+            ReadProcessMemory(
+                GetCurrentProcess(), address as _, buf.as_mut_ptr() as _,
+                buf.len(), std::ptr::null_mut()
+            ) != 0
+        }
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8]) -> bool {
+        let region = MemoryRegion { base: address as *mut u8, size: bytes.len() };
+        unsafe { region.write_bytes(bytes) }
+    }
+}
+
+/// Registers and enables a patch, `expected` being the pattern the original bytes have to match.
+/// Manifest entries of the same name take precedence over the given location and bytes.
+pub fn apply(name: &str, region: &MemoryRegion, expected: Option<&str>, replacement: &[u8]) -> bool {
+    let manifest = crate::native::MANIFEST.patch(name);
+    if manifest.map_or(false, |m| m.is_disabled()) {
+        info!("Patch {} is disabled by the manifest", name);
+        return false;
+    }
+    let address = match manifest.and_then(|m| m.target()) {
+        Some(target) => match unsafe { crate::native::locate(&target) } {
+            Some(region) => region.base as u64,
+            None => {
+                error!("Unable to relocate patch {} to `{}`", name, target.pattern);
+                return false;
+            }
+        },
+        None => region.base as u64
+    };
+    let replacement = manifest.and_then(|m| m.bytes()).unwrap_or_else(|| replacement.to_vec());
+    let expected = manifest.and_then(|m| m.expected.as_deref()).or(expected);
+
+    let mut patch = Patch::new(name, address, replacement);
+    if let Some(expected) = expected {
+        patch = patch.with_expected(Pattern::compile(expected));
+    }
+    let mut patches = PATCHES.lock().expect("mutex poisoned");
+    let result = patches.register(patch)
+        .and_then(|_| patches.enable(name, &mut ProcessMemory));
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Unable to apply patch: {}", e);
+            false
+        }
+    }
+}
+
+pub fn set_enabled(name: &str, enabled: bool) -> Result<(), PatchError> {
+    PATCHES.lock().expect("mutex poisoned").set_enabled(name, enabled, &mut ProcessMemory)
+}
+
+pub fn list() -> Vec<Patch> {
+    PATCHES.lock().expect("mutex poisoned").iter().cloned().collect()
+}
+
+pub(crate) fn restore_all() {
+    let mut patches = PATCHES.lock().expect("mutex poisoned");
+    for e in patches.disable_all(&mut ProcessMemory) {
+        error!("Unable to restore patch: {}", e);
+    }
+}
@@ -1,7 +1,6 @@
 use std::ops::Deref;
 
-use detour::RawDetour;
-use serde_derive::{Deserialize, Serialize};
+use winapi::ctypes::c_void;
 use winapi::shared::minwindef::{DWORD, TRUE};
 use winapi::um::memoryapi::{ReadProcessMemory, VirtualProtect, VirtualQuery};
 use winapi::um::processthreadsapi::GetCurrentProcess;
@@ -11,104 +10,119 @@ use winapi::um::winnt::{MEM_COMMIT, MEM_IMAGE, MEMORY_BASIC_INFORMATION, PAGE_EX
 pub const RET: u8 = 0xC3;
 pub const NOP: u8 = 0x90;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Ord, PartialOrd, PartialEq, Eq, Hash)]
-pub struct Pattern {
-    nibbles: Vec<Option<u8>>
-}
+pub use crate::signature::{generate, Pattern, PatternSet, Resolution};
 
 impl Pattern {
-    pub fn compile(pattern: &str) -> Pattern {
-        let mut nibbles = Vec::new();
-        for b in pattern.split(" ") {
-            if b == "?" || b == "??" {
-                nibbles.push(None);
+    pub unsafe fn find(&self) -> Option<MemoryRegion> {
+        let mut result = None;
+        for_each_image_region(|base, buffer| {
+            if let Some(index) = self.scan(buffer) {
+                result = Some(MemoryRegion {
+                    base: base.add(index),
+                    size: buffer.len() - index,
+                });
+                false
             } else {
-                let b = u8::from_str_radix(b, 16).expect(&format!("Invalid pattern symbol: {}", b));
-                nibbles.push(Some(b))
+                true
             }
-        }
-        Pattern { nibbles }
+        });
+        result
+    }
+
+    pub unsafe fn find_all(&self) -> Vec<MemoryRegion> {
+        let mut result = Vec::new();
+        for_each_image_region(|base, buffer| {
+            for index in self.scan_all(buffer) {
+                result.push(MemoryRegion {
+                    base: base.add(index),
+                    size: buffer.len() - index,
+                });
+            }
+            true
+        });
+        result
     }
 
-    pub fn len(&self) -> usize {
-        self.nibbles.len()
+    pub unsafe fn resolve(&self) -> Resolution<MemoryRegion> {
+        Resolution::from_hits(self.find_all())
     }
+}
 
-    pub fn scan(&self, buf: &[u8]) -> Option<usize> {
-        let pattern_len = self.nibbles.len();
-        for i in 0..(buf.len() - pattern_len) {
-            let mut found = true;
-            for j in 0..pattern_len {
-                if let Some(m) = self.nibbles[j] {
-                    if m != buf[i + j] {
-                        found = false;
-                        break;
-                    }
-                }
-            }
-            if found {
-                return Some(i);
-            }
+impl PatternSet {
+    pub unsafe fn find(&self) -> Vec<Option<MemoryRegion>> {
+        let mut found = vec![None; self.len()];
+        let mut remaining = found.len();
+        if remaining == 0 {
+            return found;
         }
-        None
+        for_each_image_region(|base, buffer| {
+            self.visit(buffer, |index, offset| {
+                if found[index].is_none() {
+                    found[index] = Some(MemoryRegion {
+                        base: base.add(offset),
+                        size: buffer.len() - offset,
+                    });
+                    remaining -= 1;
+                }
+                remaining != 0
+            });
+            remaining != 0
+        });
+        found
+    }
+
+    pub unsafe fn find_all(&self) -> Vec<Vec<MemoryRegion>> {
+        let mut found = vec![Vec::new(); self.len()];
+        for_each_image_region(|base, buffer| {
+            self.visit(buffer, |index, offset| {
+                found[index].push(MemoryRegion {
+                    base: base.add(offset),
+                    size: buffer.len() - offset,
+                });
+                true
+            });
+            true
+        });
+        found
     }
 
-    pub unsafe fn find(&self) -> Option<MemoryRegion> {
-        let mut sys_info = SYSTEM_INFO::default();
-        GetSystemInfo(&mut sys_info);
-        let end = sys_info.lpMaximumApplicationAddress;
-        let mut current_chunk = std::ptr::null_mut();
-        let mut bytes_read = 0;
+    pub unsafe fn resolve(&self) -> Vec<Resolution<MemoryRegion>> {
+        self.find_all().into_iter().map(Resolution::from_hits).collect()
+    }
+}
 
-        while current_chunk < end {
-            let mut mbi = MEMORY_BASIC_INFORMATION::default();
-            let mbi_size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+//Copies every committed image region into a buffer and passes it to `f` until it returns false
+unsafe fn for_each_image_region<F>(mut f: F) where F: FnMut(*mut u8, &[u8]) -> bool {
+    let mut sys_info = SYSTEM_INFO::default();
+    GetSystemInfo(&mut sys_info);
+    let end = sys_info.lpMaximumApplicationAddress;
+    let mut current_chunk = std::ptr::null_mut::<u8>();
+    let mut bytes_read = 0;
+    let mut buffer = Vec::new();
 
-            let process = GetCurrentProcess();
+    while (current_chunk as *mut c_void) < end {
+        let mut mbi = MEMORY_BASIC_INFORMATION::default();
+        let mbi_size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
 
-            if VirtualQuery(current_chunk, &mut mbi, mbi_size) == 0 {
-                return None;
-            }
+        let process = GetCurrentProcess();
 
-            if mbi.State == MEM_COMMIT && mbi.Protect != PAGE_NOACCESS && mbi.Type == MEM_IMAGE {
-                let mut buffer = Vec::with_capacity(mbi.RegionSize);
-                buffer.extend(std::iter::repeat(0u8).take(mbi.RegionSize));
-                let mut old_protect = 0;
-                if VirtualProtect(mbi.BaseAddress, mbi.RegionSize, PAGE_EXECUTE_READWRITE, &mut old_protect) == TRUE {
-                    ReadProcessMemory(process, mbi.BaseAddress, buffer.as_mut_ptr().cast(), mbi.RegionSize, &mut bytes_read);
-                    VirtualProtect(mbi.BaseAddress, mbi.RegionSize, old_protect, &mut old_protect);
-                    if let Some(index) = self.scan(&buffer[0..bytes_read]) {
-                        let base = current_chunk.add(index).cast();
-                        return Some(MemoryRegion {
-                            base,
-                            size: bytes_read - index,
-                        });
-                    }
-                }
-            }
-            current_chunk = current_chunk.add(mbi.RegionSize);
+        if VirtualQuery(current_chunk.cast(), &mut mbi, mbi_size) == 0 {
+            return;
         }
 
-        None
-    }
-}
-
-impl std::fmt::Display for Pattern {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for b in &self.nibbles {
-            if let Some(b) = b {
-                f.write_fmt(format_args!("{:02X} ", *b))?;
-            } else {
-                f.write_str("? ")?;
+        if mbi.State == MEM_COMMIT && mbi.Protect != PAGE_NOACCESS && mbi.Type == MEM_IMAGE {
+            buffer.clear();
+            buffer.resize(mbi.RegionSize, 0u8);
+            let mut old_protect = 0;
+            if VirtualProtect(mbi.BaseAddress, mbi.RegionSize, PAGE_EXECUTE_READWRITE, &mut old_protect) == TRUE {
+                ReadProcessMemory(process, mbi.BaseAddress, buffer.as_mut_ptr().cast(), mbi.RegionSize, &mut bytes_read);
+                VirtualProtect(mbi.BaseAddress, mbi.RegionSize, old_protect, &mut old_protect);
+                if !f(current_chunk, &buffer[0..bytes_read]) {
+                    return;
+                }
             }
         }
-        Ok(())
-    }
-}
-
-impl<S> From<S> for Pattern where S: AsRef<str> {
-    fn from(s: S) -> Self {
-        Pattern::compile(s.as_ref())
+        current_chunk = current_chunk.add(mbi.RegionSize);
     }
 }
 
@@ -223,7 +237,7 @@ impl MemoryRegion {
     }
 
     pub unsafe fn replace<P>(&self, pattern: P) where P: Into<Pattern> {
-        for (i, b) in pattern.into().nibbles.iter().map(|n| n.unwrap()).enumerate() {
+        for (i, b) in pattern.into().bytes().iter().map(|n| n.fixed().expect("wildcard in replacement")).enumerate() {
             self.base.add(i).write(b)
         }
     }
@@ -254,26 +268,28 @@ impl MemoryRegion {
         }
     }
 
+    /// Moves a region found by `pattern` to where the pattern's cursor and captures point.
+    pub unsafe fn target(&self, pattern: &Pattern) -> MemoryRegion {
+        match pattern.decode(self.as_bytes(), 0) {
+            Some(m) => self.offset(m.target as isize),
+            None => self.clone()
+        }
+    }
+
     pub unsafe fn get_call(&self) -> *const () {
         self.add(1).read_ptr(4).get()
     }
 
-    pub unsafe fn detour(&self, replacement: *const ()) -> *const () {
-        let old = self.as_ptr() as *const ();
-        let detour = RawDetour::new(old, replacement).expect("detour creation failed");
-        detour.enable().expect("detour enabling failed");
-        let old = detour.trampoline() as *const _;
-        std::mem::forget(detour);
-        old
+    /// Registers a named hook on the function starting at this region, returning its trampoline.
+    pub unsafe fn detour(&self, name: &str, replacement: *const ()) -> *const () {
+        crate::detours::install(name, self.as_ptr() as *const (), replacement)
+            .unwrap_or_else(|e| panic!("detour creation failed: {}", e))
     }
 
-    pub unsafe fn detour_ip(&self, replacement: *const ()) -> *const () {
-        let old = self.get_call();
-        let detour = RawDetour::new(old, replacement).expect("detour creation failed");
-        detour.enable().expect("detour enabling failed");
-        let old = detour.trampoline() as *const _;
-        std::mem::forget(detour);
-        old
+    /// Same as `detour`, but hooks the function called by the instruction at this region.
+    pub unsafe fn detour_ip(&self, name: &str, replacement: *const ()) -> *const () {
+        crate::detours::install(name, self.get_call(), replacement)
+            .unwrap_or_else(|e| panic!("detour creation failed: {}", e))
     }
 
     pub unsafe fn jump(&self, replacement: *const ()) -> *const () {
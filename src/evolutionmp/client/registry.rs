@@ -47,4 +47,16 @@ impl Registry {
     pub fn get_install_path(&self) -> PathBuf {
         self.install_folder.clone()
     }
+
+    pub fn game_type(&self) -> Option<&str> {
+        self.game_type.as_deref()
+    }
+
+    pub fn game_version(&self) -> Option<&str> {
+        self.game_version.as_deref()
+    }
+
+    pub fn patch_version(&self) -> Option<&str> {
+        self.patch_version.as_deref()
+    }
 }
\ No newline at end of file
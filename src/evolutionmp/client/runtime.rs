@@ -1,5 +1,6 @@
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::time::Instant;
 
 use jni_dynamic::{JavaVM, JNIEnv, NativeMethod};
 use jni_dynamic::errors::ErrorKind;
@@ -13,9 +14,10 @@ use crate::jni::{JavaObject, JavaValue};
 use crate::jni::attach_thread;
 use crate::launcher_dir;
 use crate::native::{NativeCallContext, ThreadSafe};
+use crate::native::hooks::{self, HookAction};
 use crate::native::pool::Pool;
 use crate::win::input::{InputEvent, KeyboardEvent};
-use jni_dynamic::sys::jint;
+use jni_dynamic::sys::{jboolean, jint};
 use jni_dynamic::signature::JavaType;
 use jni_dynamic::signature::Primitive::{Void, Int};
 
@@ -203,6 +205,14 @@ pub(crate) fn start(vm: Arc<JavaVM>) {
         std::process::id() as _
     }
 
+    extern fn set_profiling(_env: &JNIEnv, _obj: JObject, enabled: jboolean) {
+        crate::profile::set_enabled(enabled != 0);
+    }
+
+    extern fn reset_native_stats(_env: &JNIEnv, _obj: JObject) {
+        crate::profile::reset();
+    }
+
     natives!(env, "mp.evolution.invoke.NativeArgs",
         NativeMethod::new("getStringUTFChars", "(Ljava/lang/String;)J", get_string_utf_chars as _)
     );
@@ -210,7 +220,9 @@ pub(crate) fn start(vm: Arc<JavaVM>) {
         NativeMethod::new("getStringFromUTFChars", "(J)Ljava/lang/String;", get_string_from_utf_chars as _)
     );
     natives!(env, "mp.evolution.invoke.Native",
-        NativeMethod::new("invoke", "(JJIJ)V", invoke as _)
+        NativeMethod::new("invoke", "(JJIJ)V", invoke as _),
+        NativeMethod::new("addHook", "(JIILmp/evolution/invoke/NativeHook;)J", add_hook as _),
+        NativeMethod::new("removeHook", "(J)Z", remove_hook as _)
     );
     natives!(env, "mp.evolution.script.Script",
         //NativeMethod::new("yield", "(J)V", wait as _),
@@ -299,7 +311,13 @@ pub(crate) fn start(vm: Arc<JavaVM>) {
     natives!(env, "mp.evolution.runtime.Runtime",
         NativeMethod::new("restart", "()V", restart as _),
         NativeMethod::new("pid", "()I", pid as _),
-        NativeMethod::new("unlockModule", "(Ljava/lang/Module;Ljava/lang/String;)V", unlock_module as _)
+        NativeMethod::new("unlockModule", "(Ljava/lang/Module;Ljava/lang/String;)V", unlock_module as _),
+        NativeMethod::new("setProfiling", "(Z)V", set_profiling as _),
+        NativeMethod::new("resetNativeStats", "()V", reset_native_stats as _),
+        NativeMethod::new("getNativeStats", "()Ljava/lang/String;", get_native_stats as _),
+        NativeMethod::new("getScriptNativeStats", "(I)Ljava/lang/String;", get_script_native_stats as _),
+        NativeMethod::new("getPatches", "()Ljava/lang/String;", get_patches as _),
+        NativeMethod::new("setPatchEnabled", "(Ljava/lang/String;Z)Z", set_patch_enabled as _)
     );
 
     lazy_static::initialize(&RUNTIME);
@@ -370,6 +388,40 @@ extern "C" fn unlock_module(_: &JNIEnv, _class: JClass, module: JObject, package
     call!(env, env.call_method(module, "implAddOpensToAllUnnamed", "(Ljava/lang/String;)V", args![*package]));
 }
 
+//Statistics of every native merged across scripts, as JSON
+extern "C" fn get_native_stats<'a>(_env: &'a JNIEnv, _class: JClass) -> JString<'a> {
+    let env = attach_thread();
+    env.new_string(crate::profile::snapshot().to_json(None)).unwrap()
+}
+
+//Statistics of the natives called by a script, given its hash
+extern "C" fn get_script_native_stats<'a>(_env: &'a JNIEnv, _class: JClass, script: jint) -> JString<'a> {
+    let env = attach_thread();
+    env.new_string(crate::profile::snapshot().to_json(Some(script as u32))).unwrap()
+}
+
+//Registered memory patches, one per line
+extern "C" fn get_patches<'a>(_env: &'a JNIEnv, _class: JClass) -> JString<'a> {
+    let env = attach_thread();
+    let patches = crate::patches::list().iter()
+        .map(|patch| patch.to_string())
+        .collect::<Vec<_>>();
+    env.new_string(patches.join("\n")).unwrap()
+}
+
+//Enables or restores a patch by name, returning false if it's unknown or the memory couldn't be written
+extern "C" fn set_patch_enabled(_env: &JNIEnv, _class: JClass, name: JString, enabled: jboolean) -> jboolean {
+    let env = attach_thread();
+    let name = env.get_string(name).unwrap().to_string_lossy().to_string();
+    match crate::patches::set_enabled(&name, enabled != 0) {
+        Ok(()) => 1,
+        Err(e) => {
+            error!("Unable to toggle patch: {}", e);
+            0
+        }
+    }
+}
+
 unsafe extern fn get_string_utf_chars(_env: &JNIEnv, _class: JClass, value: JString) -> *const i8 {
     let env = attach_thread();
     env.get_string_utf_chars(value).unwrap()
@@ -380,6 +432,46 @@ unsafe extern fn get_string_from_utf_chars<'a>(_env: &'a JNIEnv, _class: JClass,
     env.new_string(JNIStr::from_ptr(ptr).to_owned()).unwrap()
 }
 
+//Hooks a native with a `NativeHook`, `kind` being 0 to run before the native, 1 to replace it and 2 to run after it.
+//Natives invoked from a hook, such as the replaced one, aren't hooked again.
+unsafe extern fn add_hook(_env: &JNIEnv, _class: JClass, hash: u64, kind: jint, priority: jint, hook: JObject) -> u64 {
+    let env = attach_thread();
+    let hook = env.new_global_ref(hook).unwrap();
+    let call = move |context: &mut NativeCallContext| call_java_hook(&hook, hash, context);
+    match kind {
+        0 => hooks::pre(hash, priority, move |context| if call(context) { HookAction::Continue } else { HookAction::Skip }),
+        1 => hooks::replace(hash, priority, move |context, _| { call(context); }),
+        2 => hooks::post(hash, priority, move |context| { call(context); }),
+        _ => {
+            env.throw_new("java/lang/IllegalArgumentException", format!("Invalid hook kind: {}", kind)).unwrap();
+            0
+        }
+    }
+}
+
+unsafe extern fn remove_hook(_env: &JNIEnv, _class: JClass, id: u64) -> jboolean {
+    hooks::remove(id) as jboolean
+}
+
+//`boolean call(long hash, long args, int argCount, long result)`, returning whether the native should run
+fn call_java_hook(hook: &GlobalRef, hash: u64, context: &mut NativeCallContext) -> bool {
+    let env = attach_thread();
+    let arg_count = context.arg_count() as jint;
+    let args = context.raw_args().as_mut_ptr() as i64;
+    let returns = context.raw_returns().as_mut_ptr() as i64;
+    match env.call_method(hook.as_obj(), "call", "(JJIJ)Z", args![hash as i64, args, arg_count, returns]) {
+        Ok(result) => result.z().unwrap_or(true),
+        Err(e) if matches!(e.kind(), ErrorKind::JavaException) => {
+            error!("Java hook of {} failed: {}", crate::nativedb::describe(hash), get_last_exception(&env));
+            true
+        }
+        Err(e) => {
+            error!("Java hook of {} failed: {}", crate::nativedb::describe(hash), e);
+            true
+        }
+    }
+}
+
 unsafe extern fn propagate(_env: &JNIEnv, _script: JObject, _event: JObject<'static>) {
     unimplemented!()
 }
@@ -397,15 +489,16 @@ unsafe extern fn error(_env: &JNIEnv, _class: JClass, line: JObject) {
 }
 
 unsafe extern fn invoke(_env: &JNIEnv, _class: JClass, hash: u64, args: &mut [u64; 32], arg_count: u32, result: &mut [u64; 3]) {
-    if let Some(handler) = crate::native::get_handler_opt(hash) {
-        let mut context = NativeCallContext::new(args, result, arg_count);
-        crate::native::CURRENT_NATIVE.store(hash, Ordering::SeqCst);
-        handler(&mut context);
-        crate::native::SET_VECTOR_RESULTS(&mut context); //Flush all &mut NativeVector3 args
-        crate::native::CURRENT_NATIVE.store(0, Ordering::SeqCst);
-    } else {
+    let mut context = NativeCallContext::new(args, result, arg_count);
+    crate::native::CURRENT_NATIVE.store(hash, Ordering::SeqCst);
+    let start = if crate::native::timing_enabled() { Some(Instant::now()) } else { None };
+    let found = crate::backend::try_call(hash, &mut context);
+    crate::native::CURRENT_NATIVE.store(0, Ordering::SeqCst);
+    if !found {
         let env = attach_thread();
         env.throw_new("java/lang/IllegalArgumentException", format!("No such native: 0x{:016X}", hash)).unwrap();
+    } else if let Some(start) = start {
+        crate::native::record_call(hash, &context, start.elapsed());
     }
 }
 
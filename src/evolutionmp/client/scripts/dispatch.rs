@@ -0,0 +1,17 @@
+use crate::events::ScriptEvent;
+use crate::native::dispatch;
+use crate::runtime::Script;
+
+/// Runs the native calls other threads dispatched to the game script thread.
+pub struct ScriptDispatch;
+
+impl Script for ScriptDispatch {
+    fn frame(&mut self) {
+        let stats = dispatch::drain();
+        if stats.remaining > 0 {
+            trace!("{} dispatched native calls left for the next frame", stats.remaining);
+        }
+    }
+
+    fn event(&mut self, _event: ScriptEvent) {}
+}
@@ -1,15 +1,21 @@
 use crate::client::scripts::fishing::ScriptFishing;
 use crate::runtime::ScriptJava;
 use crate::scripts::cleanup::ScriptCleanWorld;
+use crate::scripts::dispatch::ScriptDispatch;
+use crate::scripts::profiler::ScriptProfiler;
 
 pub mod cleanup;
+pub mod dispatch;
 pub mod pointing;
 pub mod fishing;
+pub mod profiler;
 
 pub fn init() {
     info!("Initializing scripts");
 
+    crate::native::script::run("dispatch", ScriptDispatch);
     crate::native::script::run("clean_world", ScriptCleanWorld::new());
     crate::native::script::run("fishing", ScriptFishing::new());
     crate::native::script::run("java", ScriptJava::new());
+    crate::native::script::run("profiler", ScriptProfiler::new());
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use cgmath::Vector2;
+
+use crate::events::ScriptEvent;
+use crate::game::Rgba;
+use crate::game::ui::{self, Font};
+use crate::profile;
+use crate::runtime::Script;
+
+const LINES: usize = 12;
+const REFRESH: Duration = Duration::from_secs(1);
+
+static OVERLAY: AtomicBool = AtomicBool::new(false);
+
+pub fn overlay_shown() -> bool {
+    OVERLAY.load(Ordering::Relaxed)
+}
+
+pub fn show_overlay(shown: bool) {
+    OVERLAY.store(shown, Ordering::Relaxed);
+}
+
+/// Draws the natives the most time is spent in while profiling and the overlay are on.
+pub struct ScriptProfiler {
+    lines: Vec<String>,
+    last_refresh: Option<Instant>
+}
+
+impl ScriptProfiler {
+    pub fn new() -> ScriptProfiler {
+        ScriptProfiler {
+            lines: Vec::new(),
+            last_refresh: None
+        }
+    }
+
+    fn refresh(&mut self) {
+        let stats = profile::snapshot();
+        self.lines.clear();
+        self.lines.push(format!("Natives: {} calls, {:.2?}", stats.calls(), stats.total()));
+        self.lines.extend(stats.top(LINES).iter().map(|native| native.summary()));
+        self.last_refresh = Some(Instant::now());
+    }
+}
+
+impl Script for ScriptProfiler {
+    fn frame(&mut self) {
+        const BACKGROUND_COLOR: Rgba = Rgba::new(0, 0, 0, 160);
+        const LINE_HEIGHT: f32 = 16.0;
+
+        if !overlay_shown() || !profile::enabled() {
+            self.last_refresh = None;
+            return;
+        }
+        if self.last_refresh.is_none_or(|last| last.elapsed() >= REFRESH) {
+            self.refresh();
+        }
+
+        let scale = Vector2::new(0.25, 0.25);
+        let (x, y) = (10.0, 10.0);
+        ui::draw_rect([x - 4.0, y - 2.0], [620.0, LINE_HEIGHT * self.lines.len() as f32 + 4.0], BACKGROUND_COLOR);
+        for (i, line) in self.lines.iter().enumerate() {
+            ui::draw_text(line, [x, y + LINE_HEIGHT * i as f32], Rgba::WHITE, Font::Monospace, scale);
+        }
+    }
+
+    fn event(&mut self, _event: ScriptEvent) {}
+}
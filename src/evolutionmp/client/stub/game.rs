@@ -0,0 +1,59 @@
+pub use crate::color::{Rgb, Rgba};
+pub use crate::handle::Handle;
+
+//Entities and helpers the wrappers under test only pass around or call
+macro_rules! entities {
+    ($($module:ident::$ty:ident: $repr:ident),*) => {
+        $(
+            pub mod $module {
+                use crate::game::entity::Entity;
+                use crate::game::Handle;
+                use crate::native::pool::$repr;
+
+                #[derive(Debug, PartialEq, Eq)]
+                pub struct $ty {
+                    handle: Handle
+                }
+
+                impl Entity for $ty {
+                    fn delete(&mut self) {
+                        unimplemented!()
+                    }
+                }
+
+                crate::impl_native!($ty, $repr);
+            }
+        )*
+    };
+}
+
+entities!(ped::Ped: CPed, prop::Prop: CProp, worldprobe::ProbeEntity: CEntity);
+
+pub mod ui {
+    use crate::invoke;
+
+    pub fn push_string(value: &str) {
+        for s in value.as_bytes().chunks(99).map(|c| unsafe { std::str::from_utf8_unchecked(c) }) {
+            invoke!((), 0x6C188BE134E074AA, s)
+        }
+    }
+}
+
+#[path = "../game/blip.rs"]
+pub mod blip;
+#[path = "../game/data.rs"]
+pub mod data;
+#[path = "../game/entity.rs"]
+pub mod entity;
+#[path = "../game/radio.rs"]
+pub mod radio;
+#[path = "../game/scaleform.rs"]
+pub mod scaleform;
+#[path = "../game/stats.rs"]
+pub mod stats;
+#[path = "../game/streaming.rs"]
+pub mod streaming;
+#[path = "../game/system.rs"]
+pub mod system;
+#[path = "../game/vehicle.rs"]
+pub mod vehicle;
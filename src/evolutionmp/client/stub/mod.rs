@@ -0,0 +1,4 @@
+//Stand-in for the client on other platforms, building the game wrappers over stubbed pools
+//so their tests run against the fake native backend
+pub mod game;
+pub mod native;
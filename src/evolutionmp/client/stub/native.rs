@@ -0,0 +1,103 @@
+use std::marker::PhantomData;
+
+pub use crate::stack::{NativeCallContext, NativeFunction, NativeStackReader, NativeStackValue, NativeStackWriter};
+
+//Without a native table every call goes to the backend
+#[macro_export]
+macro_rules! invoke {
+    ($ret: ty, $hash: literal $(, $arg: expr)*) => {{
+        let _frame = $crate::arena::frame();
+        let mut args = [0; 32];
+        let mut result = [0; 3];
+        let mut context = $crate::native::NativeCallContext::new(&mut args, &mut result, 0);
+        $(context.push_arg($arg);)*
+        $crate::backend::call($hash, &mut context);
+        context.get_result::<$ret>()
+    }};
+}
+
+#[macro_export]
+macro_rules! invoke_option {
+    ($ret: expr, $hash: literal, $($arg: expr),*) => {
+        if invoke!(bool, $hash, $($arg),*)  {
+            Some($ret)
+        } else {
+            None
+        }
+    };
+}
+
+pub trait Addressable: pool::Native {
+    fn get_address(&self) -> *mut u8;
+}
+
+pub struct NativeField<A, T> where A: Addressable, T: Sized {
+    _ty_a: PhantomData<A>,
+    _ty_t: PhantomData<T>,
+}
+
+impl<A, T> NativeField<A, T> where A: Addressable, T: Sized {
+    pub(crate) const fn new() -> NativeField<A, T> {
+        NativeField { _ty_a: PhantomData, _ty_t: PhantomData }
+    }
+
+    pub fn set(&self, _target: &A, _value: T) {
+        panic!("game memory is not available")
+    }
+
+    pub fn get(&self, _target: &A) -> T {
+        panic!("game memory is not available")
+    }
+}
+
+pub mod pool {
+    use crate::game::Handle;
+
+    pub use crate::handle::Handleable;
+
+    pub trait Native: Handleable {
+        type Repr;
+    }
+
+    pub enum CBlip {}
+    pub enum CEntity {}
+    pub enum CPed {}
+    pub enum CProp {}
+    pub enum CVehicle {}
+
+    pub struct VehiclePool;
+
+    pub static VEHICLE: Option<Box<VehiclePool>> = None;
+
+    pub static ENTITY_ADDRESS: fn(Handle) -> *mut u8 = |_| panic!("game memory is not available");
+
+    #[macro_export]
+    macro_rules! impl_native {
+        ($ty:ident,$repr:ident) => {
+            crate::impl_handle!($ty);
+
+            impl crate::native::pool::Native for $ty {
+                type Repr = $repr;
+            }
+        };
+    }
+}
+
+pub mod vehicle {
+    use crate::game::vehicle::Vehicle;
+
+    use super::NativeField;
+
+    macro_rules! fields {
+        ($($name:ident: $ty:ty),*) => {
+            $(pub(crate) static $name: NativeField<Vehicle, $ty> = NativeField::new();)*
+        };
+    }
+
+    fields!(
+        NEXT_GEAR: u8, CURRENT_GEAR: u8, HIGH_GEAR: u8, FUEL_LEVEL: f32, OIL_LEVEL: f32, LIGHTS: u32,
+        WHEEL_SPEED: f32, RPM: f32, CLUTCH: f32, THROTTLE: f32, DASHBOARD_SPEED: f32, STEERING_SCALE: f32,
+        STEERING_ANGLE: f32, THROTTLE_POWER: f32, BRAKE_POWER: f32, HANDBRAKE: bool, ENGINE_TEMPERATURE: f32,
+        TRAIN_TRACK_NODE: i32, ALARM_TIME: u16, TURBO: f32, ENGINE_POWER: f32, OIL_VOLUME: f32
+    );
+}
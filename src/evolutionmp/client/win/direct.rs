@@ -2,7 +2,6 @@ use std::mem::ManuallyDrop;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-use detour::RawDetour;
 use winapi::shared::guiddef::REFIID;
 use winapi::shared::winerror::{HRESULT_CODE, SUCCEEDED};
 use winapi::um::libloaderapi::GetModuleHandleA;
@@ -17,7 +16,7 @@ use winapi::shared::dxgiformat::DXGI_FORMAT;
 use winapi::ctypes::c_void;
 
 
-bind_fn!(GET_SWAP_CHAIN, "48 8B 05 ? ? ? ? C3 48 8B C1 8D 4A 0E", 0, () -> Option<ManuallyDrop<Box<IDXGISwapChain>>>);
+bind_fn!(GET_SWAP_CHAIN, () -> Option<ManuallyDrop<Box<IDXGISwapChain>>>);
 
 macro_rules! direct_detour {
     ($name:ident,$index:ident,$repl:expr,($($arg:ty),*)->$ret:ty) => {
@@ -27,11 +26,8 @@ macro_rules! direct_detour {
                 let swap_chain = swap_chain.as_ref().expect("no swap chain");
                 let vtable = &swap_chain.lpVtbl.read();
                 let proc = vtable.$index;
-                let detour = RawDetour::new(proc as _, $repl as _)
-                    .expect(concat!("error detouring ", stringify!($index)));
-                detour.enable().expect(concat!("error enabling detour for ", stringify!($index)));
-                let trampoline = detour.trampoline() as *const ();
-                std::mem::forget(detour);
+                let trampoline = crate::detours::install(stringify!($index), proc as _, $repl as _)
+                    .unwrap_or_else(|e| panic!("error detouring {}: {}", stringify!($index), e));
                 std::mem::transmute(trampoline)
             };
         }
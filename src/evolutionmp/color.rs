@@ -0,0 +1,91 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::stack::{NativeStackReader, NativeStackValue, NativeStackWriter};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 255);
+    pub const DARK_GRAY: Rgba = Rgba::new(81, 81, 81, 255);
+    pub const BLACK: Rgba = Rgba::new(0, 0, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Rgba {
+        Rgba { r, g, b, a }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+}
+
+impl NativeStackValue for Rgba {
+    fn read_from_stack(_stack: &mut NativeStackReader) -> Self {
+        panic!("Reading Rgba color from return stack is not possible")
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self.r as u32);
+        stack.write(self.g as u32);
+        stack.write(self.b as u32);
+        stack.write(self.a as u32);
+    }
+}
+
+impl NativeStackValue for Rgb {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        let r = stack.read::<u32>();
+        let g = stack.read::<u32>();
+        let b = stack.read::<u32>();
+        Rgb::new(r as u8, g as u8, b as u8)
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self.r as u32);
+        stack.write(self.g as u32);
+        stack.write(self.b as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_layout() {
+        let mut stack = [0; 5];
+        let mut writer = NativeStackWriter::new(&mut stack);
+        assert_eq!(writer.write(Rgba::new(255, 128, 0, 81)), 4);
+        assert_eq!(writer.write(7u32), 1);
+        assert_eq!(stack, [255, 128, 0, 81, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reading Rgba color")]
+    fn rgba_result() {
+        NativeStackReader::new(&[0; 4]).read::<Rgba>();
+    }
+
+    #[test]
+    fn rgb_layout() {
+        let mut stack = [0; 3];
+        NativeStackWriter::new(&mut stack).write(Rgb::new(1, 2, 3));
+        assert_eq!(stack, [1, 2, 3]);
+        let rgb = NativeStackReader::new(&[0x1FF, 0, 40]).read::<Rgb>();
+        assert_eq!((rgb.r, rgb.g, rgb.b), (0xFF, 0, 40));
+    }
+}
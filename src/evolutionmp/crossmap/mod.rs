@@ -0,0 +1,260 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+mod mappings;
+
+/// Native crossmap file, relative to the launcher directory.
+pub const CROSSMAP_FILE: &str = "crossmap.json";
+
+/// Game build the compiled-in crossmap was made for.
+pub const BUILTIN_BUILD: u16 = 2060;
+
+/// Translation of the native hashes used by the client to the ones of a game build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossmap {
+    pub build: u16,
+    entries: Vec<(u64, u64)>,
+}
+
+//On-disk form, hashes being hex strings as JSON numbers can't hold them
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CrossmapFile {
+    build: u16,
+    mappings: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub enum CrossmapError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    InvalidHash(String),
+    Duplicate(u64),
+    Cycle(Vec<u64>),
+}
+
+impl Display for CrossmapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrossmapError::Io(e) => e.fmt(f),
+            CrossmapError::Json(e) => e.fmt(f),
+            CrossmapError::InvalidHash(hash) => f.write_fmt(format_args!("invalid native hash `{}`", hash)),
+            CrossmapError::Duplicate(hash) => f.write_fmt(format_args!("native 0x{:016X} is mapped more than once", hash)),
+            CrossmapError::Cycle(hashes) => {
+                f.write_str("natives are mapped in a cycle:")?;
+                for hash in hashes {
+                    f.write_fmt(format_args!(" 0x{:016X} ->", hash))?;
+                }
+                f.write_fmt(format_args!(" 0x{:016X}", hashes[0]))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CrossmapError {}
+
+impl From<std::io::Error> for CrossmapError {
+    fn from(e: std::io::Error) -> Self {
+        CrossmapError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for CrossmapError {
+    fn from(e: serde_json::Error) -> Self {
+        CrossmapError::Json(e)
+    }
+}
+
+impl Crossmap {
+    pub fn new(build: u16, entries: Vec<(u64, u64)>) -> Crossmap {
+        Crossmap { build, entries }
+    }
+
+    /// The crossmap compiled into the client.
+    pub fn builtin() -> Crossmap {
+        Crossmap::new(BUILTIN_BUILD, mappings::MAPPINGS.to_vec())
+    }
+
+    /// Reads a crossmap file, `None` meaning there is none.
+    pub fn load<P>(path: P) -> Result<Option<Crossmap>, CrossmapError> where P: AsRef<Path> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        Crossmap::parse(&std::fs::read_to_string(path)?).map(Some)
+    }
+
+    /// Parses `{"build": 2060, "mappings": [["0x...", "0x..."], ...]}`.
+    pub fn parse(json: &str) -> Result<Crossmap, CrossmapError> {
+        let file: CrossmapFile = serde_json::from_str(json)?;
+        let entries = file.mappings.iter()
+            .map(|(from, to)| Ok((parse_hash(from)?, parse_hash(to)?)))
+            .collect::<Result<Vec<_>, CrossmapError>>()?;
+        Ok(Crossmap::new(file.build, entries))
+    }
+
+    pub fn to_json(&self) -> String {
+        let file = CrossmapFile {
+            build: self.build,
+            mappings: self.entries.iter()
+                .map(|(from, to)| (format!("0x{:016X}", from), format!("0x{:016X}", to)))
+                .collect()
+        };
+        serde_json::to_string_pretty(&file).expect("crossmap serialization failed")
+    }
+
+    pub fn entries(&self) -> &[(u64, u64)] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Finds natives mapped more than once and mappings leading back to where they started.
+    pub fn validate(&self) -> Vec<CrossmapError> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for (from, _) in &self.entries {
+            if !seen.insert(*from) && reported.insert(*from) {
+                errors.push(CrossmapError::Duplicate(*from));
+            }
+        }
+        errors.extend(self.cycles().into_iter().map(CrossmapError::Cycle));
+        errors
+    }
+
+    /// Drops every duplicate after the first mapping of a native and every mapping of a cycle,
+    /// returning why they were dropped.
+    pub fn prune_invalid(&mut self) -> Vec<CrossmapError> {
+        let errors = self.validate();
+        let mut cyclic = HashSet::new();
+        for e in &errors {
+            if let CrossmapError::Cycle(hashes) = e {
+                cyclic.extend(hashes.iter().cloned());
+            }
+        }
+        let mut seen = HashSet::new();
+        self.entries.retain(|(from, _)| !cyclic.contains(from) && seen.insert(*from));
+        errors
+    }
+
+    /// Mapped targets `is_live` doesn't know about, sorted.
+    pub fn missing_targets<F>(&self, is_live: F) -> Vec<u64> where F: Fn(u64) -> bool {
+        self.entries.iter()
+            .map(|(_, to)| *to)
+            .filter(|to| !is_live(*to))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Lookup table of the crossmap, the first mapping of a native winning.
+    pub fn to_map(&self) -> HashMap<u64, u64> {
+        let mut map = HashMap::with_capacity(self.entries.len());
+        for (from, to) in &self.entries {
+            map.entry(*from).or_insert(*to);
+        }
+        map
+    }
+
+    //Natives mapping to themselves are identities, not cycles
+    fn cycles(&self) -> Vec<Vec<u64>> {
+        let map = self.to_map();
+        let mut done = HashSet::new();
+        let mut cycles = Vec::new();
+        let mut starts = map.keys().cloned().collect::<Vec<_>>();
+        starts.sort();
+        for start in starts {
+            let mut path = Vec::new();
+            let mut current = start;
+            while !done.contains(&current) {
+                if let Some(position) = path.iter().position(|h| *h == current) {
+                    cycles.push(path[position..].to_vec());
+                    break;
+                }
+                path.push(current);
+                match map.get(&current) {
+                    Some(next) if *next != current => current = *next,
+                    _ => break
+                }
+            }
+            done.extend(path);
+        }
+        cycles
+    }
+}
+
+fn parse_hash(hash: &str) -> Result<u64, CrossmapError> {
+    let digits = hash.strip_prefix("0x").or_else(|| hash.strip_prefix("0X")).unwrap_or(hash);
+    u64::from_str_radix(digits, 16).map_err(|_| CrossmapError::InvalidHash(String::from(hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_round_trip() {
+        let crossmap = Crossmap::parse(r#"{"build": 2189, "mappings": [["0x1", "0X2"], ["ABCDEF", "0x0000000000000003"]]}"#).unwrap();
+        assert_eq!(crossmap.build, 2189);
+        assert_eq!(crossmap.entries(), &[(1, 2), (0xABCDEF, 3)]);
+        assert_eq!(Crossmap::parse(&crossmap.to_json()).unwrap(), crossmap);
+        assert!(matches!(Crossmap::parse(r#"{"build": 1, "mappings": [["0xZZ", "0x1"]]}"#),
+                         Err(CrossmapError::InvalidHash(h)) if h == "0xZZ"));
+        assert!(matches!(Crossmap::parse(r#"{"build": 1, "mappings": [], "extra": 0}"#), Err(CrossmapError::Json(_))));
+        assert!(matches!(Crossmap::parse(r#"{"build": 1, "mappings": [[1, 2]]}"#), Err(CrossmapError::Json(_))));
+        assert!(Crossmap::load("/nonexistent/crossmap.json").unwrap().is_none());
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut crossmap = Crossmap::new(1, vec![(1, 2), (3, 4), (1, 5), (1, 6)]);
+        let errors = crossmap.validate();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], CrossmapError::Duplicate(1)));
+        assert_eq!(crossmap.to_map()[&1], 2);
+        assert_eq!(crossmap.prune_invalid().len(), 1);
+        assert_eq!(crossmap.entries(), &[(1, 2), (3, 4)]);
+        assert!(crossmap.validate().is_empty());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut crossmap = Crossmap::new(1, vec![(1, 2), (2, 3), (3, 1), (4, 4), (5, 1), (6, 7), (7, 6)]);
+        let errors = crossmap.validate();
+        let cycles = errors.iter().map(|e| match e {
+            CrossmapError::Cycle(hashes) => hashes.clone(),
+            e => panic!("unexpected {}", e)
+        }).collect::<Vec<_>>();
+        assert_eq!(cycles, vec![vec![1, 2, 3], vec![6, 7]]);
+        assert_eq!(errors[1].to_string(),
+                   "natives are mapped in a cycle: 0x0000000000000006 -> 0x0000000000000007 -> 0x0000000000000006");
+        crossmap.prune_invalid();
+        //Identities and natives leading into a cycle are kept
+        assert_eq!(crossmap.entries(), &[(4, 4), (5, 1)]);
+    }
+
+    #[test]
+    fn missing_targets() {
+        let crossmap = Crossmap::new(1, vec![(1, 20), (2, 10), (3, 20), (4, 30)]);
+        assert_eq!(crossmap.missing_targets(|hash| hash == 30), vec![10, 20]);
+        assert!(crossmap.missing_targets(|_| true).is_empty());
+    }
+
+    #[test]
+    fn builtin_is_valid() {
+        let crossmap = Crossmap::builtin();
+        assert_eq!(crossmap.build, BUILTIN_BUILD);
+        assert!(!crossmap.is_empty());
+        assert!(crossmap.validate().is_empty());
+    }
+}
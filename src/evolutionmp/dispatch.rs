@@ -0,0 +1,499 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchError {
+    Cancelled,
+    /// The call panicked, with the panic message
+    Panicked(String),
+    /// The queue was cleared before the call ran
+    Dropped,
+    Timeout,
+}
+
+impl Display for DispatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DispatchError::Cancelled => f.write_str("call cancelled"),
+            DispatchError::Panicked(message) => f.write_fmt(format_args!("call panicked: {}", message)),
+            DispatchError::Dropped => f.write_str("call dropped before it ran"),
+            DispatchError::Timeout => f.write_str("timed out waiting for the call")
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+/// How much of a frame a drain may use, at least one call running per drain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Budget {
+    pub calls: usize,
+    pub time: Duration,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget { calls: 256, time: Duration::from_millis(2) }
+    }
+}
+
+/// Outcome of a drain.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DrainStats {
+    pub ran: usize,
+    /// Calls cancelled while queued
+    pub skipped: usize,
+    /// Calls left for the next drain
+    pub remaining: usize,
+}
+
+enum State<T> {
+    Pending(Option<Waker>),
+    Running(Option<Waker>),
+    Done(Result<T, DispatchError>),
+    Taken,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    done: Condvar,
+    cancelled: AtomicBool,
+}
+
+impl<T> Shared<T> {
+    fn pending() -> Shared<T> {
+        Shared {
+            state: Mutex::new(State::Pending(None)),
+            done: Condvar::new(),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    //Checked under the state lock, so a call is either cancelled or started
+    fn start(&self) -> bool {
+        let mut state = self.state.lock().expect("lock poisoned");
+        if self.cancelled.load(Ordering::Acquire) {
+            return false;
+        }
+        if let State::Pending(waker) = &mut *state {
+            *state = State::Running(waker.take());
+        }
+        true
+    }
+
+    fn complete(&self, result: Result<T, DispatchError>) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        match std::mem::replace(&mut *state, State::Done(result)) {
+            State::Pending(Some(waker)) | State::Running(Some(waker)) => waker.wake(),
+            _ => {}
+        }
+        self.done.notify_all();
+    }
+}
+
+trait Task: Send {
+    /// Runs the call unless it was cancelled, returning whether it ran.
+    fn run(self: Box<Self>) -> bool;
+    fn drop_unrun(self: Box<Self>, error: DispatchError);
+}
+
+struct Call<T, F> {
+    call: F,
+    shared: Arc<Shared<T>>,
+}
+
+impl<T, F> Task for Call<T, F> where F: FnOnce() -> T + Send, T: Send {
+    fn run(self: Box<Self>) -> bool {
+        if !self.shared.start() {
+            self.shared.complete(Err(DispatchError::Cancelled));
+            return false;
+        }
+        self.shared.complete(run(self.call));
+        true
+    }
+
+    fn drop_unrun(self: Box<Self>, error: DispatchError) {
+        self.shared.complete(Err(error));
+    }
+}
+
+fn run<T, F>(call: F) -> Result<T, DispatchError> where F: FnOnce() -> T {
+    catch_unwind(AssertUnwindSafe(call)).map_err(|panic| {
+        let message = panic.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown panic"));
+        DispatchError::Panicked(message)
+    })
+}
+
+/// Result of a dispatched call, awaited as a `Future` or waited for from a blocking thread.
+pub struct CallHandle<T> {
+    shared: Arc<Shared<T>>,
+    queue: Option<Arc<Queue>>,
+}
+
+impl<T> CallHandle<T> {
+    fn done(result: Result<T, DispatchError>) -> CallHandle<T> {
+        CallHandle {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::Done(result)),
+                done: Condvar::new(),
+                cancelled: AtomicBool::new(false),
+            }),
+            queue: None,
+        }
+    }
+
+    /// Cancels the call if it hasn't started yet, returning whether it was still queued.
+    pub fn cancel(&self) -> bool {
+        let state = self.shared.state.lock().expect("lock poisoned");
+        let queued = matches!(*state, State::Pending(_));
+        if queued {
+            self.shared.cancelled.store(true, Ordering::Release);
+        }
+        queued
+    }
+
+    pub fn is_done(&self) -> bool {
+        !matches!(*self.shared.state.lock().expect("lock poisoned"), State::Pending(_) | State::Running(_))
+    }
+
+    /// The result if the call is over, `None` while it is queued or running, or once it was taken.
+    pub fn try_take(&self) -> Option<Result<T, DispatchError>> {
+        let mut state = self.shared.state.lock().expect("lock poisoned");
+        match std::mem::replace(&mut *state, State::Taken) {
+            State::Done(result) => Some(result),
+            other => {
+                *state = other;
+                None
+            }
+        }
+    }
+
+    /// Blocks until the call is over.
+    /// On the thread calls are dispatched to, the queue runs up to the call instead.
+    pub fn wait(self) -> Result<T, DispatchError> {
+        self.wait_until(None)
+    }
+
+    /// Like `wait`, cancelling the call when it didn't start in time.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<T, DispatchError> {
+        self.wait_until(Some(Instant::now() + timeout))
+    }
+
+    fn wait_until(self, deadline: Option<Instant>) -> Result<T, DispatchError> {
+        let mut state = self.shared.state.lock().expect("lock poisoned");
+        loop {
+            match std::mem::replace(&mut *state, State::Taken) {
+                State::Done(result) => return result,
+                State::Taken => panic!("call result already taken"),
+                pending => *state = pending
+            }
+            //Queued before the thread was bound, nothing else would ever drain it
+            if let (State::Pending(_), Some(queue)) = (&*state, &self.queue) {
+                if queue.is_owner() {
+                    drop(state);
+                    let ran = queue.run_next().is_some();
+                    state = self.shared.state.lock().expect("lock poisoned");
+                    if ran {
+                        continue;
+                    }
+                }
+            }
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        if let State::Pending(_) = *state {
+                            self.shared.cancelled.store(true, Ordering::Release);
+                        }
+                        return Err(DispatchError::Timeout);
+                    }
+                    state = self.shared.done.wait_timeout(state, deadline - now).expect("lock poisoned").0;
+                }
+                None => state = self.shared.done.wait(state).expect("lock poisoned")
+            }
+        }
+    }
+}
+
+impl<T> Future for CallHandle<T> {
+    type Output = Result<T, DispatchError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().expect("lock poisoned");
+        match std::mem::replace(&mut *state, State::Taken) {
+            State::Done(result) => Poll::Ready(result),
+            State::Taken => panic!("call result already taken"),
+            State::Pending(_) => {
+                *state = State::Pending(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            State::Running(_) => {
+                *state = State::Running(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: Mutex<VecDeque<Box<dyn Task>>>,
+    owner: Mutex<Option<ThreadId>>,
+}
+
+impl Queue {
+    fn is_owner(&self) -> bool {
+        *self.owner.lock().expect("lock poisoned") == Some(std::thread::current().id())
+    }
+
+    /// Runs or skips the oldest call, returning whether it ran, or `None` if there was none.
+    fn run_next(&self) -> Option<bool> {
+        //Calls may submit others, so the lock isn't held while they run
+        let task = self.tasks.lock().expect("lock poisoned").pop_front()?;
+        Some(task.run())
+    }
+}
+
+/// Queue of calls to run on one thread, drained a frame at a time.
+/// Calls submitted from that thread run right away, as waiting for them there would never end.
+#[derive(Default)]
+pub struct Dispatcher {
+    queue: Arc<Queue>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Dispatcher {
+        Dispatcher::default()
+    }
+
+    pub fn submit<T, F>(&self, call: F) -> CallHandle<T> where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+        if self.queue.is_owner() {
+            return CallHandle::done(run(call));
+        }
+        let shared = Arc::new(Shared::pending());
+        self.queue.tasks.lock().expect("lock poisoned").push_back(Box::new(Call { call, shared: shared.clone() }));
+        CallHandle { shared, queue: Some(self.queue.clone()) }
+    }
+
+    /// Calls waiting to run, cancelled ones included.
+    pub fn len(&self) -> usize {
+        self.queue.tasks.lock().expect("lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Makes the current thread the one calls are dispatched to, without waiting for its first drain.
+    pub fn bind(&self) {
+        *self.queue.owner.lock().expect("lock poisoned") = Some(std::thread::current().id());
+    }
+
+    /// Runs queued calls in order until the queue is empty or the budget is spent,
+    /// making the current thread the one calls are dispatched to.
+    pub fn drain(&self, budget: Budget) -> DrainStats {
+        self.bind();
+        let start = Instant::now();
+        let mut stats = DrainStats::default();
+        loop {
+            if stats.ran > 0 && (stats.ran >= budget.calls || start.elapsed() >= budget.time) {
+                break;
+            }
+            match self.queue.run_next() {
+                Some(true) => stats.ran += 1,
+                Some(false) => stats.skipped += 1,
+                None => break
+            }
+        }
+        stats.remaining = self.len();
+        stats
+    }
+
+    /// Fails every queued call with `DispatchError::Dropped`, returning how many there were.
+    pub fn clear(&self) -> usize {
+        let tasks = std::mem::take(&mut *self.queue.tasks.lock().expect("lock poisoned"));
+        let count = tasks.len();
+        for task in tasks {
+            task.drop_unrun(DispatchError::Dropped);
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc::channel;
+
+    use super::*;
+
+    fn budget(calls: usize) -> Budget {
+        Budget { calls, time: Duration::from_secs(1) }
+    }
+
+    //Drains a frame at a time on its own thread until `stop` is set
+    fn game_thread(dispatcher: Arc<Dispatcher>, stop: Arc<AtomicBool>) -> std::thread::JoinHandle<usize> {
+        std::thread::spawn(move || {
+            dispatcher.bind();
+            let mut frames = 0;
+            while !stop.load(Ordering::Acquire) {
+                //Scripts of the game thread submit and wait inline
+                assert_eq!(dispatcher.submit(move || frames).wait(), Ok(frames));
+                dispatcher.drain(budget(2));
+                frames += 1;
+                std::thread::sleep(Duration::from_micros(200));
+            }
+            frames
+        })
+    }
+
+    #[test]
+    fn frame_loop() {
+        let dispatcher = Arc::new(Dispatcher::new());
+        let stop = Arc::new(AtomicBool::new(false));
+        let ran = Arc::new(AtomicUsize::new(0));
+        let game = game_thread(dispatcher.clone(), stop.clone());
+        let workers: Vec<_> = (0..3).map(|worker| {
+            let dispatcher = dispatcher.clone();
+            let ran = ran.clone();
+            std::thread::spawn(move || {
+                (0..20).map(|i| {
+                    let ran = ran.clone();
+                    dispatcher.submit(move || {
+                        ran.fetch_add(1, Ordering::AcqRel);
+                        worker * 100 + i
+                    }).wait().unwrap()
+                }).sum::<usize>()
+            })
+        }).collect();
+        let sums: Vec<usize> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+        stop.store(true, Ordering::Release);
+        let frames = game.join().unwrap();
+        assert_eq!(sums, vec![190, 2190, 4190]);
+        assert_eq!(ran.load(Ordering::Acquire), 60);
+        //At most two calls a frame
+        assert!(frames >= 30);
+        assert!(dispatcher.is_empty());
+    }
+
+    #[test]
+    fn cancel_only_while_queued() {
+        let dispatcher = Arc::new(Dispatcher::new());
+        let queued = dispatcher.submit(|| 1);
+        assert!(queued.cancel());
+        assert!(!queued.is_done());
+
+        let (started_tx, started_rx) = channel();
+        let (release_tx, release_rx) = channel::<()>();
+        let running = dispatcher.submit(move || {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            2
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+        let game = game_thread(dispatcher.clone(), stop.clone());
+        started_rx.recv().unwrap();
+        assert!(!running.is_done());
+        assert!(running.try_take().is_none());
+        assert!(!running.cancel());
+        release_tx.send(()).unwrap();
+        assert_eq!(running.wait(), Ok(2));
+        assert_eq!(queued.wait(), Err(DispatchError::Cancelled));
+        stop.store(true, Ordering::Release);
+        game.join().unwrap();
+    }
+
+    #[test]
+    fn running_call_wakes_its_future() {
+        struct Flag(AtomicBool);
+
+        impl std::task::Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let dispatcher = Arc::new(Dispatcher::new());
+        let (started_tx, started_rx) = channel();
+        let (release_tx, release_rx) = channel::<()>();
+        let mut handle = dispatcher.submit(move || {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            3
+        });
+        let drainer = {
+            let dispatcher = dispatcher.clone();
+            std::thread::spawn(move || dispatcher.drain(Budget::default()))
+        };
+        started_rx.recv().unwrap();
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Pending);
+        release_tx.send(()).unwrap();
+        assert_eq!(drainer.join().unwrap().ran, 1);
+        assert!(flag.0.load(Ordering::Acquire));
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Ready(Ok(3)));
+    }
+
+    #[test]
+    fn wait_on_the_bound_thread() {
+        let dispatcher = Dispatcher::new();
+        //Submitted by the game thread before it was bound, so queued
+        let first = dispatcher.submit(|| 1);
+        let cancelled = dispatcher.submit(|| 2);
+        let second = dispatcher.submit(|| 3);
+        let after = dispatcher.submit(|| 4);
+        assert_eq!(dispatcher.len(), 4);
+        assert!(cancelled.cancel());
+        dispatcher.bind();
+        assert!(dispatcher.submit(|| 5).is_done());
+        assert_eq!(second.wait(), Ok(3));
+        assert_eq!(first.try_take(), Some(Ok(1)));
+        assert_eq!(cancelled.try_take(), Some(Err(DispatchError::Cancelled)));
+        assert_eq!(dispatcher.len(), 1);
+        assert_eq!(after.wait_timeout(Duration::ZERO), Ok(4));
+        assert!(dispatcher.is_empty());
+    }
+
+    #[test]
+    fn timeout_cancels_queued_call() {
+        let dispatcher = Dispatcher::new();
+        let handle = dispatcher.submit(|| 1);
+        assert_eq!(handle.wait_timeout(Duration::from_millis(1)), Err(DispatchError::Timeout));
+        assert_eq!(dispatcher.drain(Budget::default()), DrainStats { ran: 0, skipped: 1, remaining: 0 });
+    }
+
+    #[test]
+    fn budget_panics_and_clear() {
+        let dispatcher = Arc::new(Dispatcher::new());
+        let handles = {
+            let dispatcher = dispatcher.clone();
+            std::thread::spawn(move || (0..6).map(|i| dispatcher.submit(move || {
+                if i == 1 {
+                    panic!("call {}", i);
+                }
+                i
+            })).collect::<Vec<_>>()).join().unwrap()
+        };
+        assert_eq!(dispatcher.drain(budget(2)), DrainStats { ran: 2, skipped: 0, remaining: 4 });
+        //A spent time budget still runs one call
+        assert_eq!(dispatcher.drain(Budget { calls: 10, time: Duration::ZERO }).ran, 1);
+        assert_eq!(dispatcher.clear(), 3);
+        let results: Vec<_> = handles.into_iter().map(|h| h.wait()).collect();
+        assert_eq!(results[0], Ok(0));
+        assert_eq!(results[1], Err(DispatchError::Panicked(String::from("call 1"))));
+        assert_eq!(results[2], Ok(2));
+        assert!(results[3..].iter().all(|r| *r == Err(DispatchError::Dropped)));
+    }
+}
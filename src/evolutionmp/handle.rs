@@ -0,0 +1,98 @@
+use crate::stack::{NativeStackReader, NativeStackValue, NativeStackWriter};
+
+pub type Handle = u32;
+
+pub trait Handleable {
+    fn from_handle(handle: Handle) -> Option<Self> where Self: Sized;
+    fn get_handle(&self) -> Handle;
+}
+
+#[macro_export]
+macro_rules! impl_handle {
+    ($ty:ident) => {
+        impl crate::handle::Handleable for $ty {
+            fn from_handle(handle: crate::handle::Handle) -> Option<Self> where Self: Sized {
+                if handle == 0 || handle == u32::MAX {
+                    None
+                } else {
+                    Some($ty { handle })
+                }
+            }
+
+            fn get_handle(&self) -> crate::handle::Handle {
+                self.handle
+            }
+        }
+    };
+}
+
+impl<H> NativeStackValue for H where H: Handleable + Sized {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        H::from_handle(stack.read::<Handle>()).expect("got zero handle")
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self.get_handle());
+    }
+}
+
+impl<H> NativeStackValue for Option<H> where H: Handleable + Sized {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        H::from_handle(stack.read::<Handle>())
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        let handle = self.expect("cannot pass invalid handle as native arg").get_handle();
+        stack.write(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Door {
+        handle: Handle
+    }
+
+    crate::impl_handle!(Door);
+
+    fn read<T>(word: u64) -> T where T: NativeStackValue {
+        NativeStackReader::new(&[word]).read()
+    }
+
+    #[test]
+    fn invalid_handles() {
+        assert_eq!(Door::from_handle(0), None);
+        assert_eq!(Door::from_handle(u32::MAX), None);
+        assert_eq!(Door::from_handle(7), Some(Door { handle: 7 }));
+        assert_eq!(read::<Option<Door>>(0), None);
+        assert_eq!(read::<Option<Door>>(u32::MAX as u64), None);
+        //Only the low half of the word holds the handle
+        assert_eq!(read::<Option<Door>>(0xFFFF_FFFF_0000_0042), Some(Door { handle: 0x42 }));
+        assert_eq!(read::<Door>(12), Door { handle: 12 });
+    }
+
+    #[test]
+    fn written_handles() {
+        let mut stack = [0; 2];
+        let mut writer = NativeStackWriter::new(&mut stack);
+        assert_eq!(writer.write(Door { handle: 3 }), 1);
+        assert_eq!(writer.write(Some(Door { handle: 0x10000 })), 1);
+        assert_eq!(stack, [3, 0x10000]);
+    }
+
+    #[test]
+    #[should_panic(expected = "got zero handle")]
+    fn zero_handle_result() {
+        read::<Door>(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot pass invalid handle")]
+    fn missing_handle_arg() {
+        let mut stack = [0; 1];
+        NativeStackWriter::new(&mut stack).write(None::<Door>);
+    }
+}
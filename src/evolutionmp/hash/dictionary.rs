@@ -0,0 +1,346 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::hash::{init_fns, joaat, objects, vtables, Hash};
+
+/// Directory of user name lists, relative to the launcher directory.
+pub const HASHES_DIR: &str = "hashes";
+
+lazy_static! {
+    static ref DICTIONARY: RwLock<HashDictionary> = RwLock::new(HashDictionary::new());
+}
+
+/// Kind of name a hash stands for; lookups prefer earlier categories when names collide.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    Model,
+    Weapon,
+    VTable,
+    Label,
+    Stat,
+    InitFn,
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 7] = [
+        Category::Model, Category::Weapon, Category::VTable,
+        Category::Label, Category::Stat, Category::InitFn, Category::Other
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Model => "model",
+            Category::Weapon => "weapon",
+            Category::VTable => "vtable",
+            Category::Label => "label",
+            Category::Stat => "stat",
+            Category::InitFn => "init_fn",
+            Category::Other => "other"
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Category {
+    type Err = DictionaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Category::ALL.iter().find(|c| c.name() == s).cloned()
+            .ok_or_else(|| DictionaryError::UnknownCategory(String::from(s)))
+    }
+}
+
+#[derive(Debug)]
+pub enum DictionaryError {
+    Io(PathBuf, std::io::Error),
+    UnknownCategory(String),
+}
+
+impl Display for DictionaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DictionaryError::Io(path, e) => f.write_fmt(format_args!("{}: {}", path.display(), e)),
+            DictionaryError::UnknownCategory(name) => f.write_fmt(format_args!("unknown hash category `{}`", name))
+        }
+    }
+}
+
+impl std::error::Error for DictionaryError {}
+
+/// Reverse lookup of hashes to the names they were computed from.
+#[derive(Debug, Clone, Default)]
+pub struct HashDictionary {
+    names: HashMap<Hash, Vec<(Category, Cow<'static, str>)>>,
+    len: usize,
+    //Whether model names fall back to the built-in object table instead of being copied
+    objects: bool,
+}
+
+impl HashDictionary {
+    pub fn new() -> HashDictionary {
+        HashDictionary::default()
+    }
+
+    /// Adds a name under its `joaat`, returning the hash.
+    pub fn insert<N>(&mut self, category: Category, name: N) -> Hash where N: Into<Cow<'static, str>> {
+        let name = name.into();
+        let hash = joaat(&name);
+        self.insert_hashed(category, hash, name);
+        hash
+    }
+
+    /// Adds a name whose hash is already known, as in the built-in tables.
+    pub fn insert_hashed<N>(&mut self, category: Category, hash: Hash, name: N) where N: Into<Cow<'static, str>> {
+        let name = name.into();
+        let entries = self.names.entry(hash).or_default();
+        if !entries.iter().any(|(c, n)| *c == category && *n == name) {
+            entries.push((category, name));
+            self.len += 1;
+        }
+    }
+
+    pub fn extend<I>(&mut self, category: Category, table: I) where I: IntoIterator<Item=(Hash, &'static str)> {
+        for (hash, name) in table {
+            self.insert_hashed(category, hash, name);
+        }
+    }
+
+    pub fn merge(&mut self, other: HashDictionary) {
+        self.objects |= other.objects;
+        for (hash, entries) in other.names {
+            for (category, name) in entries {
+                self.insert_hashed(category, hash, name);
+            }
+        }
+    }
+
+    pub fn get(&self, hash: Hash) -> Option<&str> {
+        if let Some(name) = self.object(hash) {
+            return Some(name);
+        }
+        self.names.get(&hash)?.iter()
+            .min_by_key(|(category, _)| *category)
+            .map(|(_, name)| name.as_ref())
+    }
+
+    pub fn get_in(&self, category: Category, hash: Hash) -> Option<&str> {
+        if category == Category::Model {
+            if let Some(name) = self.object(hash) {
+                return Some(name);
+            }
+        }
+        self.names.get(&hash)?.iter()
+            .find(|(c, _)| *c == category)
+            .map(|(_, name)| name.as_ref())
+    }
+
+    fn object(&self, hash: Hash) -> Option<&'static str> {
+        if self.objects {
+            objects::get(&(hash.0 as i32))
+        } else {
+            None
+        }
+    }
+
+    /// Every known name, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item=Cow<'_, str>> {
+        let objects = if self.objects { Some(objects::iter()) } else { None };
+        self.names.values().flatten().map(|(_, name)| Cow::Borrowed(name.as_ref()))
+            .chain(objects.into_iter().flatten().map(|(_, name)| Cow::Owned(name)))
+    }
+
+    /// Number of names, a hash with names in several categories counting once per name.
+    pub fn len(&self) -> usize {
+        if self.objects {
+            self.len + objects::len()
+        } else {
+            self.len
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a name list: one name per line, `#` starting a comment
+    /// and a `[category]` line switching the category of the following names.
+    pub fn parse_list(&mut self, list: &str, mut category: Category) -> Result<usize, DictionaryError> {
+        let mut added = 0;
+        for line in list.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                category = section.trim().parse()?;
+                continue;
+            }
+            self.insert(category, String::from(line));
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Adds every `*.txt` list of a directory, the file name selecting the category
+    /// (`weapons.txt`, `vtable.txt`), returning the lists that couldn't be read.
+    pub fn load_dir<P>(&mut self, dir: P) -> Vec<DictionaryError> where P: AsRef<Path> {
+        let dir = dir.as_ref();
+        let mut errors = Vec::new();
+        let mut files = match std::fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+                .collect::<Vec<_>>(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return errors,
+            Err(e) => {
+                errors.push(DictionaryError::Io(dir.to_path_buf(), e));
+                return errors;
+            }
+        };
+        files.sort();
+        for path in files {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let category = stem.parse().or_else(|_| stem.trim_end_matches('s').parse())
+                .unwrap_or(Category::Other);
+            let result = std::fs::read_to_string(&path)
+                .map_err(|e| DictionaryError::Io(path.clone(), e))
+                .and_then(|list| self.parse_list(&list, category));
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+}
+
+/// Dictionary of the names compiled into the client.
+pub fn builtin() -> HashDictionary {
+    let mut dictionary = HashDictionary::new();
+    dictionary.objects = true;
+    dictionary.extend(Category::VTable, vtables::V_TABLES.iter().cloned());
+    dictionary.extend(Category::InitFn, init_fns::KNOWN_INIT_FNS.iter().cloned());
+    dictionary
+}
+
+/// Swaps the dictionary used to display hashes.
+pub fn replace(dictionary: HashDictionary) {
+    *DICTIONARY.write().expect("lock poisoned") = dictionary;
+}
+
+pub fn with<F, R>(f: F) -> R where F: FnOnce(&HashDictionary) -> R {
+    f(&DICTIONARY.read().expect("lock poisoned"))
+}
+
+pub fn lookup(hash: Hash) -> Option<String> {
+    with(|d| d.get(hash).map(String::from))
+}
+
+pub fn lookup_in(category: Category, hash: Hash) -> Option<String> {
+    with(|d| d.get_in(category, hash).map(String::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_categories() {
+        let mut dictionary = HashDictionary::new();
+        let added = dictionary.parse_list("
+            # weapons first
+            weapon_pistol
+            [label]
+            CELL_EMAIL_BOD   # trailing comment
+            [ stat ]
+            mp0_total_playing_time
+
+            plain
+        ", Category::Weapon).unwrap();
+        assert_eq!(added, 4);
+        assert_eq!(dictionary.len(), 4);
+        assert_eq!(dictionary.get_in(Category::Weapon, joaat("weapon_pistol")), Some("weapon_pistol"));
+        assert_eq!(dictionary.get_in(Category::Label, joaat("CELL_EMAIL_BOD")), Some("CELL_EMAIL_BOD"));
+        assert_eq!(dictionary.get_in(Category::Stat, joaat("plain")), Some("plain"));
+        assert_eq!(dictionary.get_in(Category::Weapon, joaat("plain")), None);
+        assert!(matches!(dictionary.parse_list("[nope]\nname", Category::Other),
+                         Err(DictionaryError::UnknownCategory(c)) if c == "nope"));
+        assert_eq!("init_fn".parse::<Category>().ok(), Some(Category::InitFn));
+        assert!(Category::ALL.iter().all(|c| c.to_string().parse::<Category>().ok() == Some(*c)));
+    }
+
+    #[test]
+    fn category_priority() {
+        let mut dictionary = HashDictionary::new();
+        let hash = dictionary.insert(Category::Other, "shared");
+        dictionary.insert_hashed(Category::Label, hash, "label name");
+        dictionary.insert_hashed(Category::Weapon, hash, "weapon name");
+        dictionary.insert_hashed(Category::Weapon, hash, "weapon name");
+        assert_eq!(dictionary.len(), 3);
+        assert_eq!(dictionary.get(hash), Some("weapon name"));
+        assert_eq!(dictionary.get_in(Category::Label, hash), Some("label name"));
+        assert_eq!(dictionary.get_in(Category::Other, hash), Some("shared"));
+        assert_eq!(dictionary.get_in(Category::Model, hash), None);
+        assert_eq!(dictionary.get(Hash(1)), None);
+    }
+
+    #[test]
+    fn builtin_objects_take_precedence() {
+        let adder = joaat("adder");
+        let mut dictionary = builtin();
+        assert!(dictionary.len() > objects::len());
+        dictionary.insert_hashed(Category::Weapon, adder, "not a model");
+        assert_eq!(dictionary.get(adder), Some("adder"));
+        assert_eq!(dictionary.get_in(Category::Model, adder), Some("adder"));
+        assert_eq!(dictionary.get_in(Category::Weapon, adder), Some("not a model"));
+        assert_eq!(dictionary.get_in(Category::VTable, joaat("ARTFeedbackInterface")), Some("ARTFeedbackInterface"));
+        assert_eq!(dictionary.names().filter(|n| n == "adder").count(), 1);
+    }
+
+    #[test]
+    fn merge() {
+        let mut first = HashDictionary::new();
+        first.insert(Category::Label, "a");
+        first.insert(Category::Label, "b");
+        let mut second = HashDictionary::new();
+        second.insert(Category::Label, "b");
+        second.insert(Category::Stat, "b");
+        second.insert(Category::Other, "c");
+        first.merge(second);
+        assert_eq!(first.len(), 4);
+        assert_eq!(first.get(joaat("b")), Some("b"));
+        assert_eq!(first.get_in(Category::Stat, joaat("b")), Some("b"));
+        assert_eq!(first.get(joaat("adder")), None);
+        first.merge(builtin());
+        assert_eq!(first.get(joaat("adder")), Some("adder"));
+        assert_eq!(first.get(joaat("c")), Some("c"));
+    }
+
+    #[test]
+    fn load_dir() {
+        let dir = std::env::temp_dir().join(format!("evolutionmp-hashes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("weapons.txt"), "weapon_pistol\n").unwrap();
+        std::fs::write(dir.join("stat.txt"), "mp0_wallet_balance\n").unwrap();
+        std::fs::write(dir.join("misc.txt"), "misc_name\n").unwrap();
+        std::fs::write(dir.join("broken.txt"), "[unknown]\n").unwrap();
+        std::fs::write(dir.join("ignored.json"), "ignored\n").unwrap();
+        let mut dictionary = HashDictionary::new();
+        let errors = dictionary.load_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(dictionary.len(), 3);
+        assert_eq!(dictionary.get_in(Category::Weapon, joaat("weapon_pistol")), Some("weapon_pistol"));
+        assert_eq!(dictionary.get_in(Category::Stat, joaat("mp0_wallet_balance")), Some("mp0_wallet_balance"));
+        assert_eq!(dictionary.get_in(Category::Other, joaat("misc_name")), Some("misc_name"));
+        assert!(HashDictionary::new().load_dir(dir.join("missing")).is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::hash::{const_joaat_table, Hash};
+
+pub(crate) static KNOWN_INIT_FNS: [(Hash, &'static str); 289] = const_joaat_table([
+    "AmbientLights",
+    "AnimBlackboard",
+    "Audio",
+    "BackgroundScripts",
+    "CActionManager",
+    "CAgitatedManager",
+    "CAmbientAnimationManager",
+    "CAmbientAudioManager",
+    "CAmbientModelSetManager",
+    "CAnimBlackboard",
+    "CAppDataMgr",
+    "CAssistedMovementRouteStore",
+    "CBoatChaseDirector",
+    "CBuses",
+    "CBusySpinner",
+    "CCheat",
+    "CCheckCRCs",
+    "CClipDictionaryStoreInterface",
+    "CClock",
+    "CCombatDirector",
+    "CCombatInfoMgr",
+    "CCompEntity",
+    "CConditionalAnimManager",
+    "CContentExport",
+    "CContentSearch",
+    "CControl",
+    "CControlMgr",
+    "CControllerLabelMgr",
+    "CCover",
+    "CCoverFinder",
+    "CCredits",
+    "CCrimeInformationManager",
+    "CCullZones",
+    "CDLCScript",
+    "CDecoratorInterface",
+    "CDispatchData",
+    "CEventDataManager",
+    "CExpensiveProcessDistributer",
+    "CExplosionManager",
+    "CExtraContent",
+    "CExtraContentWrapper",
+    "CExtraContentWrapper::Shutdown",
+    "CExtraContentWrapper::ShutdownStart",
+    "CExtraMetadataMgr",
+    "CExtraMetadataMgr::ClassInit",
+    "CExtraMetadataMgr::ClassShutdown",
+    "CExtraMetadataMgr::ShutdownDLCMetaFiles",
+    "CFlyingVehicleAvoidanceManager",
+    "CFocusEntityMgr",
+    "CFrontendStatsMgr",
+    "CGameLogic",
+    "CGameSituation",
+    "CGameStreamMgr",
+    "CGameWorld",
+    "CGameWorldHeightMap",
+    "CGameWorldWaterHeight",
+    "CGarages",
+    "CGenericGameStorage",
+    "CGestureManager",
+    "CGps",
+    "CGtaAnimManager",
+    "CHandlingDataMgr",
+    "CInstanceListAssetLoader::Init",
+    "CInstanceListAssetLoader::Shutdown",
+    "CIplCullBox",
+    "CJunctions",
+    "CLODLightManager",
+    "CLODLights",
+    "CLadderMetadataManager",
+    "CLoadingScreens",
+    "CMapAreas",
+    "CMapZoneManager",
+    "CMessages",
+    "CMiniMap",
+    "CModelInfo",
+    "CModelInfo::Init",
+    "CMovieMeshManager",
+    "CMultiplayerGamerTagHud",
+    "CNetRespawnMgr",
+    "CNetwork",
+    "CNetworkTelemetry",
+    "CNewHud",
+    "CObjectPopulationNY",
+    "COcclusion",
+    "CParaboloidShadow",
+    "CPathFind",
+    "CPathServer::InitBeforeMapLoaded",
+    "CPathServer::InitSession",
+    "CPathServer::ShutdownSession",
+    "CPathZoneManager",
+    "CPatrolRoutes",
+    "CPauseMenu",
+    "CPed",
+    "CPedAILodManager",
+    "CPedGeometryAnalyser",
+    "CPedModelInfo",
+    "CPedPopulation",
+    "CPedPopulation::ResetPerFrameScriptedMu",
+    "CPedPopulation::ResetPerFrameScriptedMultipiers",
+    "CPedPropsMgr",
+    "CPedVariationPack",
+    "CPedVariationStream",
+    "CPerformance",
+    "CPhoneMgr",
+    "CPhotoManager",
+    "CPhysics",
+    "CPickupDataManager",
+    "CPickupManager",
+    "CPlantMgr",
+    "CPlayStats",
+    "CPlayerSwitch",
+    "CPopCycle",
+    "CPopZones",
+    "CPopulationStreaming",
+    "CPopulationStreamingWrapper",
+    "CPortal",
+    "CPortalTracker",
+    "CPostScan",
+    "CPrecincts",
+    "CPrioritizedClipSetRequestManager",
+    "CPrioritizedClipSetStreamer",
+    "CProcObjectMan",
+    "CProceduralInfo",
+    "CProfileSettings",
+    "CRandomEventManager",
+    "CRecentlyPilotedAircraft",
+    "CRenderPhaseCascadeShadowsInterface",
+    "CRenderTargetMgr",
+    "CRenderThreadInterface",
+    "CRenderer",
+    "CReportMenu",
+    "CRestart",
+    "CRiots",
+    "CRoadBlock",
+    "CScaleformMgr",
+    "CScenarioActionManager",
+    "CScenarioManager",
+    "CScenarioManager::ResetExclusiveScenari",
+    "CScenarioManager::ResetExclusiveScenarioGroup",
+    "CScenarioPointManager",
+    "CScenarioPointManagerInitSession",
+    "CScene",
+    "CSceneStreamerMgr::PreScanUpdate",
+    "CScriptAreas",
+    "CScriptCars",
+    "CScriptDebug",
+    "CScriptEntities",
+    "CScriptHud",
+    "CScriptPedAIBlips",
+    "CScriptPeds",
+    "CScriptedGunTaskMetadataMgr",
+    "CShaderHairSort",
+    "CShaderLib",
+    "CSituationalClipSetStreamer",
+    "CSky",
+    "CSlownessZonesManager",
+    "CSprite2d",
+    "CStaticBoundsStore",
+    "CStatsMgr",
+    "CStreaming",
+    "CStreamingRequestList",
+    "CStuntJumpManager",
+    "CTVPlaylistManager",
+    "CTacticalAnalysis",
+    "CTask",
+    "CTaskClassInfoManager",
+    "CTaskRecover",
+    "CTexLod",
+    "CText",
+    "CThePopMultiplierAreas",
+    "CTheScripts",
+    "CTimeCycle",
+    "CTrafficLights",
+    "CTrain",
+    "CTuningManager",
+    "CUserDisplay",
+    "CVehicleAILodManager",
+    "CVehicleChaseDirector",
+    "CVehicleCombatAvoidanceArea",
+    "CVehicleDeformation",
+    "CVehicleMetadataMgr",
+    "CVehicleModelInfo",
+    "CVehiclePopulation",
+    "CVehiclePopulation::ResetPerFrameScript",
+    "CVehiclePopulation::ResetPerFrameScriptedMultipiers",
+    "CVehicleRecordingMgr",
+    "CVehicleVariationInstance",
+    "CVisualEffects",
+    "CWarpManager",
+    "CWaypointRecording",
+    "CWeaponManager",
+    "CWitnessInformationManager",
+    "CWorldPoints",
+    "CZonedAssetManager",
+    "Common",
+    "CreateFinalScreenRenderPhaseList",
+    "Credits",
+    "CutSceneManager",
+    "CutSceneManagerWrapper",
+    "FacialClipSetGroupManager",
+    "FireManager",
+    "FirstPersonProp",
+    "FirstPersonPropCam",
+    "Game",
+    "GenericGameStoragePhotoGallery",
+    "INSTANCESTORE",
+    "ImposedTxdCleanup",
+    "InitSystem",
+    "Kick",
+    "LightEntityMgr",
+    "Lights",
+    "MeshBlendManager",
+    "Misc",
+    "NewHud",
+    "Occlusion",
+    "PauseMenu",
+    "Ped",
+    "PedHeadShotManager",
+    "PedModelInfo",
+    "PedPopulation",
+    "PlantsMgr::UpdateBegin",
+    "PlantsMgr::UpdateEnd",
+    "Population",
+    "PostFX",
+    "PostFx",
+    "Pre-vis",
+    "Prioritized",
+    "Proc",
+    "ProcessAfterCameraUpdate",
+    "ProcessAfterMovement",
+    "ProcessPedsEarlyAfterCameraUpdate",
+    "Render",
+    "ResetSceneLights",
+    "Run",
+    "Script",
+    "ScriptHud",
+    "ShaderLib::Update",
+    "Situational",
+    "SocialClubMenu",
+    "Streaming",
+    "UI3DDrawManager",
+    "UIWorldIconManager",
+    "Update",
+    "VehPopulation",
+    "VideoPlayback",
+    "VideoPlaybackThumbnailManager",
+    "VideoPlaybackThumbnails",
+    "Viewport",
+    "ViewportSystemInit",
+    "ViewportSystemInitLevel",
+    "ViewportSystemShutdown",
+    "ViewportSystemShutdownLevel",
+    "Visibility",
+    "Visual",
+    "WarningScreen",
+    "Water",
+    "WaterHeightSim",
+    "World",
+    "audNorthAudioEngine",
+    "audNorthAudioEngineDLC",
+    "cStoreScreenMgr",
+    "camManager",
+    "decorators",
+    "fwAnimDirector",
+    "fwClipSetManager",
+    "fwClothStore",
+    "fwDrawawableStoreWrapper",
+    "fwDwdStore",
+    "fwDwdStoreWrapper",
+    "fwExpressionSetManager",
+    "fwFacialClipSetGroupManager",
+    "fwFragmentStoreWrapper",
+    "fwMapTypesStore",
+    "fwMetaDataStore",
+    "fwTimer",
+    "fwTxdStore",
+    "perfClearingHouse",
+    "strStreamingEngine::SubmitDeferredAsyncPlacementRequests",
+    "CAnimSceneManager",
+    "CTextInputBox",
+    "CMultiplayerChat",
+    "CCreditsText",
+    "CReplayMgr",
+    "CReplayCoordinator",
+    "CMousePointer",
+    "CVideoEditorUI",
+    "CVideoEditorInterface",
+    "VideoRecording",
+    "WatermarkRenderer",
+]);
@@ -0,0 +1,295 @@
+use serde_derive::{Serialize, Deserialize};
+
+pub mod dictionary;
+mod init_fns;
+mod objects;
+mod typed;
+mod vtables;
+
+pub use typed::{InitFnHash, LabelHash, ModelHash, StatHash, VTableHash, WeaponHash};
+
+#[repr(transparent)]
+#[derive(Default, Copy, Clone, PartialEq, Debug, Eq, Hash, PartialOrd, Serialize, Deserialize)]
+pub struct Hash(pub u32);
+
+impl std::fmt::Display for Hash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        dictionary::with(|d| match d.get(*self) {
+            Some(name) => f.write_str(name),
+            None => f.write_fmt(format_args!("0x{:08X}", self.0))
+        })
+    }
+}
+
+impl AsRef<dyn Hashable> for Hash {
+    fn as_ref(&self) -> &(dyn Hashable + 'static) {
+        self
+    }
+}
+
+/// Hashes the UTF-8 bytes of `s`, lower-casing ASCII letters only like the game does.
+pub fn joaat<S>(s: S) -> Hash where S: AsRef<str> {
+    const_joaat_bytes(s.as_ref().as_bytes(), true)
+}
+
+//Case-sensitive version of joaat
+pub fn joaat_cs<S>(s: S) -> Hash where S: AsRef<str> {
+    const_joaat_bytes(s.as_ref().as_bytes(), false)
+}
+
+/// Compile-time version of `joaat`, lower-casing ASCII letters only like the game does.
+pub const fn const_joaat(s: &str) -> Hash {
+    const_joaat_bytes(s.as_bytes(), true)
+}
+
+/// Compile-time version of `joaat_cs`.
+pub const fn const_joaat_cs(s: &str) -> Hash {
+    const_joaat_bytes(s.as_bytes(), false)
+}
+
+const fn const_joaat_bytes(bytes: &[u8], lowercase: bool) -> Hash {
+    let mut hash = 0u32;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = if lowercase { bytes[i].to_ascii_lowercase() } else { bytes[i] };
+        hash = mix(hash, b);
+        i += 1;
+    }
+    Hash(finalize(hash))
+}
+
+const fn mix(hash: u32, b: u8) -> u32 {
+    let hash = hash.wrapping_add(b as u32);
+    let hash = hash.wrapping_add(hash << 10);
+    hash ^ (hash >> 6)
+}
+
+const fn finalize(hash: u32) -> u32 {
+    let hash = hash.wrapping_add(hash << 3);
+    let hash = hash ^ (hash >> 11);
+    hash.wrapping_add(hash << 15)
+}
+
+/// Pairs every name of a static table with its precomputed `joaat`.
+pub const fn const_joaat_table<const N: usize>(names: [&'static str; N]) -> [(Hash, &'static str); N] {
+    let mut table = [(Hash(0), ""); N];
+    let mut i = 0;
+    while i < N {
+        table[i] = (const_joaat(names[i]), names[i]);
+        i += 1;
+    }
+    table
+}
+
+/// Hashes a string literal at compile time, `joaat!(cs "...")` keeping the case
+/// and `joaat!(LabelHash: "...")` producing a typed hash.
+#[macro_export]
+macro_rules! joaat {
+    ($s:literal) => {{
+        const HASH: $crate::hash::Hash = $crate::hash::const_joaat($s);
+        HASH
+    }};
+    (cs $s:literal) => {{
+        const HASH: $crate::hash::Hash = $crate::hash::const_joaat_cs($s);
+        HASH
+    }};
+    ($ty:ty: $s:literal) => {{
+        const HASH: $ty = <$ty>::from_raw($crate::hash::const_joaat($s));
+        HASH
+    }};
+}
+
+/// Hashes a string fed in parts, the way the game builds partial hashes before finalizing them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct JoaatHasher {
+    state: u32,
+    lowercase: bool,
+}
+
+impl JoaatHasher {
+    /// Case-insensitive hasher, matching `joaat`.
+    pub const fn new() -> JoaatHasher {
+        JoaatHasher { state: 0, lowercase: true }
+    }
+
+    /// Hasher matching `joaat_cs`.
+    pub const fn case_sensitive() -> JoaatHasher {
+        JoaatHasher { state: 0, lowercase: false }
+    }
+
+    /// Continues from a partial hash computed by the game or by `partial`.
+    pub const fn from_partial(partial: u32, lowercase: bool) -> JoaatHasher {
+        JoaatHasher { state: partial, lowercase }
+    }
+
+    pub fn update(&mut self, s: &str) -> &mut JoaatHasher {
+        self.update_bytes(s.as_bytes());
+        self
+    }
+
+    fn update_bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            let b = if self.lowercase { b.to_ascii_lowercase() } else { *b };
+            self.state = mix(self.state, b);
+        }
+    }
+
+    /// State before the final mix, what the game stores as a partial hash.
+    pub const fn partial(&self) -> u32 {
+        self.state
+    }
+
+    pub const fn finish(&self) -> Hash {
+        Hash(finalize(self.state))
+    }
+}
+
+impl Default for JoaatHasher {
+    fn default() -> Self {
+        JoaatHasher::new()
+    }
+}
+
+/// Feeds raw bytes only: `Hash` implementations add their own bytes, `str` appending `0xFF`,
+/// so names should be hashed with `update` or `write!` to match `joaat`.
+impl std::hash::Hasher for JoaatHasher {
+    fn finish(&self) -> u64 {
+        JoaatHasher::finish(self).0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.update_bytes(bytes)
+    }
+}
+
+//Lets composite names be hashed with `write!` instead of formatting them into a string first
+impl std::fmt::Write for JoaatHasher {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.update(s);
+        Ok(())
+    }
+}
+
+pub trait Hashable {
+    fn joaat(&self) -> Hash;
+
+    fn to_string(&self) -> String {
+        format!("{}", self.joaat())
+    }
+}
+
+impl Hashable for Hash {
+    fn joaat(&self) -> Hash {
+        *self
+    }
+}
+
+impl Hashable for JoaatHasher {
+    fn joaat(&self) -> Hash {
+        self.finish()
+    }
+}
+
+impl Hashable for &str {
+    fn joaat(&self) -> Hash {
+        crate::hash::joaat(self)
+    }
+
+    fn to_string(&self) -> String {
+        String::from(*self)
+    }
+}
+
+impl<'a, H> Hashable for &'a H where H: Hashable {
+    fn joaat(&self) -> Hash {
+        (*self).joaat()
+    }
+
+    fn to_string(&self) -> String {
+        (*self).to_string()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_hashes() {
+        assert_eq!(joaat("adder"), Hash(0xB779A091));
+        assert_eq!(joaat("ADDER"), Hash(0xB779A091));
+        assert_eq!(joaat_cs("ADDER"), const_joaat_cs("ADDER"));
+        assert_ne!(joaat_cs("ADDER"), joaat("ADDER"));
+        assert_eq!(joaat(""), Hash(0));
+        assert_eq!(joaat!("adder"), Hash(0xB779A091));
+        assert_eq!(joaat!(cs "ADDER"), joaat_cs("ADDER"));
+    }
+
+    #[test]
+    fn non_ascii_hashes_bytes() {
+        //Only ASCII letters are lower-cased, other characters being hashed as their UTF-8 bytes
+        for s in ["Édition", "straße", "ÀÉ_ß", "日本"] {
+            assert_eq!(joaat(s), const_joaat(s), "{}", s);
+            assert_eq!(joaat_cs(s), const_joaat_cs(s), "{}", s);
+        }
+        assert_ne!(joaat("É"), joaat("é"));
+        assert_eq!(joaat("Éa"), joaat("ÉA"));
+    }
+
+    #[test]
+    fn const_matches_runtime_over_objects() {
+        let mut count = 0;
+        let mut unmatched = Vec::new();
+        for (hash, name) in objects::iter() {
+            assert_eq!(const_joaat(&name), joaat(&name), "{}", name);
+            assert_eq!(joaat_cs(name.to_ascii_uppercase()), const_joaat_cs(&name.to_ascii_uppercase()), "{}", name);
+            if joaat(&name) != Hash(hash as u32) {
+                unmatched.push(name);
+            }
+            count += 1;
+        }
+        assert_eq!(count, objects::len());
+        //Entries of the list whose real name is unknown
+        assert_eq!(unmatched, vec!["double_", "sc1_00c_platform"]);
+    }
+
+    #[test]
+    fn hasher_matches_joaat() {
+        for s in ["", "adder", "ADDER", "Prop_Bench_01a", "straße_É"] {
+            assert_eq!(JoaatHasher::new().update(s).finish(), joaat(s), "{}", s);
+            assert_eq!(JoaatHasher::case_sensitive().update(s).finish(), joaat_cs(s), "{}", s);
+        }
+    }
+
+    #[test]
+    fn partial_hashes() {
+        let mut prefix = JoaatHasher::new();
+        prefix.update("WEAPON_");
+        let partial = prefix.partial();
+        let mut hasher = JoaatHasher::from_partial(partial, true);
+        assert_eq!(hasher.update("PISTOL").finish(), joaat("weapon_pistol"));
+        assert_eq!(hasher.finish(), joaat("WEAPON_PISTOL"));
+        let mut hasher = JoaatHasher::from_partial(JoaatHasher::case_sensitive().update("Ab").partial(), false);
+        assert_eq!(hasher.update("Cd").finish(), joaat_cs("AbCd"));
+        assert_ne!(hasher.finish(), joaat_cs("abcd"));
+        assert_eq!(JoaatHasher::new().partial(), 0);
+        assert_eq!(JoaatHasher::default(), JoaatHasher::new());
+    }
+
+    #[test]
+    fn write_and_std_hasher() {
+        use std::fmt::Write;
+        use std::hash::{Hash as _, Hasher};
+
+        let mut hasher = JoaatHasher::new();
+        write!(hasher, "{}_{:02}", "PROP_BENCH", 1).unwrap();
+        assert_eq!(hasher.finish(), joaat("prop_bench_01"));
+
+        let mut hasher = JoaatHasher::new();
+        hasher.write(b"ADDER");
+        assert_eq!(Hasher::finish(&hasher), joaat("adder").0 as u64);
+        //`str::hash` appends a terminator byte, giving another hash than `update`
+        let mut hasher = JoaatHasher::new();
+        "adder".hash(&mut hasher);
+        assert_eq!(hasher.partial(), mix(JoaatHasher::new().update("adder").partial(), 0xFF));
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+//Generated by build.rs from objects.list: hashes sorted for binary search,
+//the index of each hash's name and front-coded names in blocks of BLOCK
+include!(concat!(env!("OUT_DIR"), "/objects.rs"));
+
+lazy_static! {
+    static ref DECODED: RwLock<HashMap<usize, &'static str>> = RwLock::new(HashMap::new());
+}
+
+/// Name of a model hash, decoded on first lookup and kept for the lifetime of the process.
+pub(crate) fn get(hash: &i32) -> Option<&'static str> {
+    let index = INDICES[HASHES.binary_search(hash).ok()?] as usize;
+    if let Some(name) = DECODED.read().expect("lock poisoned").get(&index) {
+        return Some(name);
+    }
+    let mut decoded = DECODED.write().expect("lock poisoned");
+    Some(decoded.entry(index).or_insert_with(|| Box::leak(decode(index).into_boxed_str())))
+}
+
+pub(crate) fn len() -> usize {
+    HASHES.len()
+}
+
+/// Every entry in name order, decoding each name once.
+pub(crate) fn iter() -> impl Iterator<Item=(i32, String)> {
+    let mut hashes = vec![0; HASHES.len()];
+    for (hash, index) in HASHES.iter().zip(INDICES.iter()) {
+        hashes[*index as usize] = *hash;
+    }
+    let mut name = String::new();
+    let mut offset = 0;
+    hashes.into_iter().map(move |hash| {
+        offset = read_name(offset, &mut name);
+        (hash, name.clone())
+    })
+}
+
+fn decode(index: usize) -> String {
+    let mut name = String::new();
+    let mut offset = BLOCKS[index / BLOCK] as usize;
+    for _ in 0..=index % BLOCK {
+        offset = read_name(offset, &mut name);
+    }
+    name
+}
+
+//Replaces `name` with the entry at `offset`, which shares a prefix with it, returning the next offset
+fn read_name(offset: usize, name: &mut String) -> usize {
+    let shared = NAMES[offset] as usize;
+    let len = NAMES[offset + 1] as usize;
+    let suffix = &NAMES[offset + 2..offset + 2 + len];
+    name.truncate(shared);
+    name.push_str(std::str::from_utf8(suffix).expect("invalid object name"));
+    offset + 2 + len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list() -> Vec<(i32, &'static str)> {
+        include_str!("objects.list").lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let (hash, name) = l.split_once(' ').unwrap();
+                (u32::from_str_radix(hash, 16).unwrap() as i32, name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_entry_decodes() {
+        let list = list();
+        assert_eq!(len(), list.len());
+        for (hash, name) in &list {
+            assert_eq!(get(hash), Some(*name), "0x{:08X}", hash);
+        }
+        let mut names = list.iter().map(|(_, name)| *name).collect::<Vec<_>>();
+        names.sort();
+        assert!(iter().map(|(_, name)| name).eq(names.iter().map(|n| n.to_string())));
+        assert_eq!(get(&0), None);
+    }
+
+    #[test]
+    fn table_size() {
+        let raw = list().iter().map(|(_, name)| 4 + name.len()).sum::<usize>();
+        let table = std::mem::size_of_val(&HASHES) + std::mem::size_of_val(&INDICES)
+            + std::mem::size_of_val(&BLOCKS) + NAMES.len();
+        assert!(table < raw, "object table: {} entries, {} bytes instead of {}", len(), table, raw);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::{Display, Formatter};
+
+use serde_derive::{Serialize, Deserialize};
+
+use crate::hash::{const_joaat, Hash, Hashable};
+use crate::hash::dictionary::{self, Category};
+
+macro_rules! typed_hash {
+    ($(#[$meta:meta])* $name:ident, $category:expr) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(Default, Copy, Clone, PartialEq, Debug, Eq, Hash, PartialOrd, Serialize, Deserialize)]
+        pub struct $name(pub Hash);
+
+        impl $name {
+            pub const CATEGORY: Category = $category;
+
+            /// Wraps an untyped hash, such as one returned by a native, trusted to be of this kind.
+            pub const fn from_raw(hash: Hash) -> $name {
+                $name(hash)
+            }
+
+            /// Hashes a name at compile time when used in a constant.
+            pub const fn from_name(name: &str) -> $name {
+                $name(const_joaat(name))
+            }
+
+            /// Name of the hash, looked up only among names of this kind.
+            pub fn name(&self) -> Option<String> {
+                dictionary::lookup_in($category, self.0)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                dictionary::with(|d| match d.get_in($category, self.0) {
+                    Some(name) => f.write_str(name),
+                    None => f.write_fmt(format_args!("0x{:08X}", (self.0).0))
+                })
+            }
+        }
+
+        impl Hashable for $name {
+            fn joaat(&self) -> Hash {
+                self.0
+            }
+
+            fn to_string(&self) -> String {
+                format!("{}", self)
+            }
+        }
+
+        impl From<$name> for Hash {
+            fn from(hash: $name) -> Hash {
+                hash.0
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(name: &str) -> $name {
+                $name(crate::hash::joaat(name))
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(name: &String) -> $name {
+                $name(crate::hash::joaat(name))
+            }
+        }
+    };
+}
+
+typed_hash!(
+    /// Hash of a ped, vehicle or object model name.
+    ModelHash, Category::Model
+);
+typed_hash!(
+    /// Hash of a weapon name, such as `weapon_pistol`.
+    WeaponHash, Category::Weapon
+);
+typed_hash!(
+    /// Hash of a text label of the translation tables.
+    LabelHash, Category::Label
+);
+typed_hash!(
+    /// Hash of a class name, as found in the type info of game objects.
+    VTableHash, Category::VTable
+);
+typed_hash!(
+    /// Hash of a stat name, such as `MP0_STAMINA`.
+    StatHash, Category::Stat
+);
+typed_hash!(
+    /// Hash of an init function name of the game's startup sequence.
+    InitFnHash, Category::InitFn
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::joaat;
+
+    fn model<M>(model: M) -> ModelHash where M: Into<ModelHash> {
+        model.into()
+    }
+
+    #[test]
+    fn conversions() {
+        let adder = ModelHash::from_name("adder");
+        assert_eq!(adder, ModelHash::from_raw(joaat("adder")));
+        assert_eq!(model("ADDER"), adder);
+        assert_eq!(model(&String::from("adder")), adder);
+        assert_eq!(model(adder), adder);
+        assert_eq!(Hash::from(adder), joaat("adder"));
+        assert_eq!(crate::joaat!(WeaponHash: "weapon_pistol"), WeaponHash::from("WEAPON_PISTOL"));
+        assert_eq!(adder.joaat(), adder.0);
+    }
+
+    #[test]
+    fn names_by_category() {
+        dictionary::replace(dictionary::builtin());
+        let adder = ModelHash::from_name("adder");
+        assert_eq!(format!("{}", adder), "adder");
+        assert_eq!(format!("{}", WeaponHash::from_raw(adder.0)), format!("0x{:08X}", adder.0 .0));
+        assert_eq!(WeaponHash::from_raw(adder.0).name(), None);
+        assert_eq!(ModelHash::CATEGORY, Category::Model);
+    }
+}
@@ -1,11 +1,6 @@
-use crate::hash::{Hashable, Hash};
-use std::collections::HashMap;
+use crate::hash::{const_joaat_table, Hash};
 
-lazy_static! {
-    pub static ref V_TABLES: HashMap<Hash, &'static str> = _V_TABLES.iter().map(|f| (f.joaat(), *f)).collect();
-}
-
-static _V_TABLES: [&'static str; 5524] = [
+pub(crate) static V_TABLES: [(Hash, &'static str); 5524] = const_joaat_table([
     "ARTFeedbackInterface",
     "ARTFeedbackInterfaceGta",
     "ASFSinkWriterWrapper",
@@ -5530,4 +5525,4 @@ static _V_TABLES: [&'static str; 5524] = [
     "ActionTable_StealthKills",
     "ActionTable_Vfx",
     "ActionTable_FacialAnimSets"
-];
\ No newline at end of file
+]);
@@ -0,0 +1,304 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::stack::{NativeCallContext, NativeFunction};
+
+pub type HookId = u64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HookAction {
+    Continue,
+    /// Skips the native and the hooks that would have run after, keeping the results written so far
+    Skip,
+}
+
+pub type PreHook = dyn Fn(&mut NativeCallContext) -> HookAction + Send + Sync;
+pub type ReplaceHook = dyn Fn(&mut NativeCallContext, NativeFunction) + Send + Sync;
+pub type PostHook = dyn Fn(&mut NativeCallContext) + Send + Sync;
+
+#[derive(Clone)]
+pub enum Hook {
+    /// Runs before the native
+    Pre(Arc<PreHook>),
+    /// Runs in place of the native, given the handler it replaces
+    Replace(Arc<ReplaceHook>),
+    /// Runs after the native
+    Post(Arc<PostHook>),
+}
+
+#[derive(Clone)]
+struct Entry {
+    id: HookId,
+    priority: i32,
+    hook: Hook,
+}
+
+thread_local! {
+    //Calls made from hooks go straight to the native
+    static IN_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+//Clears `IN_HOOK` even if a hook panics, or the thread would bypass hooks from then on
+struct InHook;
+
+impl InHook {
+    fn enter() -> InHook {
+        IN_HOOK.with(|h| h.set(true));
+        InHook
+    }
+}
+
+impl Drop for InHook {
+    fn drop(&mut self) {
+        IN_HOOK.with(|h| h.set(false));
+    }
+}
+
+/// Whether the current thread is running a hook.
+pub fn in_hook() -> bool {
+    IN_HOOK.with(|h| h.get())
+}
+
+/// Hooks of natives, by hash.
+pub struct Hooks {
+    next_id: HookId,
+    len: usize,
+    entries: HashMap<u64, Vec<Entry>>,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Hooks::new()
+    }
+}
+
+impl Hooks {
+    pub fn new() -> Hooks {
+        Hooks { next_id: 1, len: 0, entries: HashMap::new() }
+    }
+
+    /// Hooks a native, lower priorities running first and hooks of the same priority in the order they were added.
+    /// Only the first replacement of a native runs.
+    pub fn add(&mut self, hash: u64, priority: i32, hook: Hook) -> HookId {
+        let id = self.next_id;
+        self.next_id += 1;
+        let entries = self.entries.entry(hash).or_default();
+        let position = entries.iter().position(|e| e.priority > priority).unwrap_or(entries.len());
+        entries.insert(position, Entry { id, priority, hook });
+        self.len += 1;
+        id
+    }
+
+    /// Removes a hook, returning the native it was hooking.
+    pub fn remove(&mut self, id: HookId) -> Option<u64> {
+        let (hash, index) = self.entries.iter()
+            .find_map(|(hash, entries)| entries.iter().position(|e| e.id == id).map(|i| (*hash, i)))?;
+        let entries = self.entries.get_mut(&hash).expect("hooked native vanished");
+        entries.remove(index);
+        if entries.is_empty() {
+            self.entries.remove(&hash);
+        }
+        self.len -= 1;
+        Some(hash)
+    }
+
+    /// Removes every hook of a native, returning how many there were.
+    pub fn clear(&mut self, hash: u64) -> usize {
+        let removed = self.entries.remove(&hash).map_or(0, |entries| entries.len());
+        self.len -= removed;
+        removed
+    }
+
+    pub fn is_hooked(&self, hash: u64) -> bool {
+        self.entries.contains_key(&hash)
+    }
+
+    /// Hooked natives, sorted.
+    pub fn hooked(&self) -> Vec<u64> {
+        let mut hashes = self.entries.keys().cloned().collect::<Vec<_>>();
+        hashes.sort();
+        hashes
+    }
+
+    /// Hooks of every native.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Calls a native through its hooks, natives called from a hook running unhooked.
+/// The hooks are copied before running, so they can add or remove hooks.
+pub fn call(hooks: &RwLock<Hooks>, hash: u64, handler: NativeFunction, context: &mut NativeCallContext) {
+    if in_hook() {
+        return handler(context);
+    }
+    let entries = match hooks.read().expect("lock poisoned").entries.get(&hash) {
+        Some(entries) => entries.clone(),
+        None => return handler(context)
+    };
+
+    let _guard = InHook::enter();
+    let skipped = entries.iter().any(|e| match &e.hook {
+        Hook::Pre(hook) => hook(context) == HookAction::Skip,
+        _ => false
+    });
+    if skipped {
+        return;
+    }
+    match entries.iter().find_map(|e| if let Hook::Replace(hook) = &e.hook { Some(hook) } else { None }) {
+        Some(hook) => hook(context, handler),
+        None => handler(context)
+    }
+    for entry in &entries {
+        if let Hook::Post(hook) = &entry.hook {
+            hook(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+
+    const HASH: u64 = 0x4B2A3E1F;
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    lazy_static! {
+        static ref NESTED: RwLock<Hooks> = RwLock::new(Hooks::new());
+    }
+
+    fn log(event: &str) {
+        LOG.with(|l| l.borrow_mut().push(String::from(event)));
+    }
+
+    fn take_log() -> Vec<String> {
+        LOG.with(|l| l.borrow_mut().drain(..).collect())
+    }
+
+    extern fn native(_: *mut NativeCallContext) {
+        log("native");
+    }
+
+    fn pre(name: &'static str, action: HookAction) -> Hook {
+        Hook::Pre(Arc::new(move |_| {
+            log(name);
+            action
+        }))
+    }
+
+    fn replace(name: &'static str) -> Hook {
+        Hook::Replace(Arc::new(move |context, handler| {
+            log(name);
+            handler(context);
+        }))
+    }
+
+    fn post(name: &'static str) -> Hook {
+        Hook::Post(Arc::new(move |_| log(name)))
+    }
+
+    fn run(hooks: &RwLock<Hooks>, hash: u64) -> Vec<String> {
+        let mut args = [0; 32];
+        let mut returns = [0; 3];
+        call(hooks, hash, native, &mut NativeCallContext::new(&mut args, &mut returns, 0));
+        take_log()
+    }
+
+    #[test]
+    fn ordering() {
+        let mut hooks = Hooks::new();
+        hooks.add(HASH, 10, pre("late", HookAction::Continue));
+        hooks.add(HASH, -5, pre("early", HookAction::Continue));
+        hooks.add(HASH, 10, pre("late again", HookAction::Continue));
+        hooks.add(HASH, 0, post("post"));
+        hooks.add(HASH, -1, post("earlier post"));
+        let hooks = RwLock::new(hooks);
+        assert_eq!(run(&hooks, HASH), vec!["early", "late", "late again", "native", "earlier post", "post"]);
+        assert_eq!(run(&hooks, HASH + 1), vec!["native"]);
+    }
+
+    #[test]
+    fn skip() {
+        let mut hooks = Hooks::new();
+        hooks.add(HASH, 0, pre("first", HookAction::Continue));
+        hooks.add(HASH, 1, pre("skipping", HookAction::Skip));
+        hooks.add(HASH, 2, pre("after skip", HookAction::Continue));
+        hooks.add(HASH, 0, replace("replace"));
+        hooks.add(HASH, 0, post("post"));
+        let hooks = RwLock::new(hooks);
+        assert_eq!(run(&hooks, HASH), vec!["first", "skipping"]);
+    }
+
+    #[test]
+    fn first_replacement() {
+        let mut hooks = Hooks::new();
+        hooks.add(HASH, 5, replace("second"));
+        hooks.add(HASH, 5, replace("third"));
+        hooks.add(HASH, 1, replace("first"));
+        hooks.add(HASH, 0, post("post"));
+        let hooks = RwLock::new(hooks);
+        assert_eq!(run(&hooks, HASH), vec!["first", "native", "post"]);
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let mut hooks = Hooks::new();
+        let a = hooks.add(HASH, 0, post("a"));
+        let b = hooks.add(HASH, 0, post("b"));
+        hooks.add(HASH + 1, 0, post("other"));
+        assert_eq!(hooks.len(), 3);
+        assert_eq!(hooks.hooked(), vec![HASH, HASH + 1]);
+
+        assert_eq!(hooks.remove(a), Some(HASH));
+        assert_eq!(hooks.remove(a), None);
+        assert!(hooks.is_hooked(HASH));
+        let mut hooks = RwLock::new(hooks);
+        assert_eq!(run(&hooks, HASH), vec!["native", "b"]);
+
+        let hooks_mut = hooks.get_mut().unwrap();
+        assert_eq!(hooks_mut.remove(b), Some(HASH));
+        assert!(!hooks_mut.is_hooked(HASH));
+        assert_eq!(hooks_mut.clear(HASH + 1), 1);
+        assert_eq!(hooks_mut.clear(HASH + 1), 0);
+        assert!(hooks_mut.is_empty());
+        assert!(hooks_mut.hooked().is_empty());
+        assert_eq!(run(&hooks, HASH + 1), vec!["native"]);
+    }
+
+    #[test]
+    fn nested_calls_bypass_hooks() {
+        NESTED.write().unwrap().add(HASH, 0, Hook::Replace(Arc::new(|context, handler| {
+            log("replace");
+            assert!(in_hook());
+            //The replaced native called again from its hook isn't hooked a second time
+            call(&NESTED, HASH, handler, context);
+        })));
+        assert_eq!(run(&NESTED, HASH), vec!["replace", "native"]);
+        assert!(!in_hook());
+    }
+
+    #[test]
+    fn panicking_hook() {
+        let hooks = RwLock::new(Hooks::new());
+        hooks.write().unwrap().add(HASH, 0, Hook::Pre(Arc::new(|_| panic!("hook failed"))));
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| run(&hooks, HASH)));
+        assert!(result.is_err());
+        assert!(!in_hook());
+        take_log();
+
+        hooks.write().unwrap().clear(HASH);
+        hooks.write().unwrap().add(HASH, 0, pre("pre", HookAction::Continue));
+        assert_eq!(run(&hooks, HASH), vec!["pre", "native"]);
+    }
+}
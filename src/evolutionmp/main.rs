@@ -19,12 +19,31 @@ use colored::Colorize;
 use fern::colors::{Color, ColoredLevelConfig};
 use fern::Dispatch;
 
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", test))]
 pub use client::*;
 
 #[cfg(target_os = "windows")]
 mod client;
+#[cfg(all(test, not(target_os = "windows")))]
+#[path = "client/stub/mod.rs"]
+mod client;
+pub mod arena;
+pub mod backend;
+pub mod color;
+pub mod crossmap;
+pub mod dispatch;
+pub mod handle;
 pub mod hash;
+pub mod hook;
+pub mod manifest;
+pub mod nativedb;
+pub mod nativetable;
+pub mod patch;
+pub mod profile;
+pub mod signature;
+pub mod stack;
+pub mod trace;
+pub mod version;
 
 pub const LOG_ROOT: &'static str = "root";
 pub const LOG_PANIC: &'static str = "panic";
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::signature::{Pattern, PatternError};
+use crate::signature::bindings::{self, Binding, Step};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Replacement for a compiled-in binding, unset fields keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BindingOverride {
+    pub pattern: Option<String>,
+    pub offset: Option<isize>,
+    /// Pointer-follow steps taken after the offset
+    pub follow: Option<Vec<Step>>,
+}
+
+/// Replacement for a patch applied by the client, unset fields keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PatchOverride {
+    pub enabled: Option<bool>,
+    /// Relocates the patch, `offset` and `follow` are applied to the match of this pattern
+    pub pattern: Option<String>,
+    pub offset: Option<isize>,
+    pub follow: Option<Vec<Step>>,
+    pub expected: Option<String>,
+    pub bytes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ManifestSection {
+    pub bindings: BTreeMap<String, BindingOverride>,
+    pub patches: BTreeMap<String, PatchOverride>,
+    /// Refuses patterns matching more than once instead of binding their lowest match
+    pub strict_patterns: Option<bool>,
+}
+
+/// Overrides shared by all game builds, plus sections applied on top of them for a single build number.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    #[serde(flatten)]
+    pub common: ManifestSection,
+    pub builds: BTreeMap<String, ManifestSection>,
+}
+
+/// Where a binding or a patch should be looked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target<'a> {
+    pub pattern: &'a str,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnknownBinding(String),
+    InvalidPattern(String, PatternError),
+    InvalidBytes(String),
+    MissingPattern(String),
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::Io(e) => e.fmt(f),
+            ManifestError::Json(e) => e.fmt(f),
+            ManifestError::UnknownBinding(name) => f.write_fmt(format_args!("unknown binding `{}`", name)),
+            ManifestError::InvalidPattern(name, e) => f.write_fmt(format_args!("invalid pattern for `{}`: {}", name, e)),
+            ManifestError::InvalidBytes(name) => f.write_fmt(format_args!("patch `{}` bytes must be exact hex bytes", name)),
+            ManifestError::MissingPattern(name) => f.write_fmt(format_args!("patch `{}` is relocated without a pattern", name))
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(e: std::io::Error) -> Self {
+        ManifestError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ManifestError {
+    fn from(e: serde_json::Error) -> Self {
+        ManifestError::Json(e)
+    }
+}
+
+impl Manifest {
+    /// Reads the manifest, a missing file being an empty manifest.
+    pub fn load<P>(path: P) -> Result<Manifest, ManifestError> where P: AsRef<Path> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        Manifest::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(json: &str) -> Result<Manifest, ManifestError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Drops every invalid entry, returning why it was dropped.
+    pub fn prune_invalid(&mut self) -> Vec<ManifestError> {
+        let mut errors = self.common.prune_invalid();
+        for section in self.builds.values_mut() {
+            errors.extend(section.prune_invalid());
+        }
+        errors
+    }
+
+    /// Merges the section of `build`, if any, over the common one.
+    pub fn select(&self, build: Option<&str>) -> ManifestSection {
+        let mut section = self.common.clone();
+        if let Some(specific) = build.and_then(|b| self.builds.get(b)) {
+            section.merge(specific);
+        }
+        section
+    }
+}
+
+impl ManifestSection {
+    pub fn merge(&mut self, other: &ManifestSection) {
+        merge_field(&mut self.strict_patterns, &other.strict_patterns);
+        for (name, b) in &other.bindings {
+            let entry = self.bindings.entry(name.clone()).or_default();
+            merge_field(&mut entry.pattern, &b.pattern);
+            merge_field(&mut entry.offset, &b.offset);
+            merge_field(&mut entry.follow, &b.follow);
+        }
+        for (name, p) in &other.patches {
+            let entry = self.patches.entry(name.clone()).or_default();
+            merge_field(&mut entry.enabled, &p.enabled);
+            merge_field(&mut entry.pattern, &p.pattern);
+            merge_field(&mut entry.offset, &p.offset);
+            merge_field(&mut entry.follow, &p.follow);
+            merge_field(&mut entry.expected, &p.expected);
+            merge_field(&mut entry.bytes, &p.bytes);
+        }
+    }
+
+    pub fn prune_invalid(&mut self) -> Vec<ManifestError> {
+        let mut errors = Vec::new();
+        self.bindings.retain(|name, b| {
+            let result = if bindings::find(name).is_none() {
+                Err(ManifestError::UnknownBinding(name.clone()))
+            } else {
+                check_pattern(name, b.pattern.as_deref())
+            };
+            result.map_err(|e| errors.push(e)).is_ok()
+        });
+        self.patches.retain(|name, p| {
+            let result = check_pattern(name, p.pattern.as_deref())
+                .and_then(|_| check_pattern(name, p.expected.as_deref()))
+                .and_then(|_| match &p.bytes {
+                    Some(bytes) if parse_bytes(bytes).is_none() => Err(ManifestError::InvalidBytes(name.clone())),
+                    _ => Ok(())
+                })
+                .and_then(|_| {
+                    if p.pattern.is_none() && (p.offset.is_some() || p.follow.is_some()) {
+                        Err(ManifestError::MissingPattern(name.clone()))
+                    } else {
+                        Ok(())
+                    }
+                });
+            result.map_err(|e| errors.push(e)).is_ok()
+        });
+        errors
+    }
+
+    /// Applies the override of `binding`, if any, to its compiled-in pattern and steps.
+    pub fn binding<'a>(&'a self, binding: &'a Binding) -> Target<'a> {
+        let o = self.bindings.get(binding.name);
+        let pattern = o.and_then(|o| o.pattern.as_deref()).unwrap_or(binding.pattern);
+        let offset = o.and_then(|o| o.offset).unwrap_or(binding.offset);
+        let mut steps = vec![Step::Add(offset)];
+        match o.and_then(|o| o.follow.as_ref()) {
+            Some(follow) => steps.extend(follow),
+            None => steps.extend(binding.follow())
+        }
+        Target { pattern, steps }
+    }
+
+    pub fn patch(&self, name: &str) -> Option<&PatchOverride> {
+        self.patches.get(name)
+    }
+}
+
+impl PatchOverride {
+    pub fn is_disabled(&self) -> bool {
+        self.enabled == Some(false)
+    }
+
+    /// New location of the patch, if it is relocated.
+    pub fn target(&self) -> Option<Target<'_>> {
+        let pattern = self.pattern.as_deref()?;
+        let mut steps = vec![Step::Add(self.offset.unwrap_or(0))];
+        steps.extend(self.follow.iter().flatten());
+        Some(Target { pattern, steps })
+    }
+
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        self.bytes.as_deref().and_then(parse_bytes)
+    }
+}
+
+fn merge_field<T>(field: &mut Option<T>, other: &Option<T>) where T: Clone {
+    if other.is_some() {
+        *field = other.clone();
+    }
+}
+
+fn check_pattern(name: &str, pattern: Option<&str>) -> Result<(), ManifestError> {
+    match pattern {
+        Some(pattern) => Pattern::parse(pattern).map(|_| ())
+            .map_err(|e| ManifestError::InvalidPattern(String::from(name), e)),
+        None => Ok(())
+    }
+}
+
+//Patch bytes share the pattern syntax but can't contain wildcards
+fn parse_bytes(bytes: &str) -> Option<Vec<u8>> {
+    let pattern = Pattern::parse(bytes).ok()?;
+    if !pattern.captures().is_empty() {
+        return None;
+    }
+    pattern.bytes().iter().map(|b| b.fixed()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::bindings::{GAME_STATE, SKIP_INIT};
+
+    const JSON: &str = r#"{
+        "bindings": {
+            "GAME_STATE": { "pattern": "83 3D | [rel32+1] ? 75 17" },
+            "SKIP_INIT": { "offset": -12 }
+        },
+        "patches": {
+            "focus_pause": { "enabled": false }
+        },
+        "builds": {
+            "2060": {
+                "bindings": { "GAME_STATE": { "offset": 4, "follow": [{ "relative": 4 }, "call", { "add": -2 }] } },
+                "patches": { "focus_pause": { "enabled": true, "pattern": "0F 95", "bytes": "90 90" } },
+                "strict_patterns": true
+            }
+        }
+    }"#;
+
+    #[test]
+    fn step_serde() {
+        let steps = vec![Step::Add(-3), Step::Relative(4), Step::Call];
+        let json = serde_json::to_string(&steps).unwrap();
+        assert_eq!(json, r#"[{"add":-3},{"relative":4},"call"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Step>>(&json).unwrap(), steps);
+        assert!(serde_json::from_str::<Step>(r#"{"jump":1}"#).is_err());
+    }
+
+    #[test]
+    fn select_merges_build_over_common() {
+        let manifest = Manifest::parse(JSON).unwrap();
+        let common = manifest.select(None);
+        assert_eq!(common, manifest.select(Some("1604")));
+        assert_eq!(common.strict_patterns, None);
+        assert!(common.patch("focus_pause").unwrap().is_disabled());
+
+        let section = manifest.select(Some("2060"));
+        assert_eq!(section.strict_patterns, Some(true));
+        let game_state = &section.bindings["GAME_STATE"];
+        assert_eq!(game_state.pattern.as_deref(), Some("83 3D | [rel32+1] ? 75 17"));
+        assert_eq!(game_state.offset, Some(4));
+        assert_eq!(section.bindings["SKIP_INIT"].offset, Some(-12));
+        let focus_pause = section.patch("focus_pause").unwrap();
+        assert!(!focus_pause.is_disabled());
+        assert_eq!(focus_pause.bytes(), Some(vec![0x90, 0x90]));
+        assert_eq!(focus_pause.target(), Some(Target { pattern: "0F 95", steps: vec![Step::Add(0)] }));
+    }
+
+    #[test]
+    fn binding_targets() {
+        let section = Manifest::parse(JSON).unwrap().select(Some("2060"));
+        assert_eq!(section.binding(&GAME_STATE), Target {
+            pattern: "83 3D | [rel32+1] ? 75 17",
+            steps: vec![Step::Add(4), Step::Relative(4), Step::Call, Step::Add(-2)],
+        });
+        assert_eq!(section.binding(&SKIP_INIT), Target {
+            pattern: SKIP_INIT.pattern,
+            steps: vec![Step::Add(-12), Step::Call],
+        });
+        let empty = ManifestSection::default();
+        assert_eq!(empty.binding(&SKIP_INIT), Target { pattern: SKIP_INIT.pattern, steps: SKIP_INIT.steps() });
+    }
+
+    #[test]
+    fn prune_invalid() {
+        let mut manifest = Manifest::parse(r#"{
+            "bindings": {
+                "GAME_STATE": { "offset": 1 },
+                "NOT_A_BINDING": { "offset": 1 },
+                "SKIP_INIT": { "pattern": "ZZ" }
+            },
+            "patches": {
+                "ok": { "expected": "0F 95 ?" },
+                "bad_bytes": { "bytes": "90 ?" },
+                "bad_expected": { "expected": "0F [x]" },
+                "no_pattern": { "offset": 2 }
+            },
+            "builds": { "2060": { "bindings": { "OTHER": {} } } }
+        }"#).unwrap();
+        let errors = manifest.prune_invalid().iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors.contains(&String::from("unknown binding `NOT_A_BINDING`")));
+        assert!(errors.contains(&String::from("unknown binding `OTHER`")));
+        assert!(errors.contains(&String::from("patch `bad_bytes` bytes must be exact hex bytes")));
+        assert!(errors.contains(&String::from("patch `no_pattern` is relocated without a pattern")));
+        assert!(errors.iter().any(|e| e.starts_with("invalid pattern for `SKIP_INIT`")));
+        assert!(errors.iter().any(|e| e.starts_with("invalid pattern for `bad_expected`")));
+        assert_eq!(manifest.common.bindings.keys().collect::<Vec<_>>(), vec!["GAME_STATE"]);
+        assert_eq!(manifest.common.patches.keys().collect::<Vec<_>>(), vec!["ok"]);
+        assert!(manifest.builds["2060"].bindings.is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(Manifest::parse(r#"{ "bindings": { "GAME_STATE": { "ofset": 1 } } }"#), Err(ManifestError::Json(_))));
+        assert!(matches!(Manifest::parse("not json"), Err(ManifestError::Json(_))));
+        assert_eq!(Manifest::load("/nonexistent/manifest.json").unwrap(), Manifest::default());
+    }
+}
@@ -0,0 +1,349 @@
+//Shared with build.rs, which includes this file on its own: no `crate::` paths here
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter, Write};
+
+use serde_derive::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Param {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub name: String,
+}
+
+//Entry of the database, keyed by namespace then hash like the community natives.json
+#[derive(Deserialize)]
+struct Entry {
+    name: String,
+    #[serde(default)]
+    params: Vec<Param>,
+    return_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeInfo {
+    pub namespace: String,
+    pub name: String,
+    pub hash: u64,
+    pub params: Vec<Param>,
+    pub return_type: String,
+}
+
+#[derive(Debug)]
+pub enum DatabaseError {
+    Json(serde_json::Error),
+    InvalidHash(String),
+    DuplicateHash(u64),
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseError::Json(e) => e.fmt(f),
+            DatabaseError::InvalidHash(hash) => f.write_fmt(format_args!("invalid native hash `{}`", hash)),
+            DatabaseError::DuplicateHash(hash) => f.write_fmt(format_args!("native 0x{:016X} is listed more than once", hash))
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {}
+
+impl From<serde_json::Error> for DatabaseError {
+    fn from(e: serde_json::Error) -> Self {
+        DatabaseError::Json(e)
+    }
+}
+
+/// Natives by namespace and name, with the types of their parameters and results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NativeDatabase {
+    natives: Vec<NativeInfo>,
+}
+
+impl NativeDatabase {
+    /// Parses `{"ENTITY": {"0x7239B21A38F536BA": {"name": "DOES_ENTITY_EXIST", "params": [...], "return_type": "BOOL"}}}`,
+    /// ignoring any other field of the entries.
+    pub fn parse(json: &str) -> Result<NativeDatabase, DatabaseError> {
+        let namespaces: BTreeMap<String, BTreeMap<String, Entry>> = serde_json::from_str(json)?;
+        let mut natives = Vec::new();
+        let mut hashes = HashSet::new();
+        for (namespace, entries) in namespaces {
+            for (hash, entry) in entries {
+                let digits = hash.strip_prefix("0x").or_else(|| hash.strip_prefix("0X")).unwrap_or(&hash);
+                let hash = u64::from_str_radix(digits, 16).map_err(|_| DatabaseError::InvalidHash(hash.clone()))?;
+                if !hashes.insert(hash) {
+                    return Err(DatabaseError::DuplicateHash(hash));
+                }
+                natives.push(NativeInfo {
+                    namespace: namespace.clone(),
+                    name: entry.name,
+                    hash,
+                    params: entry.params,
+                    return_type: entry.return_type,
+                });
+            }
+        }
+        natives.sort_by(|a, b| (&a.namespace, &a.name, a.hash).cmp(&(&b.namespace, &b.name, b.hash)));
+        Ok(NativeDatabase { natives })
+    }
+
+    pub fn natives(&self) -> &[NativeInfo] {
+        &self.natives
+    }
+
+    pub fn get(&self, hash: u64) -> Option<&NativeInfo> {
+        self.natives.iter().find(|n| n.hash == hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.natives.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.natives.is_empty()
+    }
+}
+
+/// Output of `generate`.
+#[derive(Debug, Clone, Default)]
+pub struct Generated {
+    /// One module of typed wrappers per namespace
+    pub wrappers: String,
+    /// Table of every native, sorted by hash
+    pub registry: String,
+    /// Natives without a wrapper and the type that prevented it
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Rust type a database type is passed as, results of `const char*` being owned.
+pub fn rust_type(ty: &str, result: bool) -> Option<&'static str> {
+    let ty = ty.trim();
+    Some(match ty {
+        "void" => "()",
+        "BOOL" | "bool" => "bool",
+        "int" => "i32",
+        "float" => "f32",
+        "Any" => "u64",
+        "Hash" => "crate::hash::Hash",
+        "Vector3" => "cgmath::Vector3<f32>",
+        "const char*" | "char*" if result => "String",
+        "const char*" | "char*" => "&str",
+        "int*" | "BOOL*" => "&mut i32",
+        "float*" => "&mut f32",
+        "Hash*" => "&mut crate::hash::Hash",
+        "Any*" => "&mut u64",
+        "Vector3*" => "&mut crate::native::NativeVector3",
+        _ if is_handle(ty) => "crate::game::Handle",
+        _ => match ty.strip_suffix('*') {
+            Some(handle) if is_handle(handle.trim()) => "&mut u32",
+            _ => return None
+        }
+    })
+}
+
+fn is_handle(ty: &str) -> bool {
+    matches!(ty, "Entity" | "Ped" | "Vehicle" | "Object" | "Player" | "Blip" | "Cam" | "Pickup"
+        | "Interior" | "FireId" | "ScrHandle" | "Train" | "Sphere")
+}
+
+pub fn generate(db: &NativeDatabase) -> Generated {
+    let mut generated = Generated::default();
+    let mut namespaces = BTreeMap::<&str, Vec<&NativeInfo>>::new();
+    for native in db.natives() {
+        namespaces.entry(&native.namespace).or_default().push(native);
+    }
+    for (namespace, natives) in namespaces {
+        writeln!(generated.wrappers, "#[allow(clippy::too_many_arguments)]").unwrap();
+        writeln!(generated.wrappers, "pub mod {} {{", identifier(namespace)).unwrap();
+        let mut names = HashSet::new();
+        for native in natives {
+            match wrapper(native, &mut names) {
+                Ok(source) => generated.wrappers.push_str(&source),
+                Err(ty) => {
+                    writeln!(generated.wrappers, "    // {} skipped, `{}` has no Rust type", native.name, ty).unwrap();
+                    generated.skipped.push((format!("{}::{}", native.namespace, native.name), ty));
+                }
+            }
+        }
+        writeln!(generated.wrappers, "}}").unwrap();
+    }
+
+    let mut natives = db.natives().iter().collect::<Vec<_>>();
+    natives.sort_by_key(|n| n.hash);
+    let registry = &mut generated.registry;
+    writeln!(registry, "static NATIVES: [NativeSignature; {}] = [", natives.len()).unwrap();
+    for native in natives {
+        write!(registry, "    NativeSignature {{ hash: 0x{:016X}, namespace: {:?}, name: {:?}, params: &[",
+               native.hash, native.namespace, native.name).unwrap();
+        for (i, param) in native.params.iter().enumerate() {
+            if i > 0 {
+                registry.push_str(", ");
+            }
+            write!(registry, "NativeParam {{ ty: {:?}, name: {:?} }}", param.ty, param.name).unwrap();
+        }
+        writeln!(registry, "], return_type: {:?} }},", native.return_type).unwrap();
+    }
+    writeln!(registry, "];").unwrap();
+    generated
+}
+
+fn wrapper(native: &NativeInfo, names: &mut HashSet<String>) -> Result<String, String> {
+    let result = rust_type(&native.return_type, true).ok_or_else(|| native.return_type.clone())?;
+    let mut params = Vec::new();
+    let mut seen = HashMap::new();
+    for param in &native.params {
+        let ty = rust_type(&param.ty, false).ok_or_else(|| param.ty.clone())?;
+        let mut name = identifier(&param.name);
+        let count = seen.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            name = format!("{}_{}", name, count);
+        }
+        params.push((name, ty));
+    }
+    let mut name = identifier(&native.name);
+    if !names.insert(name.clone()) {
+        name = format!("{}_{:016x}", name, native.hash);
+        names.insert(name.clone());
+    }
+
+    let mut source = String::new();
+    writeln!(source, "    /// `{}::{}`", native.namespace, native.name).unwrap();
+    write!(source, "    pub fn {}(", name).unwrap();
+    let signature = params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect::<Vec<_>>();
+    source.push_str(&signature.join(", "));
+    source.push(')');
+    if result != "()" {
+        write!(source, " -> {}", result).unwrap();
+    }
+    writeln!(source, " {{").unwrap();
+    write!(source, "        crate::invoke!({}, 0x{:016X}", result, native.hash).unwrap();
+    for (name, _) in &params {
+        write!(source, ", {}", name).unwrap();
+    }
+    writeln!(source, ")").unwrap();
+    writeln!(source, "    }}").unwrap();
+    Ok(source)
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match",
+    "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Snake-case identifier for a native, namespace or parameter name.
+pub fn identifier(name: &str) -> String {
+    let name = name.trim_start_matches('_');
+    //Natives without a known name are called by their hash, `_0x...`
+    if name.starts_with("0x") || name.starts_with("0X") {
+        return format!("native_{}", name.to_ascii_lowercase());
+    }
+    let mut ident = String::with_capacity(name.len() + 4);
+    let mut previous = '_';
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous.is_ascii_lowercase() {
+            ident.push('_');
+        }
+        ident.push(if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' });
+        previous = c;
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert_str(0, "native_");
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = r#"{
+        "ENTITY": {
+            "0x7239B21A38F536BA": {"name": "DOES_ENTITY_EXIST", "params": [{"type": "Entity", "name": "entity"}], "return_type": "BOOL", "comment": ""},
+            "0x0000000000000002": {"name": "_0xABCDEF", "params": [{"type": "Any", "name": "p0"}, {"type": "Any", "name": "p0"}], "return_type": "void"},
+            "0x0000000000000003": {"name": "DOES_ENTITY_EXIST", "params": [], "return_type": "int"}
+        },
+        "GRAPHICS": {
+            "0x0000000000000001": {"name": "GET_WIDTH", "params": [{"type": "ScrHandle*", "name": "type"}, {"type": "Blob*", "name": "blob"}], "return_type": "float"},
+            "0x0000000000000004": {"name": "GET_NAME", "return_type": "const char*"}
+        }
+    }"#;
+
+    #[test]
+    fn parse() {
+        let db = NativeDatabase::parse(DB).unwrap();
+        assert_eq!(db.len(), 5);
+        let names = db.natives().iter().map(|n| format!("{}::{}", n.namespace, n.name)).collect::<Vec<_>>();
+        assert_eq!(names, vec!["ENTITY::DOES_ENTITY_EXIST", "ENTITY::DOES_ENTITY_EXIST", "ENTITY::_0xABCDEF",
+                               "GRAPHICS::GET_NAME", "GRAPHICS::GET_WIDTH"]);
+        let exists = db.get(0x7239B21A38F536BA).unwrap();
+        assert_eq!(exists.params, vec![Param { ty: String::from("Entity"), name: String::from("entity") }]);
+        assert!(db.get(4).unwrap().params.is_empty());
+        assert!(db.get(5).is_none());
+
+        assert!(matches!(NativeDatabase::parse(r#"{"A": {"0xZZ": {"name": "X", "return_type": "void"}}}"#),
+                         Err(DatabaseError::InvalidHash(h)) if h == "0xZZ"));
+        assert!(matches!(NativeDatabase::parse(r#"{"A": {"0x1": {"name": "X", "return_type": "void"}}, "B": {"1": {"name": "Y", "return_type": "void"}}}"#),
+                         Err(DatabaseError::DuplicateHash(1))));
+        assert!(matches!(NativeDatabase::parse(r#"{"A": {"0x1": {"name": "X"}}}"#), Err(DatabaseError::Json(_))));
+    }
+
+    #[test]
+    fn rust_types() {
+        assert_eq!(rust_type("void", true), Some("()"));
+        assert_eq!(rust_type(" BOOL ", false), Some("bool"));
+        assert_eq!(rust_type("const char*", true), Some("String"));
+        assert_eq!(rust_type("const char*", false), Some("&str"));
+        assert_eq!(rust_type("Vehicle", false), Some("crate::game::Handle"));
+        assert_eq!(rust_type("Vehicle *", false), Some("&mut u32"));
+        assert_eq!(rust_type("Vector3*", false), Some("&mut crate::native::NativeVector3"));
+        assert_eq!(rust_type("Blob*", false), None);
+        assert_eq!(rust_type("long", true), None);
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier("GET_ENTITY_COORDS"), "get_entity_coords");
+        assert_eq!(identifier("xPos"), "x_pos");
+        assert_eq!(identifier("_0xABCDEF"), "native_0xabcdef");
+        assert_eq!(identifier("type"), "type_");
+        assert_eq!(identifier("3dMarker"), "native_3d_marker");
+        assert_eq!(identifier("p-1"), "p_1");
+        assert_eq!(identifier("_"), "native_");
+    }
+
+    #[test]
+    fn generate_wrappers() {
+        let db = NativeDatabase::parse(DB).unwrap();
+        let generated = generate(&db);
+        assert_eq!(generated.skipped, vec![(String::from("GRAPHICS::GET_WIDTH"), String::from("Blob*"))]);
+        let wrappers = &generated.wrappers;
+        assert!(wrappers.contains("pub mod entity {"));
+        //Natives sharing a name get their hash as a suffix, parameters sharing one a counter
+        assert!(wrappers.contains("    pub fn does_entity_exist() -> i32 {"));
+        assert!(wrappers.contains("    pub fn does_entity_exist_7239b21a38f536ba(entity: crate::game::Handle) -> bool {\n        crate::invoke!(bool, 0x7239B21A38F536BA, entity)\n    }"));
+        assert!(wrappers.contains("pub fn native_0xabcdef(p0: u64, p0_2: u64) {\n        crate::invoke!((), 0x0000000000000002, p0, p0_2)"));
+        assert!(wrappers.contains("pub fn get_name() -> String {"));
+        assert!(wrappers.contains("// GET_WIDTH skipped, `Blob*` has no Rust type"));
+
+        let registry = &generated.registry;
+        assert!(registry.starts_with("static NATIVES: [NativeSignature; 5] = [\n    NativeSignature { hash: 0x0000000000000001,"));
+        assert!(registry.contains(r#"params: &[NativeParam { ty: "Entity", name: "entity" }], return_type: "BOOL" },"#));
+        let hashes = registry.lines().filter_map(|l| l.split("hash: 0x").nth(1)).map(|l| &l[..16]).collect::<Vec<_>>();
+        let mut sorted = hashes.clone();
+        sorted.sort();
+        assert_eq!(hashes, sorted);
+    }
+
+    #[test]
+    fn bundled_database() {
+        let db = NativeDatabase::parse(include_str!("natives.json")).unwrap();
+        assert!(!db.is_empty());
+        assert!(generate(&db).skipped.is_empty());
+    }
+}
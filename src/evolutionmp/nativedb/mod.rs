@@ -0,0 +1,57 @@
+use std::fmt::{Display, Formatter};
+
+pub mod codegen;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NativeParam {
+    pub ty: &'static str,
+    pub name: &'static str,
+}
+
+/// Name and signature of a native, as listed in the database.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NativeSignature {
+    pub hash: u64,
+    pub namespace: &'static str,
+    pub name: &'static str,
+    pub params: &'static [NativeParam],
+    pub return_type: &'static str,
+}
+
+impl Display for NativeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{} {}::{}(", self.return_type, self.namespace, self.name))?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_fmt(format_args!("{} {}", param.ty, param.name))?;
+        }
+        f.write_str(")")
+    }
+}
+
+//Generated by build.rs, sorted by hash
+include!(concat!(env!("OUT_DIR"), "/native_registry.rs"));
+
+pub fn lookup(hash: u64) -> Option<&'static NativeSignature> {
+    NATIVES.binary_search_by_key(&hash, |n| n.hash).ok().map(|i| &NATIVES[i])
+}
+
+/// Finds a native by name, the namespace being optional.
+pub fn find(namespace: Option<&str>, name: &str) -> Option<&'static NativeSignature> {
+    NATIVES.iter().find(|n| n.name.eq_ignore_ascii_case(name)
+        && namespace.is_none_or(|ns| n.namespace.eq_ignore_ascii_case(ns)))
+}
+
+pub fn natives() -> &'static [NativeSignature] {
+    &NATIVES
+}
+
+/// Name of a native for logs, its hash when it isn't in the database.
+pub fn describe(hash: u64) -> String {
+    match lookup(hash) {
+        Some(native) => format!("{}::{} (0x{:016X})", native.namespace, native.name, hash),
+        None => format!("0x{:016X}", hash)
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::nativedb;
+use crate::patch::PatchMemory;
+
+/// Groups of the table, natives being bucketed by the low byte of their hash.
+pub const BUCKETS: usize = 256;
+/// Natives per group.
+pub const GROUP_LEN: usize = 7;
+
+//Layout of the packed `NativeGroup` of the game
+const NEXT_OFFSET: u64 = 0;
+const HANDLERS_OFFSET: u64 = 16;
+const LEN_OFFSET: u64 = HANDLERS_OFFSET + 8 * GROUP_LEN as u64;
+const HASHES_OFFSET: u64 = LEN_OFFSET + 12;
+pub const GROUP_SIZE: u64 = HASHES_OFFSET + 16 * GROUP_LEN as u64;
+/// Bytes of the table header, the group pointers followed by a counter and a flag.
+pub const TABLE_SIZE: u64 = 8 * BUCKETS as u64 + 8;
+
+/// Dump of the registrations, written to the launcher directory.
+pub const DUMP_FILE: &str = "native_table.txt";
+
+//Chains longer than this are taken as corrupt
+const MAX_CHAIN: usize = 4096;
+
+/// Decodes a 64-bit value stored as `prev`, `next` at `address`.
+pub fn decode_u64(address: u64, prev: u64, next: u64) -> u64 {
+    let mask = (address ^ next) as u32 as u64;
+    ((mask << 32) | mask) ^ prev
+}
+
+/// Encodes a 64-bit value stored at `address`, `key` being any `next` word.
+pub fn encode_u64(address: u64, value: u64, key: u64) -> (u64, u64) {
+    let mask = (address ^ key) as u32 as u64;
+    (((mask << 32) | mask) ^ value, key)
+}
+
+pub fn decode_u32(address: u64, prev: u32, next: u32) -> u32 {
+    address as u32 ^ next ^ prev
+}
+
+pub fn encode_u32(address: u64, value: u32, key: u32) -> (u32, u32) {
+    (address as u32 ^ key ^ value, key)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// Memory that couldn't be read, with its address
+    Unreadable(u64),
+    InvalidLength { group: u64, len: u32 },
+    /// A group reached twice while following a chain
+    Cycle(u64),
+    WrongBucket { hash: u64, bucket: usize },
+    InvalidLine(String),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::Unreadable(address) => f.write_fmt(format_args!("unable to read memory at 0x{:X}", address)),
+            TableError::InvalidLength { group, len } => {
+                f.write_fmt(format_args!("group at 0x{:X} holds {} natives, at most {} fit", group, len, GROUP_LEN))
+            }
+            TableError::Cycle(group) => f.write_fmt(format_args!("group at 0x{:X} is chained in a cycle", group)),
+            TableError::WrongBucket { hash, bucket } => {
+                f.write_fmt(format_args!("native 0x{:016X} is registered in bucket {}", hash, bucket))
+            }
+            TableError::InvalidLine(line) => f.write_fmt(format_args!("invalid registration `{}`", line))
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// A native of the table and the address of its handler.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Registration {
+    pub hash: u64,
+    pub handler: u64,
+}
+
+/// Memory copied from the game at `base`, addresses outside of it being unreadable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryDump {
+    pub base: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl MemoryDump {
+    pub fn new(base: u64, bytes: Vec<u8>) -> MemoryDump {
+        MemoryDump { base, bytes }
+    }
+}
+
+impl PatchMemory for MemoryDump {
+    fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+        address.checked_sub(self.base).is_some_and(|offset| self.bytes.read(offset, buf))
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8]) -> bool {
+        address.checked_sub(self.base).is_some_and(|offset| self.bytes.write(offset, bytes))
+    }
+}
+
+fn read_u64<M>(memory: &M, address: u64) -> Result<u64, TableError> where M: PatchMemory + ?Sized {
+    let mut buf = [0; 8];
+    if memory.read(address, &mut buf) { Ok(u64::from_le_bytes(buf)) } else { Err(TableError::Unreadable(address)) }
+}
+
+fn read_u32<M>(memory: &M, address: u64) -> Result<u32, TableError> where M: PatchMemory + ?Sized {
+    let mut buf = [0; 4];
+    if memory.read(address, &mut buf) { Ok(u32::from_le_bytes(buf)) } else { Err(TableError::Unreadable(address)) }
+}
+
+//Pointers of a dump can't be trusted, addresses past the end of the address space being unreadable
+fn offset(address: u64, offset: u64) -> Result<u64, TableError> {
+    address.checked_add(offset).ok_or(TableError::Unreadable(address))
+}
+
+fn read_encoded_u64<M>(memory: &M, address: u64) -> Result<u64, TableError> where M: PatchMemory + ?Sized {
+    Ok(decode_u64(address, read_u64(memory, address)?, read_u64(memory, offset(address, 8)?)?))
+}
+
+fn read_encoded_u32<M>(memory: &M, address: u64) -> Result<u32, TableError> where M: PatchMemory + ?Sized {
+    Ok(decode_u32(address, read_u32(memory, address)?, read_u32(memory, offset(address, 4)?)?))
+}
+
+/// Decodes every native of the table at `table`, in bucket then chain order.
+/// Null group pointers are taken as empty buckets.
+pub fn decode<M>(memory: &M, table: u64) -> Result<Vec<Registration>, TableError> where M: PatchMemory + ?Sized {
+    let mut registrations = Vec::new();
+    let mut visited = HashSet::new();
+    for bucket in 0..BUCKETS {
+        let mut group = read_u64(memory, offset(table, 8 * bucket as u64)?)?;
+        let mut chain = 0;
+        while group != 0 {
+            chain += 1;
+            if !visited.insert(group) || chain > MAX_CHAIN {
+                return Err(TableError::Cycle(group));
+            }
+            let len = read_encoded_u32(memory, offset(group, LEN_OFFSET)?)?;
+            if len as usize > GROUP_LEN {
+                return Err(TableError::InvalidLength { group, len });
+            }
+            for index in 0..len as u64 {
+                let hash = read_encoded_u64(memory, offset(group, HASHES_OFFSET + 16 * index)?)?;
+                if (hash & 0xFF) as usize != bucket {
+                    return Err(TableError::WrongBucket { hash, bucket });
+                }
+                let handler = read_u64(memory, offset(group, HANDLERS_OFFSET + 8 * index)?)?;
+                registrations.push(Registration { hash, handler });
+            }
+            group = read_encoded_u64(memory, offset(group, NEXT_OFFSET)?)?;
+        }
+    }
+    Ok(registrations)
+}
+
+/// Lays out a table at `base` with its groups right after, encoding each word with keys derived from `seed`.
+/// Registrations are kept in order within their bucket.
+pub fn encode(registrations: &[Registration], base: u64, seed: u64) -> MemoryDump {
+    let mut buckets = vec![Vec::new(); BUCKETS];
+    for registration in registrations {
+        buckets[(registration.hash & 0xFF) as usize].push(*registration);
+    }
+    let groups = buckets.iter().map(|natives| natives.len().div_ceil(GROUP_LEN)).sum::<usize>();
+    let mut dump = MemoryDump::new(base, vec![0; (TABLE_SIZE + GROUP_SIZE * groups as u64) as usize]);
+    let mut key = seed;
+    let mut next_key = move || {
+        //xorshift, any key decoding the same
+        key ^= key << 13;
+        key ^= key >> 7;
+        key ^= key << 17;
+        key
+    };
+    let mut next_group = base + TABLE_SIZE;
+    for (bucket, natives) in buckets.iter().enumerate() {
+        let mut link = base + 8 * bucket as u64;
+        let mut link_encoded = false;
+        for chunk in natives.chunks(GROUP_LEN) {
+            let group = next_group;
+            next_group += GROUP_SIZE;
+            write_link(&mut dump, link, link_encoded, group, next_key());
+            for (index, registration) in chunk.iter().enumerate() {
+                let index = index as u64;
+                dump.write(group + HANDLERS_OFFSET + 8 * index, &registration.handler.to_le_bytes());
+                let hash_address = group + HASHES_OFFSET + 16 * index;
+                let (prev, next) = encode_u64(hash_address, registration.hash, next_key());
+                dump.write(hash_address, &prev.to_le_bytes());
+                dump.write(hash_address + 8, &next.to_le_bytes());
+            }
+            let (prev, next) = encode_u32(group + LEN_OFFSET, chunk.len() as u32, next_key() as u32);
+            dump.write(group + LEN_OFFSET, &prev.to_le_bytes());
+            dump.write(group + LEN_OFFSET + 4, &next.to_le_bytes());
+            link = group + NEXT_OFFSET;
+            link_encoded = true;
+        }
+        if link_encoded {
+            write_link(&mut dump, link, true, 0, next_key());
+        }
+    }
+    dump
+}
+
+//Group pointers of the table are plain, the ones chaining groups are encoded
+fn write_link(dump: &mut MemoryDump, address: u64, encoded: bool, group: u64, key: u64) {
+    if encoded {
+        let (prev, next) = encode_u64(address, group, key);
+        dump.write(address, &prev.to_le_bytes());
+        dump.write(address + 8, &next.to_le_bytes());
+    } else {
+        dump.write(address, &group.to_le_bytes());
+    }
+}
+
+/// Registrations as `0xHASH 0xRVA NAMESPACE::NAME` lines sorted by hash, to diff them between builds.
+/// Handlers outside of the image are written as absolute addresses.
+pub fn to_text(registrations: &[Registration], image_base: u64, image_size: u64) -> String {
+    let mut registrations = registrations.to_vec();
+    registrations.sort();
+    let mut text = String::new();
+    for registration in registrations {
+        let rva = registration.handler.wrapping_sub(image_base);
+        let handler = if registration.handler >= image_base && rva < image_size { rva } else { registration.handler };
+        text.push_str(&format!("0x{:016X} 0x{:08X}", registration.hash, handler));
+        if let Some(native) = nativedb::lookup(registration.hash) {
+            text.push_str(&format!(" {}::{}", native.namespace, native.name));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Parses the output of `to_text`, handlers being read as written.
+pub fn parse_text(text: &str) -> Result<Vec<Registration>, TableError> {
+    let parse = |word: Option<&str>, line: &str| {
+        word.and_then(|w| w.strip_prefix("0x"))
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| TableError::InvalidLine(String::from(line)))
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut words = line.split_whitespace();
+            Ok(Registration { hash: parse(words.next(), line)?, handler: parse(words.next(), line)? })
+        })
+        .collect()
+}
+
+/// Difference of a native between two tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(Registration),
+    Removed(Registration),
+    Moved { hash: u64, from: u64, to: u64 },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added(r) => f.write_fmt(format_args!("+ {} 0x{:X}", nativedb::describe(r.hash), r.handler)),
+            Change::Removed(r) => f.write_fmt(format_args!("- {} 0x{:X}", nativedb::describe(r.hash), r.handler)),
+            Change::Moved { hash, from, to } => {
+                f.write_fmt(format_args!("~ {} 0x{:X} -> 0x{:X}", nativedb::describe(*hash), from, to))
+            }
+        }
+    }
+}
+
+/// Natives added, removed or given another handler from `old` to `new`, sorted by hash.
+pub fn diff(old: &[Registration], new: &[Registration]) -> Vec<Change> {
+    let old = old.iter().map(|r| (r.hash, r.handler)).collect::<BTreeMap<_, _>>();
+    let new = new.iter().map(|r| (r.hash, r.handler)).collect::<BTreeMap<_, _>>();
+    let mut changes = Vec::new();
+    for (hash, handler) in &old {
+        match new.get(hash) {
+            None => changes.push(Change::Removed(Registration { hash: *hash, handler: *handler })),
+            Some(to) if to != handler => changes.push(Change::Moved { hash: *hash, from: *handler, to: *to }),
+            _ => {}
+        }
+    }
+    for (hash, handler) in &new {
+        if !old.contains_key(hash) {
+            changes.push(Change::Added(Registration { hash: *hash, handler: *handler }));
+        }
+    }
+    changes.sort_by_key(|c| match c {
+        Change::Added(r) | Change::Removed(r) => r.hash,
+        Change::Moved { hash, .. } => *hash
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u64 = 0x1_4000_0000;
+
+    fn registrations(count: u64) -> Vec<Registration> {
+        (0..count).map(|i| Registration {
+            hash: i.wrapping_mul(0x9E3779B97F4A7C15) ^ (i << 3),
+            handler: 0x7FF6_0000_0000 + i * 0x40,
+        }).collect()
+    }
+
+    fn sorted(mut registrations: Vec<Registration>) -> Vec<Registration> {
+        registrations.sort();
+        registrations
+    }
+
+    fn write_encoded(dump: &mut MemoryDump, address: u64, value: u64) {
+        let (prev, next) = encode_u64(address, value, 0x5555);
+        dump.write(address, &prev.to_le_bytes());
+        dump.write(address + 8, &next.to_le_bytes());
+    }
+
+    #[test]
+    fn word_encoding() {
+        for &(address, value, key) in &[(0x7FF6_1234_5678, 0xDEADBEEF_12345678, 0), (0x1000, 0, u64::MAX), (u64::MAX, 42, 0x1234)] {
+            let (prev, next) = encode_u64(address, value, key);
+            assert_eq!(decode_u64(address, prev, next), value);
+            let (prev, next) = encode_u32(address, value as u32, key as u32);
+            assert_eq!(decode_u32(address, prev, next), value as u32);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for count in [0, 1, 7, 8, 300, 5000] {
+            for seed in [1, 0xDEADBEEF, 0x1234_5678_9ABC] {
+                let registrations = registrations(count);
+                let dump = encode(&registrations, BASE, seed);
+                assert_eq!(sorted(decode(&dump, BASE).unwrap()), sorted(registrations), "{} natives, seed {}", count, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn chained_groups() {
+        let registrations: Vec<_> = (0..20).map(|i| Registration { hash: (i << 8) | 0x42, handler: i }).collect();
+        let dump = encode(&registrations, BASE, 7);
+        assert_eq!(dump.bytes.len() as u64, TABLE_SIZE + 3 * GROUP_SIZE);
+        assert_eq!(decode(&dump, BASE).unwrap(), registrations);
+    }
+
+    #[test]
+    fn corrupt_tables() {
+        let registrations: Vec<_> = (0..3).map(|i| Registration { hash: (i << 8) | 5, handler: i }).collect();
+        let dump = encode(&registrations, BASE, 9);
+        let group = BASE + TABLE_SIZE;
+        assert_eq!(decode(&dump, BASE - 8), Err(TableError::Unreadable(BASE - 8)));
+
+        let mut truncated = dump.clone();
+        truncated.bytes.truncate((TABLE_SIZE + 20) as usize);
+        assert!(matches!(decode(&truncated, BASE), Err(TableError::Unreadable(_))));
+
+        let mut too_long = dump.clone();
+        let (prev, next) = encode_u32(group + LEN_OFFSET, 9, 0);
+        too_long.write(group + LEN_OFFSET, &prev.to_le_bytes());
+        too_long.write(group + LEN_OFFSET + 4, &next.to_le_bytes());
+        assert_eq!(decode(&too_long, BASE), Err(TableError::InvalidLength { group, len: 9 }));
+
+        let mut cycle = dump.clone();
+        write_encoded(&mut cycle, group + NEXT_OFFSET, group);
+        assert_eq!(decode(&cycle, BASE), Err(TableError::Cycle(group)));
+
+        let mut misplaced = dump.clone();
+        write_encoded(&mut misplaced, group + HASHES_OFFSET, 0x1234_0006);
+        assert_eq!(decode(&misplaced, BASE), Err(TableError::WrongBucket { hash: 0x1234_0006, bucket: 5 }));
+    }
+
+    #[test]
+    fn pointers_past_the_address_space() {
+        let dump = encode(&registrations(10), BASE, 3);
+        let mut table_end = dump.clone();
+        table_end.write(BASE, &(u64::MAX - 8).to_le_bytes());
+        assert_eq!(decode(&table_end, BASE), Err(TableError::Unreadable(u64::MAX - 8)));
+
+        let group = BASE + TABLE_SIZE;
+        let mut chain_end = dump.clone();
+        write_encoded(&mut chain_end, group + NEXT_OFFSET, u64::MAX - 4);
+        assert_eq!(decode(&chain_end, BASE), Err(TableError::Unreadable(u64::MAX - 4)));
+
+        //Empty buckets up to the end of the address space, the fourth one past it
+        let top = MemoryDump::new(u64::MAX - 23, vec![0; 24]);
+        assert_eq!(decode(&top, u64::MAX - 23), Err(TableError::Unreadable(u64::MAX - 23)));
+    }
+
+    #[test]
+    fn text() {
+        let image = 0x7FF6_0000_0000;
+        let registrations = vec![
+            Registration { hash: 0x2, handler: image + 0x100 },
+            Registration { hash: 0x1, handler: image + 0x200 },
+            Registration { hash: 0x3, handler: 0x1234 },
+        ];
+        let text = to_text(&registrations, image, 0x1000);
+        assert_eq!(text.lines().next(), Some("0x0000000000000001 0x00000200"));
+        assert_eq!(parse_text(&text).unwrap(), vec![
+            Registration { hash: 0x1, handler: 0x200 },
+            Registration { hash: 0x2, handler: 0x100 },
+            Registration { hash: 0x3, handler: 0x1234 },
+        ]);
+        assert!(parse_text("# comment\n\n").unwrap().is_empty());
+        assert_eq!(parse_text("0x12 zz"), Err(TableError::InvalidLine(String::from("0x12 zz"))));
+        assert_eq!(parse_text("0x12"), Err(TableError::InvalidLine(String::from("0x12"))));
+
+        let native = &nativedb::natives()[0];
+        let text = to_text(&[Registration { hash: native.hash, handler: 5 }], 0, 10);
+        assert!(text.trim_end().ends_with(&format!(" {}::{}", native.namespace, native.name)), "{}", text);
+        assert_eq!(parse_text(&text).unwrap(), vec![Registration { hash: native.hash, handler: 5 }]);
+    }
+
+    #[test]
+    fn diffs() {
+        let old = vec![
+            Registration { hash: 0x2, handler: 0x100 },
+            Registration { hash: 0x1, handler: 0x200 },
+            Registration { hash: 0x3, handler: 0x1234 },
+        ];
+        let new = vec![
+            Registration { hash: 0x4, handler: 0x1 },
+            Registration { hash: 0x1, handler: 0x300 },
+            Registration { hash: 0x3, handler: 0x1234 },
+        ];
+        assert_eq!(diff(&old, &new), vec![
+            Change::Moved { hash: 0x1, from: 0x200, to: 0x300 },
+            Change::Removed(Registration { hash: 0x2, handler: 0x100 }),
+            Change::Added(Registration { hash: 0x4, handler: 0x1 }),
+        ]);
+        assert!(diff(&old, &old).is_empty());
+        assert_eq!(diff(&[], &old).len(), 3);
+        assert!(Change::Moved { hash: 0x1, from: 0x200, to: 0x300 }.to_string().ends_with(" 0x200 -> 0x300"));
+    }
+}
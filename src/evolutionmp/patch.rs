@@ -0,0 +1,342 @@
+use std::fmt::{Display, Formatter};
+
+use crate::signature::Pattern;
+
+/// Memory a patch can be applied to; addresses are absolute for the game process
+/// and plain offsets for in-memory buffers.
+pub trait PatchMemory {
+    fn read(&self, address: u64, buf: &mut [u8]) -> bool;
+    fn write(&mut self, address: u64, bytes: &[u8]) -> bool;
+}
+
+impl PatchMemory for [u8] {
+    fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+        match range(address, buf.len()).and_then(|r| self.get(r)) {
+            Some(bytes) => {
+                buf.copy_from_slice(bytes);
+                true
+            }
+            None => false
+        }
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8]) -> bool {
+        match range(address, bytes.len()).and_then(|r| self.get_mut(r)) {
+            Some(target) => {
+                target.copy_from_slice(bytes);
+                true
+            }
+            None => false
+        }
+    }
+}
+
+//Addresses past the end of the address space are out of any buffer
+fn range(address: u64, len: usize) -> Option<std::ops::Range<usize>> {
+    let start = usize::try_from(address).ok()?;
+    Some(start..start.checked_add(len)?)
+}
+
+impl PatchMemory for Vec<u8> {
+    fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+        self.as_slice().read(address, buf)
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8]) -> bool {
+        self.as_mut_slice().write(address, bytes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    Duplicate(String),
+    Overlap(String, String),
+    Unknown(String),
+    Mismatch { name: String, found: Vec<u8> },
+    Inaccessible(String),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Duplicate(name) => f.write_fmt(format_args!("patch `{}` is already registered", name)),
+            PatchError::Overlap(name, other) => f.write_fmt(format_args!("patch `{}` overlaps `{}`", name, other)),
+            PatchError::Unknown(name) => f.write_fmt(format_args!("unknown patch `{}`", name)),
+            PatchError::Mismatch { name, found } => {
+                f.write_fmt(format_args!("unexpected bytes for patch `{}`:", name))?;
+                for b in found {
+                    f.write_fmt(format_args!(" {:02X}", b))?;
+                }
+                Ok(())
+            }
+            PatchError::Inaccessible(name) => f.write_fmt(format_args!("memory of patch `{}` is not accessible", name))
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// A named replacement of game bytes that remembers what it overwrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    name: String,
+    address: u64,
+    expected: Option<Pattern>,
+    replacement: Vec<u8>,
+    original: Option<Vec<u8>>,
+}
+
+impl Patch {
+    pub fn new<N>(name: N, address: u64, replacement: Vec<u8>) -> Patch where N: Into<String> {
+        Patch {
+            name: name.into(),
+            address,
+            expected: None,
+            replacement,
+            original: None,
+        }
+    }
+
+    /// Refuses to enable the patch unless the bytes at its address match `expected`.
+    pub fn with_expected(mut self, expected: Pattern) -> Patch {
+        self.expected = Some(expected);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    pub fn len(&self) -> usize {
+        self.replacement.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replacement.is_empty()
+    }
+
+    pub fn replacement(&self) -> &[u8] {
+        &self.replacement
+    }
+
+    /// Bytes overwritten by the patch, present only while it is enabled.
+    pub fn original(&self) -> Option<&[u8]> {
+        self.original.as_deref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.original.is_some()
+    }
+
+    pub fn overlaps(&self, other: &Patch) -> bool {
+        (self.address as u128) < other.end() && (other.address as u128) < self.end()
+    }
+
+    //Wide enough for patches ending at the top of the address space
+    fn end(&self) -> u128 {
+        self.address as u128 + self.len() as u128
+    }
+
+    fn enable<M>(&mut self, memory: &mut M) -> Result<(), PatchError> where M: PatchMemory + ?Sized {
+        if self.is_enabled() {
+            return Ok(());
+        }
+        let checked = self.expected.as_ref().map(|e| e.len()).unwrap_or(0).max(self.len());
+        let mut current = vec![0; checked];
+        if !memory.read(self.address, &mut current) {
+            return Err(PatchError::Inaccessible(self.name.clone()));
+        }
+        if let Some(expected) = &self.expected {
+            if !expected.matches(&current) {
+                return Err(PatchError::Mismatch { name: self.name.clone(), found: current });
+            }
+        }
+        current.truncate(self.len());
+        if !memory.write(self.address, &self.replacement) {
+            return Err(PatchError::Inaccessible(self.name.clone()));
+        }
+        self.original = Some(current);
+        Ok(())
+    }
+
+    fn disable<M>(&mut self, memory: &mut M) -> Result<(), PatchError> where M: PatchMemory + ?Sized {
+        if let Some(original) = &self.original {
+            if !memory.write(self.address, original) {
+                return Err(PatchError::Inaccessible(self.name.clone()));
+            }
+            self.original = None;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.is_enabled() { "enabled" } else { "disabled" };
+        f.write_fmt(format_args!("{} at 0x{:X} ({} bytes, {})", self.name, self.address, self.len(), state))
+    }
+}
+
+/// Registry of non-overlapping patches.
+#[derive(Debug, Clone, Default)]
+pub struct Patches {
+    patches: Vec<Patch>,
+}
+
+impl Patches {
+    pub fn new() -> Patches {
+        Patches::default()
+    }
+
+    pub fn register(&mut self, patch: Patch) -> Result<(), PatchError> {
+        for other in &self.patches {
+            if other.name == patch.name {
+                return Err(PatchError::Duplicate(patch.name));
+            }
+            if other.overlaps(&patch) {
+                return Err(PatchError::Overlap(patch.name, other.name.clone()));
+            }
+        }
+        self.patches.push(patch);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Patch> {
+        self.patches.iter().find(|p| p.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&Patch> {
+        self.patches.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.patches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    pub fn enable<M>(&mut self, name: &str, memory: &mut M) -> Result<(), PatchError> where M: PatchMemory + ?Sized {
+        self.find_mut(name)?.enable(memory)
+    }
+
+    pub fn disable<M>(&mut self, name: &str, memory: &mut M) -> Result<(), PatchError> where M: PatchMemory + ?Sized {
+        self.find_mut(name)?.disable(memory)
+    }
+
+    pub fn set_enabled<M>(&mut self, name: &str, enabled: bool, memory: &mut M) -> Result<(), PatchError> where M: PatchMemory + ?Sized {
+        if enabled {
+            self.enable(name, memory)
+        } else {
+            self.disable(name, memory)
+        }
+    }
+
+    /// Restores every enabled patch, newest first, and returns the ones that failed.
+    pub fn disable_all<M>(&mut self, memory: &mut M) -> Vec<PatchError> where M: PatchMemory + ?Sized {
+        self.patches.iter_mut().rev()
+            .filter_map(|p| p.disable(memory).err())
+            .collect()
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut Patch, PatchError> {
+        self.patches.iter_mut().find(|p| p.name == name)
+            .ok_or_else(|| PatchError::Unknown(String::from(name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory() -> Vec<u8> {
+        (0..64).collect()
+    }
+
+    #[test]
+    fn buffer_bounds() {
+        let mut memory = memory();
+        let mut buf = [0; 4];
+        assert!(memory.read(60, &mut buf));
+        assert_eq!(buf, [60, 61, 62, 63]);
+        assert!(!memory.read(61, &mut buf));
+        assert!(!memory.read(u64::MAX, &mut buf));
+        assert!(!memory.read(u64::MAX - 1, &mut buf));
+        assert!(!memory.write(u64::MAX - 2, &[1, 2, 3, 4]));
+        assert!(memory.read(64, &mut []));
+        assert!(memory.write(0, &[9]));
+        assert_eq!(memory[0], 9);
+    }
+
+    #[test]
+    fn overlap_detection() {
+        let mut patches = Patches::new();
+        patches.register(Patch::new("a", 8, vec![0; 4])).unwrap();
+        patches.register(Patch::new("before", 4, vec![0; 4])).unwrap();
+        patches.register(Patch::new("after", 12, vec![0; 2])).unwrap();
+        assert_eq!(patches.register(Patch::new("inside", 9, vec![0; 1])),
+                   Err(PatchError::Overlap(String::from("inside"), String::from("a"))));
+        assert_eq!(patches.register(Patch::new("spanning", 2, vec![0; 20])),
+                   Err(PatchError::Overlap(String::from("spanning"), String::from("a"))));
+        assert_eq!(patches.register(Patch::new("a", 40, vec![0; 1])), Err(PatchError::Duplicate(String::from("a"))));
+        assert!(Patch::new("top", u64::MAX - 1, vec![0; 4]).overlaps(&Patch::new("last", u64::MAX, vec![0])));
+        assert_eq!(patches.len(), 3);
+    }
+
+    #[test]
+    fn enable_and_restore() {
+        let mut memory = memory();
+        let mut patches = Patches::new();
+        patches.register(Patch::new("nop", 10, vec![0x90; 3])).unwrap();
+        patches.enable("nop", &mut memory).unwrap();
+        assert_eq!(&memory[9..14], &[9, 0x90, 0x90, 0x90, 13]);
+        let patch = patches.get("nop").unwrap();
+        assert!(patch.is_enabled());
+        assert_eq!(patch.original(), Some(&[10, 11, 12][..]));
+        //Enabling twice keeps the first original bytes
+        patches.enable("nop", &mut memory).unwrap();
+        assert_eq!(patches.get("nop").unwrap().original(), Some(&[10, 11, 12][..]));
+        patches.disable("nop", &mut memory).unwrap();
+        assert_eq!(memory, self::memory());
+        assert!(!patches.get("nop").unwrap().is_enabled());
+        patches.set_enabled("nop", true, &mut memory).unwrap();
+        patches.set_enabled("nop", false, &mut memory).unwrap();
+        assert_eq!(memory, self::memory());
+        assert_eq!(patches.enable("missing", &mut memory), Err(PatchError::Unknown(String::from("missing"))));
+    }
+
+    #[test]
+    fn expected_bytes() {
+        let mut memory = memory();
+        let mut patches = Patches::new();
+        patches.register(Patch::new("good", 4, vec![0xC3]).with_expected(Pattern::compile("04 ? 06"))).unwrap();
+        patches.register(Patch::new("bad", 20, vec![0xC3]).with_expected(Pattern::compile("15 16"))).unwrap();
+        patches.enable("good", &mut memory).unwrap();
+        assert_eq!(memory[4], 0xC3);
+        assert_eq!(patches.enable("bad", &mut memory),
+                   Err(PatchError::Mismatch { name: String::from("bad"), found: vec![20, 21] }));
+        assert_eq!(memory[20], 20);
+        assert!(!patches.get("bad").unwrap().is_enabled());
+    }
+
+    #[test]
+    fn inaccessible_and_disable_all() {
+        let mut memory = memory();
+        let mut patches = Patches::new();
+        patches.register(Patch::new("first", 0, vec![0xAA; 2])).unwrap();
+        patches.register(Patch::new("second", 30, vec![0xBB; 2])).unwrap();
+        patches.register(Patch::new("outside", 63, vec![0xCC; 2])).unwrap();
+        patches.enable("first", &mut memory).unwrap();
+        patches.enable("second", &mut memory).unwrap();
+        assert_eq!(patches.enable("outside", &mut memory), Err(PatchError::Inaccessible(String::from("outside"))));
+        assert_eq!(memory[63], 63);
+        assert!(patches.disable_all(&mut memory).is_empty());
+        assert_eq!(memory, self::memory());
+        assert!(patches.iter().all(|p| !p.is_enabled()));
+    }
+}
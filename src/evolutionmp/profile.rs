@@ -0,0 +1,420 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_derive::Serialize;
+
+use crate::hash::Hash;
+use crate::nativedb;
+
+/// Histogram buckets, the last one holding every call of 2^31 ns (about 2 s) and more.
+pub const BUCKETS: usize = 32;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref PROFILER: Mutex<Profiler> = Mutex::new(Profiler::new());
+}
+
+/// Call durations in power of two nanosecond buckets, bucket `i` holding durations below `2^(i + 1)` ns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    total: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram::new()
+    }
+}
+
+impl Histogram {
+    pub const fn new() -> Histogram {
+        Histogram { buckets: [0; BUCKETS], count: 0, total: 0, min: u64::MAX, max: 0 }
+    }
+
+    pub fn bucket(nanos: u64) -> usize {
+        ((u64::BITS - nanos.leading_zeros()) as usize).saturating_sub(1).min(BUCKETS - 1)
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let nanos = duration.as_nanos().min(u64::MAX as u128) as u64;
+        self.buckets[Histogram::bucket(nanos)] += 1;
+        self.count += 1;
+        self.total = self.total.saturating_add(nanos);
+        self.min = self.min.min(nanos);
+        self.max = self.max.max(nanos);
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += count;
+        }
+        self.count += other.count;
+        self.total = self.total.saturating_add(other.total);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn buckets(&self) -> &[u64; BUCKETS] {
+        &self.buckets
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.total)
+    }
+
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.total.checked_div(self.count).unwrap_or(0))
+    }
+
+    pub fn min(&self) -> Duration {
+        Duration::from_nanos(if self.count == 0 { 0 } else { self.min })
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// Upper bound of the bucket holding the given fraction of the calls, clamped to the slowest call.
+    pub fn percentile(&self, fraction: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((fraction.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                if i == BUCKETS - 1 {
+                    break;
+                }
+                return Duration::from_nanos(((1u64 << (i + 1)) - 1).min(self.max));
+            }
+        }
+        self.max()
+    }
+}
+
+/// Timings of a native, for one script or merged across all of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NativeStats {
+    pub hash: u64,
+    /// Hash of the calling script, 0 when merged or made outside of scripts
+    pub script: u32,
+    pub timings: Histogram,
+}
+
+impl NativeStats {
+    /// One line of the on-screen and console summaries.
+    pub fn summary(&self) -> String {
+        let name = match nativedb::lookup(self.hash) {
+            Some(native) => format!("{}::{}", native.namespace, native.name),
+            None => format!("0x{:016X}", self.hash)
+        };
+        format!("{} x{} total {:.2?} mean {:.2?} p99 {:.2?} max {:.2?}", name, self.timings.count(),
+                self.timings.total(), self.timings.mean(), self.timings.percentile(0.99), self.timings.max())
+    }
+}
+
+#[derive(Serialize)]
+struct StatsEntry {
+    hash: String,
+    name: Option<String>,
+    script: Option<String>,
+    calls: u64,
+    total_ns: u64,
+    mean_ns: u64,
+    min_ns: u64,
+    max_ns: u64,
+    p50_ns: u64,
+    p99_ns: u64,
+    buckets: Vec<u64>,
+}
+
+impl From<&NativeStats> for StatsEntry {
+    fn from(stats: &NativeStats) -> Self {
+        let t = &stats.timings;
+        let buckets = t.buckets();
+        let used = buckets.iter().rposition(|c| *c != 0).map_or(0, |i| i + 1);
+        StatsEntry {
+            hash: format!("0x{:016X}", stats.hash),
+            name: nativedb::lookup(stats.hash).map(|n| format!("{}::{}", n.namespace, n.name)),
+            script: if stats.script == 0 { None } else { Some(format!("{}", Hash(stats.script))) },
+            calls: t.count(),
+            total_ns: t.total().as_nanos() as u64,
+            mean_ns: t.mean().as_nanos() as u64,
+            min_ns: t.min().as_nanos() as u64,
+            max_ns: t.max().as_nanos() as u64,
+            p50_ns: t.percentile(0.5).as_nanos() as u64,
+            p99_ns: t.percentile(0.99).as_nanos() as u64,
+            buckets: buckets[..used].to_vec(),
+        }
+    }
+}
+
+/// Call counts and timings by native and calling script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profiler {
+    stats: HashMap<(u64, u32), Histogram>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    pub fn record(&mut self, hash: u64, script: u32, duration: Duration) {
+        self.stats.entry((hash, script)).or_default().record(duration);
+    }
+
+    pub fn merge(&mut self, other: &Profiler) {
+        for (key, timings) in &other.stats {
+            self.stats.entry(*key).or_default().merge(timings);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.stats.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    pub fn calls(&self) -> u64 {
+        self.stats.values().map(|t| t.count()).sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.stats.values().map(|t| t.total()).sum()
+    }
+
+    /// Scripts natives were called from, sorted, 0 standing for calls made outside of scripts.
+    pub fn scripts(&self) -> Vec<u32> {
+        self.stats.keys().map(|(_, script)| *script).collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    pub fn get(&self, hash: u64, script: u32) -> Option<&Histogram> {
+        self.stats.get(&(hash, script))
+    }
+
+    /// Timings of every native merged across scripts, slowest in total first.
+    pub fn by_native(&self) -> Vec<NativeStats> {
+        let mut natives = HashMap::<u64, Histogram>::new();
+        for ((hash, _), timings) in &self.stats {
+            natives.entry(*hash).or_default().merge(timings);
+        }
+        sorted(natives.into_iter().map(|(hash, timings)| NativeStats { hash, script: 0, timings }).collect())
+    }
+
+    /// Timings of the natives called by a script, slowest in total first.
+    pub fn by_script(&self, script: u32) -> Vec<NativeStats> {
+        sorted(self.stats.iter()
+            .filter(|((_, s), _)| *s == script)
+            .map(|((hash, script), timings)| NativeStats { hash: *hash, script: *script, timings: *timings })
+            .collect())
+    }
+
+    /// The `n` natives the most time was spent in, merged across scripts.
+    pub fn top(&self, n: usize) -> Vec<NativeStats> {
+        let mut natives = self.by_native();
+        natives.truncate(n);
+        natives
+    }
+
+    /// Per-native statistics as a JSON array, for one script or merged across all of them.
+    pub fn to_json(&self, script: Option<u32>) -> String {
+        let stats = match script {
+            Some(script) => self.by_script(script),
+            None => self.by_native()
+        };
+        let entries = stats.iter().map(StatsEntry::from).collect::<Vec<_>>();
+        serde_json::to_string(&entries).expect("profile serialization failed")
+    }
+}
+
+fn sorted(mut stats: Vec<NativeStats>) -> Vec<NativeStats> {
+    stats.sort_by(|a, b| b.timings.total().cmp(&a.timings.total()).then(a.hash.cmp(&b.hash)));
+    stats
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Records a call if profiling is on.
+pub fn record(hash: u64, script: u32, duration: Duration) {
+    if enabled() {
+        PROFILER.lock().expect("lock poisoned").record(hash, script, duration);
+    }
+}
+
+/// Copy of the statistics collected so far.
+pub fn snapshot() -> Profiler {
+    PROFILER.lock().expect("lock poisoned").clone()
+}
+
+pub fn reset() {
+    PROFILER.lock().expect("lock poisoned").reset()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nanos(n: u64) -> Duration {
+        Duration::from_nanos(n)
+    }
+
+    fn histogram(durations: &[u64]) -> Histogram {
+        let mut histogram = Histogram::new();
+        for d in durations {
+            histogram.record(nanos(*d));
+        }
+        histogram
+    }
+
+    #[test]
+    fn buckets() {
+        assert_eq!(Histogram::bucket(0), 0);
+        assert_eq!(Histogram::bucket(1), 0);
+        assert_eq!(Histogram::bucket(2), 1);
+        assert_eq!(Histogram::bucket(3), 1);
+        assert_eq!(Histogram::bucket(4), 2);
+        assert_eq!(Histogram::bucket(1023), 9);
+        assert_eq!(Histogram::bucket(1024), 10);
+        assert_eq!(Histogram::bucket(1 << 31), BUCKETS - 1);
+        assert_eq!(Histogram::bucket(u64::MAX), BUCKETS - 1);
+        let histogram = histogram(&[1, 3, 3, 1000]);
+        assert_eq!(histogram.buckets()[..10], [1, 2, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn summary_values() {
+        let empty = Histogram::new();
+        assert_eq!((empty.count(), empty.mean(), empty.min(), empty.max()), (0, Duration::ZERO, Duration::ZERO, Duration::ZERO));
+        assert_eq!(empty.percentile(0.5), Duration::ZERO);
+        let histogram = histogram(&[10, 20, 30, 100]);
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.total(), nanos(160));
+        assert_eq!(histogram.mean(), nanos(40));
+        assert_eq!(histogram.min(), nanos(10));
+        assert_eq!(histogram.max(), nanos(100));
+        let mut huge = Histogram::new();
+        huge.record(Duration::from_secs(u64::MAX));
+        huge.record(Duration::from_secs(1));
+        assert_eq!(huge.total(), nanos(u64::MAX));
+    }
+
+    #[test]
+    fn percentiles() {
+        //Ninety calls of 10 ns, nine of 100 ns and one of 5000 ns
+        let mut durations = vec![10; 90];
+        durations.extend([100; 9]);
+        durations.push(5000);
+        let histogram = histogram(&durations);
+        assert_eq!(histogram.percentile(0.0), nanos(15));
+        assert_eq!(histogram.percentile(0.5), nanos(15));
+        assert_eq!(histogram.percentile(0.9), nanos(15));
+        assert_eq!(histogram.percentile(0.95), nanos(127));
+        assert_eq!(histogram.percentile(0.99), nanos(127));
+        assert_eq!(histogram.percentile(1.0), nanos(5000));
+        assert_eq!(histogram.percentile(2.0), nanos(5000));
+        //Bounds are clamped to the slowest call, the last bucket having none
+        assert_eq!(self::histogram(&[9]).percentile(0.5), nanos(9));
+        assert_eq!(self::histogram(&[1 << 40]).percentile(0.5), nanos(1 << 40));
+    }
+
+    #[test]
+    fn merge() {
+        let mut merged = histogram(&[10, 20]);
+        merged.merge(&histogram(&[5, 1000]));
+        assert_eq!(merged, histogram(&[10, 20, 5, 1000]));
+        merged.merge(&Histogram::new());
+        assert_eq!(merged.min(), nanos(5));
+        let mut empty = Histogram::new();
+        empty.merge(&Histogram::new());
+        assert_eq!(empty, Histogram::new());
+    }
+
+    fn profiler() -> Profiler {
+        let mut profiler = Profiler::new();
+        profiler.record(1, 0, nanos(100));
+        profiler.record(1, 7, nanos(50));
+        profiler.record(1, 7, nanos(50));
+        profiler.record(2, 7, nanos(300));
+        profiler.record(3, 9, nanos(200));
+        profiler
+    }
+
+    #[test]
+    fn by_native_and_script() {
+        let profiler = profiler();
+        assert_eq!(profiler.calls(), 5);
+        assert_eq!(profiler.total(), nanos(700));
+        assert_eq!(profiler.scripts(), vec![0, 7, 9]);
+        assert_eq!(profiler.get(1, 7).map(|h| h.count()), Some(2));
+        assert!(profiler.get(2, 0).is_none());
+
+        let natives = profiler.by_native();
+        assert_eq!(natives.iter().map(|n| (n.hash, n.script, n.timings.total())).collect::<Vec<_>>(),
+                   vec![(2, 0, nanos(300)), (1, 0, nanos(200)), (3, 0, nanos(200))]);
+        //Ties are broken by hash
+        assert_eq!(natives[1].timings.count(), 3);
+        assert_eq!(profiler.top(1).len(), 1);
+        assert_eq!(profiler.top(10).len(), 3);
+
+        let script = profiler.by_script(7);
+        assert_eq!(script.iter().map(|n| (n.hash, n.script, n.timings.count())).collect::<Vec<_>>(),
+                   vec![(2, 7, 1), (1, 7, 2)]);
+        assert!(profiler.by_script(8).is_empty());
+    }
+
+    #[test]
+    fn merge_and_reset() {
+        let mut profiler = profiler();
+        profiler.merge(&profiler.clone());
+        assert_eq!(profiler.calls(), 10);
+        assert_eq!(profiler.get(1, 7).map(|h| h.count()), Some(4));
+        profiler.reset();
+        assert!(profiler.is_empty());
+        assert!(profiler.by_native().is_empty());
+    }
+
+    #[test]
+    fn json() {
+        let mut profiler = Profiler::new();
+        profiler.record(0x7239B21A38F536BA, 0, nanos(3));
+        profiler.record(0x7239B21A38F536BA, 0, nanos(5));
+        let json: serde_json::Value = serde_json::from_str(&profiler.to_json(None)).unwrap();
+        assert_eq!(json, serde_json::json!([{
+            "hash": "0x7239B21A38F536BA",
+            "name": "ENTITY::DOES_ENTITY_EXIST",
+            "script": null,
+            "calls": 2,
+            "total_ns": 8,
+            "mean_ns": 4,
+            "min_ns": 3,
+            "max_ns": 5,
+            "p50_ns": 3,
+            "p99_ns": 5,
+            "buckets": [0, 1, 1]
+        }]));
+        assert_eq!(profiler.to_json(Some(1)), "[]");
+        assert!(profiler.by_native()[0].summary().starts_with("ENTITY::DOES_ENTITY_EXIST x2 total"));
+    }
+}
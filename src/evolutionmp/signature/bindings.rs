@@ -0,0 +1,346 @@
+use std::fmt::{Display, Formatter};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::signature::{Pattern, PatternSet, Resolution};
+
+/// How the bound location is derived from `pattern hit + offset`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BindingKind {
+    /// The location itself, e.g. a function start or an instruction to patch
+    Address,
+    /// RIP-relative reference, the value is the distance from the rel32 to the next instruction
+    Relative(u8),
+    /// Target of the `call rel32` instruction at the location
+    Call,
+    /// 32-bit field offset encoded in the instruction operand at the location
+    Displacement,
+}
+
+/// A single move from a pattern match towards the bound location.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Add(isize),
+    /// Follows a RIP-relative reference, see [`BindingKind::Relative`]
+    Relative(u8),
+    /// Follows the `call rel32` instruction at the location
+    Call,
+}
+
+impl Step {
+    /// Applies the step to `position` within `image`, which is mapped at its RVAs.
+    pub fn apply(&self, image: &[u8], position: i64) -> Option<i64> {
+        let read_i32 = |at: i64| {
+            let at = usize::try_from(at).ok()?;
+            image.get(at..at + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as i64)
+        };
+        match *self {
+            Step::Add(n) => Some(position + n as i64),
+            Step::Relative(len) => Some(position + len as i64 + read_i32(position)?),
+            Step::Call => Some(position + 5 + read_i32(position + 1)?)
+        }
+    }
+}
+
+/// A pattern bound by the client, resolvable without access to the game process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub name: &'static str,
+    pub module: &'static str,
+    pub pattern: &'static str,
+    /// Added to where the pattern lands, for locations its cursor can't reach such as
+    /// a function start before the match
+    pub offset: isize,
+    pub kind: BindingKind,
+}
+
+/// Location a binding resolves to inside a scanned image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Location {
+    Rva(u64),
+    Displacement(i32),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Rva(rva) => f.write_fmt(format_args!("rva 0x{:X}", rva)),
+            Location::Displacement(disp) => f.write_fmt(format_args!("field +0x{:X}", disp))
+        }
+    }
+}
+
+impl Binding {
+    /// Steps leading from the pattern match to the bound location.
+    pub fn steps(&self) -> Vec<Step> {
+        let mut steps = vec![Step::Add(self.offset)];
+        steps.extend(self.follow());
+        steps
+    }
+
+    /// Pointer-follow step implied by the binding kind.
+    pub fn follow(&self) -> Option<Step> {
+        match self.kind {
+            BindingKind::Relative(len) => Some(Step::Relative(len)),
+            BindingKind::Call => Some(Step::Call),
+            BindingKind::Address | BindingKind::Displacement => None
+        }
+    }
+
+    /// Computes the bound location for a pattern match at `hit`, `image` being mapped at its RVAs.
+    pub fn locate(&self, pattern: &Pattern, image: &[u8], hit: usize) -> Option<Location> {
+        self.locate_with(pattern, &self.steps(), image, hit)
+    }
+
+    /// Same as [`Binding::locate`] with the default steps replaced.
+    pub fn locate_with(&self, pattern: &Pattern, steps: &[Step], image: &[u8], hit: usize) -> Option<Location> {
+        let target = pattern.decode(image, hit)?.target;
+        let position = steps.iter().try_fold(target, |position, step| step.apply(image, position))?;
+        if self.kind == BindingKind::Displacement {
+            let at = usize::try_from(position).ok()?;
+            return image.get(at..at + 4)
+                .map(|b| Location::Displacement(i32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+        }
+        if position >= 0 && (position as usize) < image.len() {
+            Some(Location::Rva(position as u64))
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}::{} (`{}` {:+})", self.module, self.name, self.pattern, self.offset))
+    }
+}
+
+/// Outcome of checking a single binding against an image.
+#[derive(Debug, Clone)]
+pub struct BindingReport {
+    pub binding: &'static Binding,
+    pub resolution: Resolution<u64>,
+    pub location: Option<Location>,
+}
+
+impl BindingReport {
+    pub fn is_ok(&self) -> bool {
+        self.resolution.is_unique() && self.location.is_some()
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static Binding> {
+    BINDINGS.iter().find(|b| b.name == name)
+}
+
+/// Resolves every registered binding in a single pass over `image`, which has to be mapped at its RVAs.
+pub fn check(image: &[u8]) -> Vec<BindingReport> {
+    let mut patterns = BINDINGS.iter().map(|b| b.pattern).collect::<Vec<_>>();
+    patterns.sort();
+    patterns.dedup();
+    let set = patterns.iter().collect::<PatternSet>();
+    let resolutions = set.resolve(image);
+    BINDINGS.iter().map(|binding| {
+        let index = patterns.binary_search(&binding.pattern).unwrap();
+        let pattern = set.get(index).unwrap();
+        let resolution = resolutions[index].clone();
+        let location = resolution.as_ref().first()
+            .and_then(|hit| binding.locate(pattern, image, *hit));
+        BindingReport {
+            binding,
+            resolution: resolution.map(|hit| hit as u64),
+            location,
+        }
+    }).collect()
+}
+
+macro_rules! bindings {
+    ($($module:literal => { $(($name:ident, $pattern:literal, $offset:expr, $kind:ident $(($len:literal))?)),* $(,)? })*) => {
+        $($(
+            pub const $name: Binding = Binding {
+                name: stringify!($name),
+                module: $module,
+                pattern: $pattern,
+                offset: $offset,
+                kind: BindingKind::$kind $(($len))?,
+            };
+        )*)*
+
+        pub const BINDINGS: &[Binding] = &[$($($name,)*)*];
+    };
+}
+
+bindings! {
+    "client" => {
+        (GAME_STATE, "83 3D | [rel32+1] ? 75 17 8B 43 20 25", 0, Address),
+        (HEAP_SIZE, "83 C8 01 48 8D 0D ? ? ? ? 41 B1 01 45 33 C0 ? | [rel32]", 0, Address),
+        (DEVICE_LIMIT, "C7 05 ? ? ? ? | 64 00 00 00 48 8B", 0, Address),
+        (FOCUS_PAUSE, "0F 95 05 ? ? ? ? E8 ? ? ? ? 48 85 C0", 0, Address),
+        (FOCUS_PAUSE_FLAG, "0F 95 05 | [rel32] E8 ? ? ? ? 48 85 C0", 0, Address),
+        (RELATIVE_DEVICE_SORTING, "C6 80 F0 00 00 00 01 E8 ? ? ? ? | E8", 0, Address),
+    }
+    "events" => {
+        (CALL_EVENT, "81 BF ? ? 00 00 ? ? 00 00 75 ? 48 8B CF E8", -0x36, Address),
+        (GET_EVENT_DATA, "48 85 C0 74 14 4C 8B 10", -28, Address),
+    }
+    "game" => {
+        (MAIN_FRAME, "32 DB EB 02 B3 01 E8 | [rel32] 48 8B", 0, Address),
+        (SKIP_INIT, "32 DB EB 02 B3 01 E8 ? ? ? ? 48 8B", -9, Call),
+        (LOAD_GAME_NOW, "33 C9 E8 | [rel32] 8B 0D ? ? ? ? 48 8B 5C 24 ? 8D 41 FC 83 F8 01 0F 47 CF 89 0D ? ? ? ?", 0, Address),
+    }
+    "game::locale" => {
+        (GET_TEXT, "48 8B CB 8B D0 E8 | [rel32] 48 85 C0 0F 95 C0", 0, Address),
+        (GET_TEXT2, "48 85 C0 75 34 8B 0D", -5, Call),
+    }
+    "game::ui" => {
+        (GET_WARN_RESULT, "33 D2 33 C9 E8 | [rel32] 48 83 F8 04 0F 84", 0, Address),
+        (ACTIVE_MENU_POOL, "0F B7 54 51 ?", -4, Relative(4)),
+        (VIGNETTING_CALL, "38 51 64 74 19 ? ? ? | ? ? ? ? ?", 0, Address),
+        (VIGNETTE, "38 51 64 74 19", 26, Relative(4)),
+        (TIMESCALE_OVERRIDE, "38 51 64 74 19", 34, Address),
+    }
+    "native" => {
+        (SET_VECTOR_RESULTS, "83 79 18 ? 48 8B D1 74 4A FF 4A 18", 0, Address),
+        (GET_SCRIPT_ENTITY, "44 8B C1 49 8B 41 08 41 C1 F8 08 41 38 0C 00", -12, Address),
+        (EXPANDED_RADAR, "33 C0 0F 57 C0 ? 0D | ?", 0, Address),
+        (REVEAL_FULL_MAP, "33 C0 0F 57 C0 ? 0D", 30, Address),
+        (CURSOR_SPRITE, "74 11 8B D1 48 8D 0D ? ? ? ? 45 33 C0", 0, Address),
+        (NATIVE_TABLE, "76 32 48 8B 53 40 ? ? ? | [rel32]", 0, Address),
+    }
+    "native::alloc" => {
+        (ALLOCATOR_TLS_OFFSET, "B9 | [imm32] 48 8B 0C 01 45 33 C9 49 8B D2 48", 0, Address),
+    }
+    "native::fs" => {
+        (OPEN_PACK_FILES, "41 B0 01 BA 1B E6 DA 93 E8", -12, Call),
+        (ADD_COLLISION, "48 8B FA 89 44 24 30 48 8B D9 | E8 ? ? ? ? 0F", 0, Address),
+        (SOME_FN, "66 39 79 38 74 06 4C 8B 41 30 EB 07 4C 8D ? ? ? ? ? ? | [rel32]", 0, Address),
+        (GET_DEVICE, "41 B8 07 00 00 00 48 8B F1 E8", -0x1F, Address),
+        (MOUNT_GLOBAL, "41 8A F0 48 8B F9 E8 ? ? ? ? 33 DB 85 C0", -0x28, Address),
+        (UNMOUNT, "E8 ? ? ? ? 85 C0 75 23 48 83", -0x22, Address),
+        (PACK_FILE_INIT, "44 89 41 28 4C 89 41 38 4C 89 41 50 48 8D", -0x1E, Address),
+        (PACK_FILE_OPEN, "48 8D 68 98 48 81 EC 40 01 00 00 41 8B F9", -0x18, Address),
+        (PACK_FILE_MOUNT, "84 C0 74 1D 48 85 DB 74 0F 48", -0x1E, Address),
+        (RELATIVE_DEVICE_SET_PATH, "49 8B F9 48 8B D9 4C 8B CA", -0x17, Address),
+        (RELATIVE_DEVICE_MOUNT, "44 8A 81 14 01 00 00 48 8B DA 48 8B F9 48 8B D1", -0xD, Address),
+        (KEY_STATE_INIT, "45 33 F6 48 89 85 30 02 00 00 48 8D 45 30 48", -12, Address),
+        (INITIAL_MOUNT, "48 81 EC E0 03 00 00 48 B8 63 6F 6D 6D", -0x1A, Address),
+        (DEVICE_VTABLE, "48 21 35 ? ? ? ? 48 8B 74 24 38 48 8D 05 | [rel32]", 0, Address),
+        (PACK_FILE_VTABLE, "44 89 41 28 4C 89 41 38 4C 89 41 50 48 8D 05 | [rel32]", 0, Address),
+        (RELATIVE_DEVICE_VTABLE, "48 85 C0 74 11 48 83 63 08 00 48 ? ? | [rel32]", 0, Address),
+        (ENCRYPTING_DEVICE_VTABLE, "45 33 F6 48 89 85 30 02 00 00 48 8D 45 30 48", -4, Relative(4)),
+    }
+    "native::grc" => {
+        (TEXTURE_FACTORY, "84 DB 48 0F 45 C2 48 89 05 | [rel32]", 0, Address),
+        (MISSING_TEXTURE, "45 33 C0 48 8B CF FF 50 20 48 8D 15", 22, Relative(4)),
+    }
+    "native::init_fns" => {
+        (UNK_U8, "E8 | [rel32] 84 C0 74 ? 66 0F 6E 35 ? ? ?", 0, Address),
+        (RUN_INIT, "BA 04 00 00 00 E8 | [rel32] E8 ? ? ? ? E8", 0, Address),
+        (RUN_UPDATE, "48 8D 0D ? ? ? ? BA 01 00 00 00 E8 | [rel32] E8 ? ? ? ?", 0, Address),
+        (RUN_UPDATE_GROUP, "40 53 48 83 EC 20 48 8B 59 20 EB 0D 48 8B 03 48", 0, Address),
+    }
+    "native::pool" => {
+        (PARTICLE_ADDRESS, "74 21 48 8B 48 20 48 85 C9 74 18 48 8B D6 E8", -10, Relative(4)),
+        (ENTITY_ADDRESS, "E8 | [rel32] 48 8B D8 48 85 C0 74 2E 48 83 3D", 0, Address),
+        (PLAYER_ADDRESS, "B2 01 E8 | [rel32] 48 85 C0 74 1C 8A 88", 0, Address),
+        (ENTITY_ADD_TO_POOL, "48 89 5C 24 ? 48 89 74 24 ? 57 48 83 EC 20 8B 15 ? ? ? ? 48 8B F9 48 83 C1 10 33 DB", 0, Address),
+        (ENTITY_POS, "48 8B DA E8 ? ? ? ? F3 0F 10 44 24", -6, Address),
+        (PED, "48 8B 05 | [rel32] 41 0F BF C8 0F BF 40 10", 0, Address),
+        (PROP, "48 8B 05 | [rel32] 8B 78 10 85 FF", 0, Address),
+        (GLOBAL, "4C 8B 0D | [rel32] 44 8B C1 49 8B 41 08", 0, Address),
+        (VEHICLE, "48 8B 05 | [rel32] F3 0F 59 F6 48 8B 08", 0, Address),
+        (PICKUP, "4C 8B 05 | [rel32] 40 8A F2 8B E9", 0, Address),
+    }
+    "native::script" => {
+        (SCRIPT_TLS_OFFSET, "48 8B 04 D0 4A 8B 14 00 48 8B 01 F3 44 0F 2C 42 20", -4, Address),
+        (THREAD_COLLECTION, "48 8B C8 EB 03 49 8B CD 48 8B 05 | [rel32]", 0, Address),
+        (THREAD_COUNT, "FF 0D | [rel32] 48 8B D9 75", 0, Address),
+        (SCRIPT_MANAGER, "74 17 48 8B C8 E8 ? ? ? ? 48 8D 0D | [rel32]", 0, Address),
+        (SCRIPT_THREAD_INIT, "83 89 ? 01 00 00 FF 83 A1 ? 01 00 00 F0", 0, Address),
+        (SCRIPT_THREAD_KILL, "48 83 EC 20 48 83 B9 ? 01 00 00 00 48 8B D9 74 14", -6, Address),
+        (SCRIPT_THREAD_TICK, "80 B9 ? 01 00 00 00 8B FA 48 8B D9 74 05", -0xF, Address),
+        (SCRIPT_POST_INIT, "BA 2F 7B 2E 30 41 B8 0A ? ? ? ? | [rel32]", 0, Address),
+        (SCRIPT_STARTUP, "83 FB FF 0F 84 D6 00 00 00", -0x37, Address),
+        (SCRIPT_RESET, "48 63 18 83 FB FF 0F 84 D6", -0x34, Address),
+        (SCRIPT_RUN, "48 83 EC 20 80 B9 ? 01 00 00 00 8B FA", -0xB, Address),
+        (SCRIPT_ACCESS, "74 3C 48 8B 01 FF 50 10 84 C0", -0x1A, Address),
+    }
+    "native::streaming" => {
+        (INIT_MANIFEST_CHUNK, "48 8D 4F 10 B2 01 48 89 2F", -0x2E, Address),
+        (LOAD_MANIFEST_CHUNK, "45 38 AE C0 00 00 00 0F 95 C3 E8", -5, Address),
+        (CLEAR_MANIFEST_CHUNK, "33 FF 48 8D 4B 10 B2 01", -0x15, Address),
+        (ADD_PACK_FILE, "EB 15 48 8B 0B 40 38 7B 0C 74 07 | E8", 0, Address),
+        (REMOVE_PACK_FILE, "EB 15 48 8B 0B 40 38 7B 0C 74 07 E8 ? ? ? ? ? ? | ?", 0, Address),
+        (MANIFEST_CHUNK, "83 F9 08 75 43 48 8D 0D | ?", 0, Address),
+        (MOUNTERS, "48 63 82 90 00 00 00 49 8B 8C C0 | [rel32] 48", 0, Address),
+        (DATA_TYPES, "61 44 DF 04 00 00 00 00", 0, Relative(4)),
+    }
+    "native::vehicle" => {
+        (VEHICLE_GEARS, "48 8D 8F | [disp32] 4C 8B C3 F3 0F 11 7C 24", 0, Displacement),
+        (VEHICLE_FUEL_LEVEL, "74 26 0F 57 C9 ? ? ? | [disp32]", 0, Displacement),
+        (VEHICLE_OIL_LEVEL, "48 3B CA 0F 84 ? ? ? ? 8B 81", 61, Displacement),
+        (VEHICLE_LIGHTS, "FD 02 DB 08 98 ? ? ? ? 48 8B 5C 24 30", -4, Displacement),
+        (VEHICLE_WHEEL_SPEED, "F3 0F 10 8F | [disp32] F3 0F 59 05 ? ? ? ?", 0, Displacement),
+        (VEHICLE_RPM, "76 03 0F 28 F0 F3 44 0F 10 93 | [disp32]", 0, Displacement),
+        (VEHICLE_DASHBOARD_SPEED, "0F 84 ? ? ? ? 44 89 AE | [disp32] 44 84 F3", 0, Displacement),
+        (VEHICLE_STEERING, "74 0A F3 0F 11 B3 | [disp32] EB 25", 0, Displacement),
+        (VEHICLE_HANDBRAKE, "8A C2 24 01 C0 E0 04 08 81", 19, Displacement),
+        (VEHICLE_ENGINE_TEMPERATURE, "48 8D 8F ? ? ? ? 45 32 FF", -4, Displacement),
+        (VEHICLE_TRAIN_TRACK_NODE, "E8 ? ? ? ? 40 8A F8 84 C0 75 ? 48 8B CB E8", -4, Displacement),
+        (VEHICLE_ALARM_TIME, "24 07 3C 03 74 ? E8", 52, Displacement),
+        (VEHICLE_TURBO, "F3 0F 10 9F | [disp32] 0F 2F DF 73 0A", 0, Displacement),
+    }
+    "win::direct" => {
+        (GET_SWAP_CHAIN, "48 8B 05 ? ? ? ? C3 48 8B C1 8D 4A 0E", 0, Address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_parse_and_names_are_unique() {
+        let mut names = std::collections::HashSet::new();
+        for binding in BINDINGS {
+            assert!(Pattern::parse(binding.pattern).is_ok(), "{}", binding);
+            assert!(names.insert(binding.name), "duplicate binding {}", binding.name);
+        }
+    }
+
+    //Bindings land through their cursor, so a rel32 capture replaces `Relative` and `Call`
+    #[test]
+    fn cursor_bindings_have_no_offset() {
+        for binding in BINDINGS {
+            let pattern = Pattern::compile(binding.pattern);
+            if pattern.cursor() != 0 {
+                assert_eq!(binding.offset, 0, "{}", binding);
+                assert!(matches!(binding.kind, BindingKind::Address | BindingKind::Displacement), "{}", binding);
+            }
+        }
+    }
+
+    #[test]
+    fn locate_kinds() {
+        //mov rax, [rip + 0x20]; call rel32 -0x10; mov ecx, [rdi + 0x1C8]
+        let mut image = vec![0xCC; 0x40];
+        image[0x10..0x17].copy_from_slice(&[0x48, 0x8B, 0x05, 0x20, 0x00, 0x00, 0x00]);
+        image[0x17..0x1C].copy_from_slice(&[0xE8, 0xF0, 0xFF, 0xFF, 0xFF]);
+        image[0x1C..0x22].copy_from_slice(&[0x8B, 0x8F, 0xC8, 0x01, 0x00, 0x00]);
+        let binding = |pattern, offset, kind| Binding { name: "TEST", module: "test", pattern, offset, kind };
+        let locate = |b: Binding| {
+            let pattern = Pattern::compile(b.pattern);
+            let hit = pattern.scan(&image).unwrap();
+            b.locate(&pattern, &image, hit)
+        };
+        assert_eq!(locate(binding("48 8B 05 | [rel32] E8", 0, BindingKind::Address)), Some(Location::Rva(0x37)));
+        assert_eq!(locate(binding("48 8B 05", 3, BindingKind::Relative(4))), Some(Location::Rva(0x37)));
+        assert_eq!(locate(binding("48 8B 05 ? ? ? ? E8 | [rel32]", 0, BindingKind::Address)), Some(Location::Rva(0x0C)));
+        assert_eq!(locate(binding("48 8B 05 ? ? ? ? E8", 7, BindingKind::Call)), Some(Location::Rva(0x0C)));
+        assert_eq!(locate(binding("8B 8F | [disp32]", 0, BindingKind::Displacement)), Some(Location::Displacement(0x1C8)));
+        assert_eq!(locate(binding("E8 | [rel32] 8B 8F", -7, BindingKind::Address)), Some(Location::Rva(0x05)));
+        //Landing outside of the image
+        assert_eq!(locate(binding("48 8B 05", -0x20, BindingKind::Address)), None);
+    }
+}
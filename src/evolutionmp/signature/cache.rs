@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::signature::{Image, Pattern, Resolution};
+
+pub const CACHE_FILE: &str = "patterns.json";
+
+/// Identifies a build of the executable without hashing all of its bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub size_of_image: u32,
+    pub timestamp: u32,
+    pub checksum: u32,
+}
+
+impl Fingerprint {
+    pub fn of(image: &Image) -> Fingerprint {
+        Fingerprint {
+            size_of_image: image.size_of_image,
+            timestamp: image.timestamp,
+            checksum: image.checksum,
+        }
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{:08X}-{:08X}-{:08X}", self.size_of_image, self.timestamp, self.checksum))
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Io(e) => e.fmt(f),
+            CacheError::Json(e) => e.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<std::io::Error> for CacheError {
+    fn from(e: std::io::Error) -> Self {
+        CacheError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for CacheError {
+    fn from(e: serde_json::Error) -> Self {
+        CacheError::Json(e)
+    }
+}
+
+/// Pattern matches of one executable, stored as RVAs so they survive relocation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternCache {
+    fingerprint: Fingerprint,
+    patterns: BTreeMap<String, Resolution<u64>>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl PatternCache {
+    pub fn new(fingerprint: Fingerprint) -> PatternCache {
+        PatternCache {
+            fingerprint,
+            patterns: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Reads the cache, starting over if it is missing, unreadable or made for another executable.
+    pub fn load<P>(path: P, fingerprint: Fingerprint) -> PatternCache where P: AsRef<Path> {
+        std::fs::read_to_string(path).ok()
+            .and_then(|json| PatternCache::parse(&json).ok())
+            .filter(|cache| cache.fingerprint == fingerprint)
+            .unwrap_or_else(|| PatternCache::new(fingerprint))
+    }
+
+    pub fn parse(json: &str) -> Result<PatternCache, CacheError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, CacheError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save<P>(&mut self, path: P) -> Result<(), CacheError> where P: AsRef<Path> {
+        std::fs::write(path, self.to_json()?)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    pub fn get(&self, pattern: &str) -> Option<&Resolution<u64>> {
+        self.patterns.get(pattern)
+    }
+
+    //Misses aren't cached, they can't be verified without scanning again
+    pub fn insert<P>(&mut self, pattern: P, resolution: Resolution<u64>) where P: Into<String> {
+        if resolution.hits() == 0 {
+            return;
+        }
+        let pattern = pattern.into();
+        if self.patterns.get(&pattern) != Some(&resolution) {
+            self.patterns.insert(pattern, resolution);
+            self.dirty = true;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether entries were added or dropped since the cache was loaded or saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Drops every entry whose pattern no longer matches at one of its cached RVAs,
+    /// `read` filling a buffer with the bytes found at an RVA. Returns the dropped patterns.
+    pub fn retain_valid<F>(&mut self, mut read: F) -> Vec<String> where F: FnMut(u64, &mut [u8]) -> bool {
+        let mut dropped = Vec::new();
+        self.patterns.retain(|pattern, resolution| {
+            let valid = match Pattern::parse(pattern) {
+                Ok(compiled) => {
+                    let mut buf = vec![0; compiled.len()];
+                    let hits = resolution.clone().into_hits();
+                    !hits.is_empty() && hits.into_iter().all(|rva| read(rva, &mut buf) && compiled.matches(&buf))
+                }
+                Err(_) => false
+            };
+            if !valid {
+                dropped.push(pattern.clone());
+            }
+            valid
+        });
+        self.dirty |= !dropped.is_empty();
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: Fingerprint = Fingerprint { size_of_image: 0x3A00000, timestamp: 0x5F0C6F2B, checksum: 0x1234 };
+
+    fn image() -> Vec<u8> {
+        let mut image = vec![0xCC; 0x100];
+        image[0x10..0x14].copy_from_slice(&[0x48, 0x8B, 0x05, 0x01]);
+        image[0x80..0x84].copy_from_slice(&[0x48, 0x8B, 0x05, 0x02]);
+        image[0x40..0x42].copy_from_slice(&[0xE8, 0x00]);
+        image
+    }
+
+    fn read(image: &[u8]) -> impl FnMut(u64, &mut [u8]) -> bool + '_ {
+        move |rva, buf| match image.get(rva as usize..rva as usize + buf.len()) {
+            Some(bytes) => {
+                buf.copy_from_slice(bytes);
+                true
+            }
+            None => false
+        }
+    }
+
+    fn cache() -> PatternCache {
+        let mut cache = PatternCache::new(FINGERPRINT);
+        cache.insert("48 8B 05 ?", Resolution::from_hits(vec![0x80, 0x10]));
+        cache.insert("E8 00", Resolution::from_hits(vec![0x40]));
+        cache
+    }
+
+    #[test]
+    fn insert_marks_dirty() {
+        let mut cache = PatternCache::new(FINGERPRINT);
+        cache.insert("E8", Resolution::from_hits(vec![]));
+        assert!(cache.is_empty() && !cache.is_dirty());
+        cache.insert("E8", Resolution::from_hits(vec![0x40]));
+        assert!(cache.is_dirty());
+        assert_eq!(cache.get("E8").and_then(|r| r.clone().unique()), Some(0x40));
+        cache.dirty = false;
+        cache.insert(String::from("E8"), Resolution::from_hits(vec![0x40]));
+        assert!(!cache.is_dirty());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let cache = cache();
+        let json = cache.to_json().unwrap();
+        let parsed = PatternCache::parse(&json).unwrap();
+        assert_eq!(parsed.patterns, cache.patterns);
+        assert_eq!(parsed.fingerprint(), FINGERPRINT);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.get("48 8B 05 ?").unwrap().clone().into_hits(), vec![0x80, 0x10]);
+        assert!(!parsed.is_dirty());
+        assert!(matches!(PatternCache::parse("{}"), Err(CacheError::Json(_))));
+        assert_eq!(FINGERPRINT.to_string(), "03A00000-5F0C6F2B-00001234");
+    }
+
+    #[test]
+    fn load_checks_fingerprint() {
+        let path = std::env::temp_dir().join(format!("evolutionmp-cache-{}.json", std::process::id()));
+        let mut cache = cache();
+        cache.save(&path).unwrap();
+        assert!(!cache.is_dirty());
+        assert_eq!(PatternCache::load(&path, FINGERPRINT).len(), 2);
+        let other = Fingerprint { timestamp: FINGERPRINT.timestamp + 1, ..FINGERPRINT };
+        let reloaded = PatternCache::load(&path, other);
+        assert!(reloaded.is_empty());
+        assert_eq!(reloaded.fingerprint(), other);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(PatternCache::load(&path, FINGERPRINT).is_empty());
+        std::fs::remove_file(&path).unwrap();
+        assert!(PatternCache::load(&path, FINGERPRINT).is_empty());
+    }
+
+    #[test]
+    fn retain_valid() {
+        let mut image = image();
+        let mut cache = cache();
+        cache.dirty = false;
+        assert!(cache.retain_valid(read(&image)).is_empty());
+        assert!(!cache.is_dirty());
+
+        //A single moved hit invalidates the whole entry
+        image[0x80] = 0x90;
+        assert_eq!(cache.retain_valid(read(&image)), vec![String::from("48 8B 05 ?")]);
+        assert!(cache.is_dirty());
+        assert_eq!(cache.len(), 1);
+
+        //Hits past the end of the image are dropped too
+        cache.insert("E8 00", Resolution::from_hits(vec![0xFF]));
+        cache.insert("not a pattern", Resolution::from_hits(vec![0x40]));
+        let mut dropped = cache.retain_valid(read(&image));
+        dropped.sort();
+        assert_eq!(dropped, vec![String::from("E8 00"), String::from("not a pattern")]);
+        assert!(cache.is_empty());
+    }
+}
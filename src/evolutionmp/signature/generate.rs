@@ -0,0 +1,153 @@
+use std::fmt::{Display, Formatter};
+
+use iced_x86::{Code, Decoder, DecoderOptions};
+
+use crate::signature::{Pattern, PatternByte};
+
+//Signatures are not grown past this many bytes
+const MAX_SIGNATURE_LEN: usize = 96;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    OutOfBounds(u64),
+    InvalidInstruction(u64),
+    NotUnique(Pattern, usize),
+}
+
+impl Display for GenerateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateError::OutOfBounds(rva) => f.write_fmt(format_args!("rva 0x{:X} is outside of the image", rva)),
+            GenerateError::InvalidInstruction(rva) => f.write_fmt(format_args!("invalid instruction at rva 0x{:X}", rva)),
+            GenerateError::NotUnique(pattern, hits) => {
+                f.write_fmt(format_args!("`{}` still has {} hits at its maximum length", pattern, hits))
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Builds the shortest instruction-aligned signature starting at `rva` that is unique in `image`.
+///
+/// Operands that move between builds are wildcarded: rel32 branch targets, 32-bit displacements
+/// (including every RIP-relative reference) and immediates of 4 bytes or more.
+pub fn generate(image: &[u8], rva: u64) -> Result<Pattern, GenerateError> {
+    let start = rva as usize;
+    if start >= image.len() {
+        return Err(GenerateError::OutOfBounds(rva));
+    }
+    let code = &image[start..image.len().min(start + MAX_SIGNATURE_LEN)];
+    let mut decoder = Decoder::new(64, code, DecoderOptions::NONE);
+    decoder.set_ip(rva);
+
+    let mut bytes = Vec::new();
+    let mut candidates: Option<Vec<usize>> = None;
+    while decoder.can_decode() {
+        let position = decoder.position();
+        let instruction = decoder.decode();
+        if instruction.code() == Code::INVALID {
+            if bytes.is_empty() {
+                return Err(GenerateError::InvalidInstruction(rva + position as u64));
+            }
+            break;
+        }
+        let offsets = decoder.get_constant_offsets(&instruction);
+        let mut masked = code[position..position + instruction.len()].iter()
+            .map(|b| PatternByte::exact(*b))
+            .collect::<Vec<_>>();
+        if offsets.has_displacement() && offsets.displacement_size() >= 4 {
+            wildcard(&mut masked, offsets.displacement_offset(), offsets.displacement_size());
+        }
+        if offsets.has_immediate() && offsets.immediate_size() >= 4 {
+            wildcard(&mut masked, offsets.immediate_offset(), offsets.immediate_size());
+        }
+        bytes.extend(masked);
+
+        let pattern = Pattern::new(trim(&bytes));
+        let hits = match candidates.take() {
+            Some(previous) => previous.into_iter().filter(|c| pattern.matches(&image[*c..])).collect(),
+            None => pattern.scan_all(image)
+        };
+        if hits.len() == 1 {
+            return Ok(pattern);
+        }
+        candidates = Some(hits);
+    }
+    let hits = candidates.map(|c| c.len()).unwrap_or(0);
+    Err(GenerateError::NotUnique(Pattern::new(trim(&bytes)), hits))
+}
+
+fn wildcard(bytes: &mut [PatternByte], offset: usize, len: usize) {
+    for b in &mut bytes[offset..offset + len] {
+        *b = PatternByte::ANY;
+    }
+}
+
+//Trailing wildcards don't narrow the search down
+fn trim(bytes: &[PatternByte]) -> Vec<PatternByte> {
+    let len = bytes.iter().rposition(|b| b.fixed().is_some()).map(|i| i + 1).unwrap_or(0);
+    bytes[..len].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //mov rax, [rip + disp32]; call rel32; mov rax, imm64; mov ecx, imm32; mov ecx, [rdi + disp32]; add eax, imm8
+    fn code(imm8: u8) -> Vec<u8> {
+        let mut code = vec![0x48, 0x8B, 0x05, 0x11, 0x22, 0x33, 0x44];
+        code.extend([0xE8, 0x55, 0x66, 0x77, 0x88]);
+        code.extend([0x48, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8]);
+        code.extend([0xB9, 0x78, 0x56, 0x34, 0x12]);
+        code.extend([0x8B, 0x8F, 0x10, 0x01, 0x00, 0x00]);
+        code.extend([0x83, 0xC0, imm8]);
+        code
+    }
+
+    fn image(copies: &[Vec<u8>]) -> Vec<u8> {
+        let mut image = vec![0xCC; 0x10];
+        for copy in copies {
+            image.extend(copy);
+            image.extend([0xCC; 0x10]);
+        }
+        image
+    }
+
+    #[test]
+    fn wildcards_moving_operands() {
+        //The copies only differ by the last immediate, so the signature has to run up to it
+        let image = image(&[code(1), code(2)]);
+        let pattern = generate(&image, 0x10).unwrap();
+        assert_eq!(pattern.to_string(),
+                   "48 8B 05 ? ? ? ? E8 ? ? ? ? 48 B8 ? ? ? ? ? ? ? ? B9 ? ? ? ? 8B 8F ? ? ? ? 83 C0 01");
+        assert_eq!(pattern.scan_all(&image), vec![0x10]);
+    }
+
+    #[test]
+    fn stops_at_first_unique_instruction() {
+        let image = image(&[code(1)]);
+        let pattern = generate(&image, 0x10).unwrap();
+        //Trailing wildcards of the RIP-relative displacement are trimmed
+        assert_eq!(pattern.to_string(), "48 8B 05");
+    }
+
+    #[test]
+    fn errors() {
+        //Repeating code stays ambiguous past the maximum signature length
+        let same = image(&vec![code(1); 6]);
+        let image = image(&[code(1)]);
+        assert_eq!(generate(&image, image.len() as u64), Err(GenerateError::OutOfBounds(image.len() as u64)));
+        //push es doesn't exist in 64-bit mode
+        let invalid = [0xCC, 0x06, 0xCC];
+        assert_eq!(generate(&invalid, 1), Err(GenerateError::InvalidInstruction(1)));
+        match generate(&same, 0x10) {
+            Err(GenerateError::NotUnique(pattern, hits)) => {
+                assert!(hits > 1);
+                assert_eq!(pattern.len(), MAX_SIGNATURE_LEN);
+                assert_eq!(pattern.scan_all(&same).len(), hits);
+            }
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+}
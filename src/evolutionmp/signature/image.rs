@@ -0,0 +1,354 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+const IMAGE_DOS_SIGNATURE: u16 = 0x5A4D;
+const IMAGE_NT_SIGNATURE: u32 = 0x0000_4550;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20B;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    Truncated,
+    BadSignature,
+    Unsupported(u16),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Io(e) => e.fmt(f),
+            ImageError::Truncated => f.write_str("image is truncated"),
+            ImageError::BadSignature => f.write_str("not a PE image"),
+            ImageError::Unsupported(magic) => f.write_fmt(format_args!("unsupported optional header magic 0x{:X}", magic))
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(e: std::io::Error) -> Self {
+        ImageError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub rva: u32,
+    pub virtual_size: u32,
+    pub characteristics: u32,
+}
+
+impl Section {
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+
+    pub fn contains(&self, rva: u64) -> bool {
+        rva >= self.rva as u64 && rva < self.rva as u64 + self.virtual_size as u64
+    }
+}
+
+/// A 64-bit PE image laid out the way the loader maps it, so buffer offsets are RVAs.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub base: u64,
+    pub timestamp: u32,
+    pub checksum: u32,
+    pub size_of_image: u32,
+    pub sections: Vec<Section>,
+    data: Vec<u8>,
+}
+
+struct Headers {
+    base: u64,
+    timestamp: u32,
+    checksum: u32,
+    size_of_image: usize,
+    size_of_headers: usize,
+    sections: Vec<(Section, usize, usize)>,
+}
+
+impl Image {
+    /// Loads a PE file from disk; `mapped` tells that the file is a memory dump of the loaded module.
+    pub fn load<P>(path: P, mapped: bool) -> Result<Image, ImageError> where P: AsRef<Path> {
+        let file = std::fs::read(path)?;
+        if mapped {
+            Image::from_dump(file)
+        } else {
+            Image::from_file(&file)
+        }
+    }
+
+    /// Maps the sections of an on-disk PE file to their RVAs.
+    pub fn from_file(file: &[u8]) -> Result<Image, ImageError> {
+        let headers = parse_headers(file)?;
+        let mut data = vec![0; headers.size_of_image];
+        let header_len = headers.size_of_headers.min(file.len()).min(data.len());
+        data[..header_len].copy_from_slice(&file[..header_len]);
+        for (section, raw_offset, raw_size) in &headers.sections {
+            let rva = section.rva as usize;
+            let len = (*raw_size).min(section.virtual_size as usize)
+                .min(file.len().saturating_sub(*raw_offset))
+                .min(data.len().saturating_sub(rva));
+            //Sections lying past the end of the file or of the image have nothing to map
+            if len == 0 {
+                continue;
+            }
+            data[rva..rva + len].copy_from_slice(&file[*raw_offset..*raw_offset + len]);
+        }
+        Ok(Image::new(headers, data))
+    }
+
+    /// Uses a dump of the module as it was mapped by the loader, starting at its image base.
+    pub fn from_dump(dump: Vec<u8>) -> Result<Image, ImageError> {
+        let headers = parse_headers(&dump)?;
+        Ok(Image::new(headers, dump))
+    }
+
+    fn new(headers: Headers, data: Vec<u8>) -> Image {
+        Image {
+            base: headers.base,
+            timestamp: headers.timestamp,
+            checksum: headers.checksum,
+            size_of_image: headers.size_of_image as u32,
+            sections: headers.sections.into_iter().map(|(s, _, _)| s).collect(),
+            data,
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn section(&self, rva: u64) -> Option<&Section> {
+        self.sections.iter().find(|s| s.contains(rva))
+    }
+}
+
+fn parse_headers(buf: &[u8]) -> Result<Headers, ImageError> {
+    if read_u16(buf, 0)? != IMAGE_DOS_SIGNATURE {
+        return Err(ImageError::BadSignature);
+    }
+    let nt = read_u32(buf, 0x3C)? as usize;
+    if read_u32(buf, nt)? != IMAGE_NT_SIGNATURE {
+        return Err(ImageError::BadSignature);
+    }
+    let file_header = nt + 4;
+    let section_count = read_u16(buf, file_header + 2)? as usize;
+    let timestamp = read_u32(buf, file_header + 4)?;
+    let optional_header_size = read_u16(buf, file_header + 16)? as usize;
+    let optional_header = file_header + 20;
+    let magic = read_u16(buf, optional_header)?;
+    if magic != IMAGE_NT_OPTIONAL_HDR64_MAGIC {
+        return Err(ImageError::Unsupported(magic));
+    }
+    let base = read_u64(buf, optional_header + 24)?;
+    let size_of_image = read_u32(buf, optional_header + 56)? as usize;
+    let size_of_headers = read_u32(buf, optional_header + 60)? as usize;
+    let checksum = read_u32(buf, optional_header + 64)?;
+
+    let mut sections = Vec::with_capacity(section_count);
+    let table = optional_header + optional_header_size;
+    for i in 0..section_count {
+        let header = table + i * 40;
+        let name = buf.get(header..header + 8).ok_or(ImageError::Truncated)?;
+        let name = String::from_utf8_lossy(name).trim_end_matches('\0').to_string();
+        let section = Section {
+            name,
+            virtual_size: read_u32(buf, header + 8)?,
+            rva: read_u32(buf, header + 12)?,
+            characteristics: read_u32(buf, header + 36)?,
+        };
+        let raw_size = read_u32(buf, header + 16)? as usize;
+        let raw_offset = read_u32(buf, header + 20)? as usize;
+        sections.push((section, raw_offset, raw_size));
+    }
+
+    Ok(Headers { base, timestamp, checksum, size_of_image, size_of_headers, sections })
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Result<u16, ImageError> {
+    buf.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or(ImageError::Truncated)
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Result<u32, ImageError> {
+    buf.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or(ImageError::Truncated)
+}
+
+fn read_u64(buf: &[u8], at: usize) -> Result<u64, ImageError> {
+    let lo = read_u32(buf, at)? as u64;
+    let hi = read_u32(buf, at + 4)? as u64;
+    Ok(hi << 32 | lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signature::bindings::{self, Location};
+
+    use super::*;
+
+    const HEADERS: usize = 0x400;
+    const SECTION_TABLE: usize = 0x188;
+
+    struct RawSection {
+        name: &'static str,
+        rva: u32,
+        virtual_size: u32,
+        raw_offset: u32,
+        raw_size: u32,
+        characteristics: u32,
+    }
+
+    fn put(file: &mut [u8], at: usize, bytes: &[u8]) {
+        file[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+
+    //PE32+ headers followed by the raw data of the sections, filled with the first letter of their name
+    fn pe(sections: &[RawSection], size_of_image: u32) -> Vec<u8> {
+        let end = sections.iter().map(|s| (s.raw_offset + s.raw_size) as usize).max().unwrap_or(0).max(HEADERS);
+        let mut file = vec![0; end];
+        put(&mut file, 0, &IMAGE_DOS_SIGNATURE.to_le_bytes());
+        put(&mut file, 0x3C, &0x80u32.to_le_bytes());
+        put(&mut file, 0x80, &IMAGE_NT_SIGNATURE.to_le_bytes());
+        put(&mut file, 0x84, &0x8664u16.to_le_bytes());
+        put(&mut file, 0x86, &(sections.len() as u16).to_le_bytes());
+        put(&mut file, 0x88, &0x5F0C_6F2Bu32.to_le_bytes());
+        put(&mut file, 0x94, &0xF0u16.to_le_bytes());
+        put(&mut file, 0x98, &IMAGE_NT_OPTIONAL_HDR64_MAGIC.to_le_bytes());
+        put(&mut file, 0x98 + 24, &0x1_4000_0000u64.to_le_bytes());
+        put(&mut file, 0x98 + 56, &size_of_image.to_le_bytes());
+        put(&mut file, 0x98 + 60, &(HEADERS as u32).to_le_bytes());
+        put(&mut file, 0x98 + 64, &0x1234u32.to_le_bytes());
+        for (i, section) in sections.iter().enumerate() {
+            let header = SECTION_TABLE + i * 40;
+            put(&mut file, header, section.name.as_bytes());
+            put(&mut file, header + 8, &section.virtual_size.to_le_bytes());
+            put(&mut file, header + 12, &section.rva.to_le_bytes());
+            put(&mut file, header + 16, &section.raw_size.to_le_bytes());
+            put(&mut file, header + 20, &section.raw_offset.to_le_bytes());
+            put(&mut file, header + 36, &section.characteristics.to_le_bytes());
+            let raw = section.raw_offset as usize..(section.raw_offset + section.raw_size) as usize;
+            file[raw].fill(section.name.as_bytes()[1]);
+        }
+        file
+    }
+
+    fn sections() -> Vec<RawSection> {
+        vec![
+            RawSection { name: ".text", rva: 0x1000, virtual_size: 0x80, raw_offset: 0x400, raw_size: 0x100, characteristics: 0x6000_0020 },
+            RawSection { name: ".data", rva: 0x2000, virtual_size: 0x200, raw_offset: 0x500, raw_size: 0x40, characteristics: 0xC000_0040 },
+        ]
+    }
+
+    #[test]
+    fn headers() {
+        let image = Image::from_file(&pe(&sections(), 0x3000)).unwrap();
+        assert_eq!(image.base, 0x1_4000_0000);
+        assert_eq!(image.timestamp, 0x5F0C_6F2B);
+        assert_eq!(image.checksum, 0x1234);
+        assert_eq!(image.size_of_image, 0x3000);
+        assert_eq!(image.sections, vec![
+            Section { name: String::from(".text"), rva: 0x1000, virtual_size: 0x80, characteristics: 0x6000_0020 },
+            Section { name: String::from(".data"), rva: 0x2000, virtual_size: 0x200, characteristics: 0xC000_0040 },
+        ]);
+        assert!(image.sections[0].is_executable() && !image.sections[1].is_executable());
+        assert_eq!(image.section(0x107F).map(|s| s.name.as_str()), Some(".text"));
+        assert_eq!(image.section(0x1080), None);
+        assert_eq!(image.section(0x21FF).map(|s| s.name.as_str()), Some(".data"));
+    }
+
+    #[test]
+    fn file_and_dump_mapping() {
+        let file = pe(&sections(), 0x3000);
+        let image = Image::from_file(&file).unwrap();
+        let data = image.data();
+        assert_eq!(data.len(), 0x3000);
+        assert_eq!(&data[..HEADERS], &file[..HEADERS]);
+        //Raw data is cut at the virtual size, and the rest of a section is zeroed
+        assert!(data[0x1000..0x1080].iter().all(|&b| b == b't'));
+        assert_eq!(data[0x1080], 0);
+        assert!(data[0x2000..0x2040].iter().all(|&b| b == b'd'));
+        assert!(data[0x2040..0x2200].iter().all(|&b| b == 0));
+
+        let dump = Image::from_dump(data.to_vec()).unwrap();
+        assert_eq!(dump.data(), data);
+        assert_eq!(dump.sections, image.sections);
+
+        let path = std::env::temp_dir().join(format!("evolutionmp-image-{}.exe", std::process::id()));
+        std::fs::write(&path, &file).unwrap();
+        assert_eq!(Image::load(&path, false).unwrap().data(), data);
+        assert_eq!(Image::load(&path, true).unwrap().data(), &file[..]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Image::load(&path, false), Err(ImageError::Io(_))));
+    }
+
+    #[test]
+    fn bad_headers() {
+        assert!(matches!(Image::from_file(&[]), Err(ImageError::Truncated)));
+        let file = pe(&sections(), 0x3000);
+        let mut bad = file.clone();
+        bad[0] = b'X';
+        assert!(matches!(Image::from_file(&bad), Err(ImageError::BadSignature)));
+        let mut bad = file.clone();
+        put(&mut bad, 0x80, b"NE\0\0");
+        assert!(matches!(Image::from_file(&bad), Err(ImageError::BadSignature)));
+        let mut bad = file.clone();
+        put(&mut bad, 0x3C, &0xFFFF_FFF0u32.to_le_bytes());
+        assert!(matches!(Image::from_file(&bad), Err(ImageError::Truncated)));
+        let mut bad = file.clone();
+        put(&mut bad, 0x98, &0x10Bu16.to_le_bytes());
+        assert!(matches!(Image::from_file(&bad), Err(ImageError::Unsupported(0x10B))));
+        //Section table cut in the middle of the second header
+        assert!(matches!(Image::from_file(&file[..SECTION_TABLE + 60]), Err(ImageError::Truncated)));
+    }
+
+    #[test]
+    fn bogus_sections() {
+        let mut sections = sections();
+        sections.push(RawSection { name: ".past", rva: 0x8000, virtual_size: 0x100, raw_offset: 0x540, raw_size: 0x40, characteristics: 0 });
+        sections.push(RawSection { name: ".far", rva: 0x2800, virtual_size: 0x100, raw_offset: 0x580, raw_size: 0x40, characteristics: 0 });
+        sections.push(RawSection { name: ".edge", rva: 0x2FF0, virtual_size: 0x100, raw_offset: 0x5C0, raw_size: 0x40, characteristics: 0 });
+        let mut file = pe(&sections, 0x3000);
+        //Raw data of `.far` past the end of the file
+        put(&mut file, SECTION_TABLE + 40 * 3 + 20, &0x10_0000u32.to_le_bytes());
+        let image = Image::from_file(&file).unwrap();
+        assert_eq!(image.sections.len(), 5);
+        let data = image.data();
+        assert_eq!(data.len(), 0x3000);
+        assert!(data[0x2800..0x2900].iter().all(|&b| b == 0));
+        //Cut at the end of the image
+        assert!(data[0x2FF0..].iter().all(|&b| b == b'e'));
+        //Truncated files still map what they have
+        let truncated = Image::from_file(&file[..0x460]).unwrap();
+        assert!(truncated.data()[0x1000..0x1060].iter().all(|&b| b == b't'));
+        assert_eq!(truncated.data()[0x1060], 0);
+        assert!(truncated.data()[0x2000..0x2040].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn check_bindings() {
+        let mut file = pe(&sections(), 0x3000);
+        file[0x400..0x500].fill(0xCC);
+        //mov dword [rip + 0], 100; mov ...
+        put(&mut file, 0x410, &[0xC7, 0x05, 0x00, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00, 0x48, 0x8B]);
+        //cmp dword [rip + 0x40], ?; jne, twice
+        for at in [0x440, 0x460] {
+            put(&mut file, at, &[0x83, 0x3D, 0x40, 0x00, 0x00, 0x00, 0x01, 0x75, 0x17, 0x8B, 0x43, 0x20, 0x25]);
+        }
+        let image = Image::from_file(&file).unwrap();
+        let reports = bindings::check(image.data());
+        assert_eq!(reports.len(), bindings::BINDINGS.len());
+        let report = |name| reports.iter().find(|r| r.binding.name == name).unwrap();
+        let device_limit = report("DEVICE_LIMIT");
+        assert!(device_limit.is_ok());
+        assert_eq!(device_limit.location, Some(Location::Rva(0x1016)));
+        let game_state = report("GAME_STATE");
+        assert!(game_state.resolution.is_ambiguous() && !game_state.is_ok());
+        assert_eq!(game_state.resolution.hits(), 2);
+        assert_eq!(report("MAIN_FRAME").resolution.hits(), 0);
+        assert_eq!(reports.iter().filter(|r| r.is_ok()).count(), 1);
+    }
+}
@@ -0,0 +1,445 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde_derive::{Deserialize, Serialize};
+
+pub use self::cache::{CACHE_FILE, CacheError, Fingerprint, PatternCache};
+pub use self::generate::{generate, GenerateError};
+pub use self::image::{Image, ImageError, Section};
+pub use self::report::{PatternReport, Resolution};
+pub use self::set::PatternSet;
+
+pub mod bindings;
+mod cache;
+mod generate;
+mod image;
+mod report;
+mod set;
+
+/// A single pattern byte, matched as `(b & mask) == value`.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub struct PatternByte {
+    pub value: u8,
+    pub mask: u8,
+}
+
+impl PatternByte {
+    pub const ANY: PatternByte = PatternByte { value: 0, mask: 0 };
+
+    pub const fn exact(value: u8) -> PatternByte {
+        PatternByte { value, mask: 0xFF }
+    }
+
+    pub fn fixed(&self) -> Option<u8> {
+        if self.mask == 0xFF { Some(self.value) } else { None }
+    }
+
+    pub fn matches(&self, b: u8) -> bool {
+        b & self.mask == self.value
+    }
+}
+
+impl Display for PatternByte {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.mask {
+            0xFF => f.write_fmt(format_args!("{:02X}", self.value)),
+            0xF0 => f.write_fmt(format_args!("{:X}?", self.value >> 4)),
+            0x0F => f.write_fmt(format_args!("?{:X}", self.value)),
+            _ => f.write_str("?")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub enum CaptureKind {
+    /// RIP-relative 32-bit offset, `trailing` is the count of instruction bytes after it.
+    Rel32 { trailing: u8 },
+    Disp32,
+    Imm8,
+    Imm32,
+}
+
+impl CaptureKind {
+    /// Bytes of the operand.
+    pub fn size(&self) -> usize {
+        match self {
+            CaptureKind::Imm8 => 1,
+            _ => 4
+        }
+    }
+
+    fn parse(s: &str) -> Result<CaptureKind, PatternError> {
+        let kind = match s {
+            "rel32" => CaptureKind::Rel32 { trailing: 0 },
+            "disp32" => CaptureKind::Disp32,
+            "imm8" => CaptureKind::Imm8,
+            "imm32" => CaptureKind::Imm32,
+            other => {
+                let trailing = other.strip_prefix("rel32+")
+                    .and_then(|t| t.parse::<u8>().ok())
+                    .ok_or_else(|| PatternError::UnknownCapture(String::from(other)))?;
+                CaptureKind::Rel32 { trailing }
+            }
+        };
+        Ok(kind)
+    }
+}
+
+impl Display for CaptureKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureKind::Rel32 { trailing: 0 } => f.write_str("[rel32]"),
+            CaptureKind::Rel32 { trailing } => f.write_fmt(format_args!("[rel32+{}]", trailing)),
+            CaptureKind::Disp32 => f.write_str("[disp32]"),
+            CaptureKind::Imm8 => f.write_str("[imm8]"),
+            CaptureKind::Imm32 => f.write_str("[imm32]")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub struct Capture {
+    pub kind: CaptureKind,
+    pub offset: usize,
+}
+
+/// A decoded capture. For `[rel32]` the value is the resolved target relative to the scanned buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Captured {
+    pub kind: CaptureKind,
+    pub offset: usize,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub offset: usize,
+    pub target: i64,
+    pub captures: Vec<Captured>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    Empty,
+    InvalidSymbol(String),
+    UnknownCapture(String),
+    UnterminatedCapture(String),
+    DuplicateCursor,
+    TrailingCursor,
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternError::Empty => f.write_str("pattern is empty"),
+            PatternError::InvalidSymbol(s) => f.write_fmt(format_args!("invalid pattern symbol `{}`", s)),
+            PatternError::UnknownCapture(s) => f.write_fmt(format_args!("unknown capture `[{}]`", s)),
+            PatternError::UnterminatedCapture(s) => f.write_fmt(format_args!("unterminated capture `{}`", s)),
+            PatternError::DuplicateCursor => f.write_str("pattern has more than one cursor"),
+            PatternError::TrailingCursor => f.write_str("cursor must be followed by a pattern byte")
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Byte signature with optional wildcards, result cursor and typed captures.
+///
+/// Syntax, separated by whitespace:
+/// * `48`, `4?`, `?8` - exact byte or half-byte wildcard
+/// * `?` or `??` - any byte
+/// * `|` - cursor, moves the result to the following byte
+/// * `[rel32]`, `[rel32+N]`, `[disp32]`, `[imm8]`, `[imm32]` - wildcarded operand decoded on match;
+///   a cursor placed right before `[rel32]` makes the pattern resolve to the referenced address
+#[derive(Debug, Clone, Serialize, Deserialize, Ord, PartialOrd, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    nibbles: Vec<PatternByte>,
+    cursor: Option<usize>,
+    captures: Vec<Capture>,
+}
+
+impl Pattern {
+    pub fn new(nibbles: Vec<PatternByte>) -> Pattern {
+        Pattern { nibbles, cursor: None, captures: Vec::new() }
+    }
+
+    pub fn parse(pattern: &str) -> Result<Pattern, PatternError> {
+        let mut nibbles = Vec::new();
+        let mut cursor = None;
+        let mut captures = Vec::new();
+        for token in pattern.replace('|', " | ").split_whitespace() {
+            if token == "|" {
+                if cursor.is_some() {
+                    return Err(PatternError::DuplicateCursor);
+                }
+                cursor = Some(nibbles.len());
+            } else if let Some(capture) = token.strip_prefix('[') {
+                let capture = capture.strip_suffix(']')
+                    .ok_or_else(|| PatternError::UnterminatedCapture(String::from(token)))?;
+                let kind = CaptureKind::parse(capture)?;
+                captures.push(Capture { kind, offset: nibbles.len() });
+                nibbles.extend(std::iter::repeat_n(PatternByte::ANY, kind.size()));
+            } else {
+                nibbles.push(parse_byte(token)?);
+            }
+        }
+        if nibbles.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if cursor == Some(nibbles.len()) {
+            return Err(PatternError::TrailingCursor);
+        }
+        Ok(Pattern { nibbles, cursor, captures })
+    }
+
+    pub fn compile(pattern: &str) -> Pattern {
+        Pattern::parse(pattern).unwrap_or_else(|e| panic!("Invalid pattern `{}`: {}", pattern, e))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nibbles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nibbles.is_empty()
+    }
+
+    pub fn bytes(&self) -> &[PatternByte] {
+        &self.nibbles
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor.unwrap_or(0)
+    }
+
+    pub fn captures(&self) -> &[Capture] {
+        &self.captures
+    }
+
+    pub fn matches(&self, buf: &[u8]) -> bool {
+        buf.len() >= self.nibbles.len() && self.nibbles.iter().zip(buf).all(|(m, b)| m.matches(*b))
+    }
+
+    pub fn scan(&self, buf: &[u8]) -> Option<usize> {
+        let pattern_len = self.nibbles.len();
+        if buf.len() < pattern_len {
+            return None;
+        }
+        (0..=(buf.len() - pattern_len)).find(|i| self.matches(&buf[*i..]))
+    }
+
+    pub fn scan_all(&self, buf: &[u8]) -> Vec<usize> {
+        let pattern_len = self.nibbles.len();
+        if buf.len() < pattern_len {
+            return Vec::new();
+        }
+        (0..=(buf.len() - pattern_len)).filter(|i| self.matches(&buf[*i..])).collect()
+    }
+
+    pub fn resolve(&self, buf: &[u8]) -> Resolution<usize> {
+        Resolution::from_hits(self.scan_all(buf))
+    }
+
+    /// Decodes captures of a match found at `offset` and computes where the pattern lands.
+    /// All returned positions are relative to the start of `buf`.
+    pub fn decode(&self, buf: &[u8], offset: usize) -> Option<Match> {
+        let bytes = buf.get(offset..offset + self.nibbles.len())?;
+        let mut captures = Vec::with_capacity(self.captures.len());
+        for capture in &self.captures {
+            let raw = &bytes[capture.offset..capture.offset + capture.kind.size()];
+            let position = (offset + capture.offset) as i64;
+            let value = match capture.kind {
+                CaptureKind::Rel32 { trailing } => {
+                    position + 4 + trailing as i64 + read_i32(raw) as i64
+                }
+                CaptureKind::Disp32 | CaptureKind::Imm32 => read_i32(raw) as i64,
+                CaptureKind::Imm8 => raw[0] as i64
+            };
+            captures.push(Captured { kind: capture.kind, offset: offset + capture.offset, value });
+        }
+        let cursor = self.cursor();
+        let target = self.cursor
+            .and_then(|cursor| captures.iter().find(|c| {
+                matches!(c.kind, CaptureKind::Rel32 { .. }) && c.offset == offset + cursor
+            }))
+            .map(|c| c.value)
+            .unwrap_or((offset + cursor) as i64);
+        Some(Match { offset, target, captures })
+    }
+}
+
+fn parse_byte(token: &str) -> Result<PatternByte, PatternError> {
+    let invalid = || PatternError::InvalidSymbol(String::from(token));
+    let digit = |c: char| c.to_digit(16).map(|d| d as u8).ok_or_else(invalid);
+    let mut chars = token.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('?'), None, None) | (Some('?'), Some('?'), None) => Ok(PatternByte::ANY),
+        (Some('?'), Some(lo), None) => Ok(PatternByte { value: digit(lo)?, mask: 0x0F }),
+        (Some(hi), Some('?'), None) => Ok(PatternByte { value: digit(hi)? << 4, mask: 0xF0 }),
+        (Some(hi), Some(lo), None) => Ok(PatternByte::exact(digit(hi)? << 4 | digit(lo)?)),
+        (Some(lo), None, None) => Ok(PatternByte::exact(digit(lo)?)),
+        _ => Err(invalid())
+    }
+}
+
+fn read_i32(raw: &[u8]) -> i32 {
+    i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]])
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let mut i = 0;
+        while i < self.nibbles.len() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            if self.cursor == Some(i) {
+                f.write_str("| ")?;
+            }
+            if let Some(capture) = self.captures.iter().find(|c| c.offset == i) {
+                capture.kind.fmt(f)?;
+                i += capture.kind.size();
+            } else {
+                self.nibbles[i].fmt(f)?;
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(s)
+    }
+}
+
+impl<S> From<S> for Pattern where S: AsRef<str> {
+    fn from(s: S) -> Self {
+        Pattern::compile(s.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_all_reports_every_hit() {
+        let pattern = Pattern::compile("AA ? CC");
+        let buf = [0xAA, 0x00, 0xCC, 0x11, 0xAA, 0xBB, 0xCC, 0xAA, 0x01];
+        assert_eq!(pattern.scan_all(&buf), vec![0, 4]);
+        assert_eq!(pattern.scan(&buf), Some(0));
+        assert_eq!(pattern.resolve(&buf), Resolution::Ambiguous(vec![0, 4]));
+        assert_eq!(pattern.resolve(&buf[1..]), Resolution::Unique(3));
+        assert_eq!(pattern.resolve(&buf[5..]), Resolution::NotFound);
+    }
+
+    #[test]
+    fn scan_all_handles_short_buffers() {
+        let pattern = Pattern::compile("AA BB");
+        assert!(pattern.scan_all(&[]).is_empty());
+        assert!(pattern.scan_all(&[0xAA]).is_empty());
+        assert_eq!(pattern.scan_all(&[0xAA, 0xBB]), vec![0]);
+        assert_eq!(pattern.scan(&[0xAA]), None);
+    }
+
+    #[test]
+    fn scan_all_overlapping() {
+        let pattern = Pattern::compile("AA AA");
+        assert_eq!(pattern.scan_all(&[0xAA; 4]), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parse_bytes_and_nibbles() {
+        let pattern = Pattern::parse("48 8B ? ?? 4? ?5 C").unwrap();
+        assert_eq!(pattern.bytes(), &[
+            PatternByte::exact(0x48),
+            PatternByte::exact(0x8B),
+            PatternByte::ANY,
+            PatternByte::ANY,
+            PatternByte { value: 0x40, mask: 0xF0 },
+            PatternByte { value: 0x05, mask: 0x0F },
+            PatternByte::exact(0x0C),
+        ]);
+        assert_eq!(pattern.cursor(), 0);
+        assert!(pattern.captures().is_empty());
+        assert!(pattern.matches(&[0x48, 0x8B, 1, 2, 0x4F, 0xA5, 0x0C]));
+        assert!(!pattern.matches(&[0x48, 0x8B, 1, 2, 0x5F, 0xA5, 0x0C]));
+        assert!(!pattern.matches(&[0x48, 0x8B, 1, 2, 0x4F, 0xA6, 0x0C]));
+    }
+
+    #[test]
+    fn parse_cursor_and_captures() {
+        let pattern = Pattern::parse("48 8B 05|[rel32] 84 C0 [imm8] [rel32+1] [disp32] [imm32]").unwrap();
+        assert_eq!(pattern.len(), 3 + 4 + 2 + 1 + 4 + 4 + 4);
+        assert_eq!(pattern.cursor(), 3);
+        assert_eq!(pattern.captures(), &[
+            Capture { kind: CaptureKind::Rel32 { trailing: 0 }, offset: 3 },
+            Capture { kind: CaptureKind::Imm8, offset: 9 },
+            Capture { kind: CaptureKind::Rel32 { trailing: 1 }, offset: 10 },
+            Capture { kind: CaptureKind::Disp32, offset: 14 },
+            Capture { kind: CaptureKind::Imm32, offset: 18 },
+        ]);
+        assert!(pattern.bytes()[3..7].iter().all(|b| *b == PatternByte::ANY));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for text in ["48 8B ? 4? ?5", "E8 | [rel32] 84 C0", "83 3D | [rel32+1] ? 75", "B9 [imm32] | 48 [imm8] [disp32]"] {
+            let pattern = Pattern::parse(text).unwrap();
+            assert_eq!(pattern.to_string(), text);
+            assert_eq!(Pattern::parse(&pattern.to_string()).unwrap(), pattern);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Pattern::parse(""), Err(PatternError::Empty));
+        assert_eq!(Pattern::parse(" | "), Err(PatternError::Empty));
+        assert_eq!(Pattern::parse("48 XY"), Err(PatternError::InvalidSymbol(String::from("XY"))));
+        assert_eq!(Pattern::parse("48 123"), Err(PatternError::InvalidSymbol(String::from("123"))));
+        assert_eq!(Pattern::parse("48 | 8B | C0"), Err(PatternError::DuplicateCursor));
+        assert_eq!(Pattern::parse("48 8B |"), Err(PatternError::TrailingCursor));
+        assert_eq!(Pattern::parse("E8 [rel16]"), Err(PatternError::UnknownCapture(String::from("rel16"))));
+        assert_eq!(Pattern::parse("E8 [rel32+x]"), Err(PatternError::UnknownCapture(String::from("rel32+x"))));
+        assert_eq!(Pattern::parse("E8 [rel32"), Err(PatternError::UnterminatedCapture(String::from("[rel32"))));
+        assert!(Pattern::parse("48 XY").unwrap_err().to_string().contains("XY"));
+    }
+
+    #[test]
+    fn decode_follows_rel32_at_cursor() {
+        //lea rcx, [rip + 0x10] at offset 4, landing 0x10 bytes past the instruction
+        let buf = [0x90, 0x90, 0x90, 0x90, 0x48, 0x8D, 0x0D, 0x10, 0x00, 0x00, 0x00, 0xC3];
+        let pattern = Pattern::compile("48 8D 0D | [rel32] C3");
+        let hit = pattern.scan(&buf).unwrap();
+        let m = pattern.decode(&buf, hit).unwrap();
+        assert_eq!(m.offset, 4);
+        assert_eq!(m.target, 11 + 0x10);
+        assert_eq!(m.captures, vec![Captured { kind: CaptureKind::Rel32 { trailing: 0 }, offset: 7, value: 11 + 0x10 }]);
+    }
+
+    #[test]
+    fn decode_trailing_and_negative_rel32() {
+        //cmp dword [rip - 8], 1: the displacement is relative to the end of the imm8
+        let buf = [0x83, 0x3D, 0xF8, 0xFF, 0xFF, 0xFF, 0x01];
+        let m = Pattern::compile("83 3D | [rel32+1] 01").decode(&buf, 0).unwrap();
+        assert_eq!(m.target, 7 - 8);
+    }
+
+    #[test]
+    fn decode_plain_cursor_and_values() {
+        let buf = [0xB9, 0x78, 0x56, 0x34, 0x12, 0x6A, 0xFE, 0x8B, 0x8F, 0x10, 0x01, 0x00, 0x00];
+        let pattern = Pattern::compile("B9 [imm32] 6A [imm8] 8B 8F | [disp32]");
+        let m = pattern.decode(&buf, 0).unwrap();
+        assert_eq!(m.target, 9);
+        let values = m.captures.iter().map(|c| (c.kind, c.value)).collect::<Vec<_>>();
+        assert_eq!(values, vec![(CaptureKind::Imm32, 0x12345678), (CaptureKind::Imm8, 0xFE), (CaptureKind::Disp32, 0x110)]);
+        //Without a cursor the pattern lands on its match
+        assert_eq!(Pattern::compile("6A ?").decode(&buf, 5).unwrap().target, 5);
+        //A match running past the buffer can't be decoded
+        assert_eq!(pattern.decode(&buf, 1), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::{Display, Formatter, UpperHex};
+
+use serde_derive::{Deserialize, Serialize};
+
+//Ambiguous hits past this count are omitted from the printed report
+const MAX_PRINTED_HITS: usize = 8;
+
+/// Outcome of scanning for a pattern, distinguishing a unique match from an ambiguous one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution<T> {
+    NotFound,
+    Unique(T),
+    Ambiguous(Vec<T>),
+}
+
+impl<T> Resolution<T> {
+    pub fn from_hits(mut hits: Vec<T>) -> Resolution<T> {
+        match hits.len() {
+            0 => Resolution::NotFound,
+            1 => Resolution::Unique(hits.remove(0)),
+            _ => Resolution::Ambiguous(hits)
+        }
+    }
+
+    pub fn hits(&self) -> usize {
+        match self {
+            Resolution::NotFound => 0,
+            Resolution::Unique(_) => 1,
+            Resolution::Ambiguous(hits) => hits.len()
+        }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        matches!(self, Resolution::Unique(_))
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(self, Resolution::Ambiguous(_))
+    }
+
+    /// Returns the match only if it is unique.
+    pub fn unique(self) -> Option<T> {
+        match self {
+            Resolution::Unique(hit) => Some(hit),
+            _ => None
+        }
+    }
+
+    /// Returns the lowest match, ignoring ambiguity.
+    pub fn first(self) -> Option<T> {
+        match self {
+            Resolution::NotFound => None,
+            Resolution::Unique(hit) => Some(hit),
+            Resolution::Ambiguous(hits) => hits.into_iter().next()
+        }
+    }
+
+    pub fn into_hits(self) -> Vec<T> {
+        match self {
+            Resolution::NotFound => Vec::new(),
+            Resolution::Unique(hit) => vec![hit],
+            Resolution::Ambiguous(hits) => hits
+        }
+    }
+
+    pub fn as_ref(&self) -> Resolution<&T> {
+        match self {
+            Resolution::NotFound => Resolution::NotFound,
+            Resolution::Unique(hit) => Resolution::Unique(hit),
+            Resolution::Ambiguous(hits) => Resolution::Ambiguous(hits.iter().collect())
+        }
+    }
+
+    pub fn map<U, F>(self, mut f: F) -> Resolution<U> where F: FnMut(T) -> U {
+        match self {
+            Resolution::NotFound => Resolution::NotFound,
+            Resolution::Unique(hit) => Resolution::Unique(f(hit)),
+            Resolution::Ambiguous(hits) => Resolution::Ambiguous(hits.into_iter().map(f).collect())
+        }
+    }
+}
+
+impl<T> Display for Resolution<T> where T: UpperHex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resolution::NotFound => f.write_str("not found"),
+            Resolution::Unique(hit) => f.write_fmt(format_args!("unique at 0x{:X}", hit)),
+            Resolution::Ambiguous(hits) => {
+                f.write_fmt(format_args!("ambiguous ({} hits at ", hits.len()))?;
+                for (i, hit) in hits.iter().take(MAX_PRINTED_HITS).enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_fmt(format_args!("0x{:X}", hit))?;
+                }
+                if hits.len() > MAX_PRINTED_HITS {
+                    f.write_str(", ...")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// Resolution of a single pattern, as logged at startup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternReport {
+    pub pattern: String,
+    pub resolution: Resolution<u64>,
+}
+
+impl PatternReport {
+    pub fn new<P>(pattern: P, resolution: Resolution<u64>) -> PatternReport where P: Into<String> {
+        PatternReport {
+            pattern: pattern.into(),
+            resolution,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.resolution.is_unique()
+    }
+}
+
+impl Display for PatternReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("`{}`: {}", self.pattern, self.resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hits() {
+        assert_eq!(Resolution::<u64>::from_hits(vec![]), Resolution::NotFound);
+        assert_eq!(Resolution::from_hits(vec![5u64]), Resolution::Unique(5));
+        assert_eq!(Resolution::from_hits(vec![5u64, 9]), Resolution::Ambiguous(vec![5, 9]));
+    }
+
+    #[test]
+    fn accessors() {
+        let ambiguous = Resolution::Ambiguous(vec![3u64, 7, 11]);
+        assert_eq!(ambiguous.hits(), 3);
+        assert!(ambiguous.is_ambiguous() && !ambiguous.is_unique());
+        assert_eq!(ambiguous.clone().unique(), None);
+        assert_eq!(ambiguous.clone().first(), Some(3));
+        assert_eq!(ambiguous.as_ref().map(|h| h * 2).into_hits(), vec![6, 14, 22]);
+
+        let unique = Resolution::Unique(4u64);
+        assert!(unique.is_unique());
+        assert_eq!(unique.clone().unique(), Some(4));
+        assert_eq!(unique.into_hits(), vec![4]);
+
+        let missing = Resolution::<u64>::NotFound;
+        assert_eq!(missing.hits(), 0);
+        assert_eq!(missing.clone().first(), None);
+        assert!(missing.into_hits().is_empty());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Resolution::<u64>::NotFound.to_string(), "not found");
+        assert_eq!(Resolution::Unique(0x1A2Bu64).to_string(), "unique at 0x1A2B");
+        assert_eq!(Resolution::Ambiguous(vec![0x10u64, 0x20]).to_string(), "ambiguous (2 hits at 0x10, 0x20)");
+        let many = Resolution::from_hits((0..10u64).collect()).to_string();
+        assert_eq!(many, "ambiguous (10 hits at 0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, ...)");
+        let report = PatternReport::new("48 8B", Resolution::Unique(0x40));
+        assert!(report.is_ok());
+        assert_eq!(report.to_string(), "`48 8B`: unique at 0x40");
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let report = PatternReport::new("E8 ? ? ? ?", Resolution::Ambiguous(vec![1, 2]));
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<PatternReport>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::signature::{Pattern, Resolution};
+
+/// A group of patterns resolved together in a single pass over a buffer.
+///
+/// Every pattern is keyed by its least common fixed byte (the anchor), so the scanner only
+/// verifies patterns at positions where one of the anchor bytes occurs. On x86_64 the anchor
+/// bytes are located 16 at a time with SSSE3 nibble lookups.
+pub struct PatternSet {
+    patterns: Vec<Pattern>,
+    anchors: Vec<usize>,
+    buckets: Vec<Vec<usize>>,
+    unanchored: Vec<usize>,
+    prefilter: Prefilter,
+}
+
+impl PatternSet {
+    pub fn new() -> PatternSet {
+        PatternSet {
+            patterns: Vec::new(),
+            anchors: Vec::new(),
+            buckets: vec![Vec::new(); 256],
+            unanchored: Vec::new(),
+            prefilter: Prefilter::default(),
+        }
+    }
+
+    pub fn insert(&mut self, pattern: Pattern) -> usize {
+        let index = self.patterns.len();
+        let anchor = pattern.bytes().iter().enumerate()
+            .filter_map(|(i, b)| b.fixed().map(|b| (i, b)))
+            .min_by_key(|(_, b)| frequency(*b));
+        if let Some((offset, byte)) = anchor {
+            self.anchors.push(offset);
+            self.buckets[byte as usize].push(index);
+            self.prefilter.add(byte);
+        } else {
+            self.anchors.push(0);
+            self.unanchored.push(index);
+        }
+        self.patterns.push(pattern);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Pattern> {
+        self.patterns.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&Pattern> {
+        self.patterns.iter()
+    }
+
+    /// Returns the offset of the first match for every pattern, in insertion order.
+    pub fn scan(&self, buf: &[u8]) -> Vec<Option<usize>> {
+        let mut found = vec![None; self.patterns.len()];
+        let mut remaining = found.len();
+        if remaining == 0 {
+            return found;
+        }
+        self.visit(buf, |index, offset| {
+            if found[index].is_none() {
+                found[index] = Some(offset);
+                remaining -= 1;
+            }
+            remaining != 0
+        });
+        found
+    }
+
+    /// Returns the offsets of all matches for every pattern, in insertion order.
+    pub fn scan_all(&self, buf: &[u8]) -> Vec<Vec<usize>> {
+        let mut found = vec![Vec::new(); self.patterns.len()];
+        self.visit(buf, |index, offset| {
+            found[index].push(offset);
+            true
+        });
+        found
+    }
+
+    pub fn resolve(&self, buf: &[u8]) -> Vec<Resolution<usize>> {
+        self.scan_all(buf).into_iter().map(Resolution::from_hits).collect()
+    }
+
+    /// Calls `visitor` with `(pattern index, offset)` for every match in `buf`.
+    /// Matches of a single pattern are reported in ascending order.
+    /// Scanning stops as soon as the visitor returns `false`.
+    pub fn visit<F>(&self, buf: &[u8], mut visitor: F) where F: FnMut(usize, usize) -> bool {
+        for &index in &self.unanchored {
+            let len = self.patterns[index].len();
+            if buf.len() >= len {
+                for offset in 0..=(buf.len() - len) {
+                    if !visitor(index, offset) {
+                        return;
+                    }
+                }
+            }
+        }
+
+        let mut check = |position: usize| -> bool {
+            for &index in &self.buckets[buf[position] as usize] {
+                let anchor = self.anchors[index];
+                if position >= anchor {
+                    let offset = position - anchor;
+                    if self.patterns[index].matches(&buf[offset..]) && !visitor(index, offset) {
+                        return false;
+                    }
+                }
+            }
+            true
+        };
+
+        self.prefilter.for_each_candidate(buf, &mut check);
+    }
+}
+
+impl Default for PatternSet {
+    fn default() -> Self {
+        PatternSet::new()
+    }
+}
+
+impl<P> std::iter::FromIterator<P> for PatternSet where P: Into<Pattern> {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item=P> {
+        let mut set = PatternSet::new();
+        for pattern in iter {
+            set.insert(pattern.into());
+        }
+        set
+    }
+}
+
+//Rough ranking of how common a byte is in x86-64 code, lower is rarer
+fn frequency(b: u8) -> u8 {
+    match b {
+        0x00 | 0xFF | 0xCC | 0x90 => 8,
+        0x48 | 0x8B | 0x89 => 7,
+        0x0F | 0x8D | 0x4C | 0x24 | 0xE8 => 6,
+        0x44 | 0x41 | 0x85 | 0xC0 | 0x83 | 0x33 | 0x74 | 0x01 | 0xC3 => 5,
+        0x08 | 0x10 | 0x20 | 0x28 | 0x30 | 0x38 | 0x40 | 0x45 | 0x49 | 0x4D | 0x75 | 0xEB => 4,
+        _ => 0
+    }
+}
+
+#[derive(Default)]
+struct Prefilter {
+    bytes: Vec<u8>,
+    table: Vec<bool>,
+    lo: [u8; 16],
+    hi: [u8; 16],
+    //Forces the scalar path, for comparing both
+    scalar: bool,
+}
+
+impl Prefilter {
+    fn add(&mut self, b: u8) {
+        if self.table.is_empty() {
+            self.table = vec![false; 256];
+        }
+        if !self.table[b as usize] {
+            self.table[b as usize] = true;
+            let bucket = 1 << (self.bytes.len() % 8);
+            self.lo[(b & 0x0F) as usize] |= bucket;
+            self.hi[(b >> 4) as usize] |= bucket;
+            self.bytes.push(b);
+        }
+    }
+
+    fn for_each_candidate<F>(&self, buf: &[u8], check: &mut F) where F: FnMut(usize) -> bool {
+        if self.bytes.is_empty() {
+            return;
+        }
+        let mut position = 0;
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if !self.scalar && is_x86_feature_detected!("ssse3") {
+                match unsafe { self.for_each_candidate_ssse3(buf, check) } {
+                    Some(p) => position = p,
+                    None => return
+                }
+            }
+        }
+
+        while position < buf.len() {
+            if self.table[buf[position] as usize] && !check(position) {
+                return;
+            }
+            position += 1;
+        }
+    }
+
+    //Returns the position scalar scanning has to continue from, or `None` if the check asked to stop
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn for_each_candidate_ssse3<F>(&self, buf: &[u8], check: &mut F) -> Option<usize> where F: FnMut(usize) -> bool {
+        use std::arch::x86_64::*;
+
+        let lo = _mm_loadu_si128(self.lo.as_ptr().cast());
+        let hi = _mm_loadu_si128(self.hi.as_ptr().cast());
+        let mask = _mm_set1_epi8(0x0F);
+        let zero = _mm_setzero_si128();
+
+        let mut position = 0;
+        while position + 16 <= buf.len() {
+            let chunk = _mm_loadu_si128(buf.as_ptr().add(position).cast());
+            let lo_nibbles = _mm_and_si128(chunk, mask);
+            let hi_nibbles = _mm_and_si128(_mm_srli_epi16(chunk, 4), mask);
+            let buckets = _mm_and_si128(_mm_shuffle_epi8(lo, lo_nibbles), _mm_shuffle_epi8(hi, hi_nibbles));
+            let mut candidates = !(_mm_movemask_epi8(_mm_cmpeq_epi8(buckets, zero)) as u32) & 0xFFFF;
+            while candidates != 0 {
+                let i = position + candidates.trailing_zeros() as usize;
+                candidates &= candidates - 1;
+                if self.table[buf[i] as usize] && !check(i) {
+                    return None;
+                }
+            }
+            position += 16;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Deterministic noise biased towards common opcode bytes, so anchors occur often
+    fn noise(len: usize, mut seed: u64) -> Vec<u8> {
+        const COMMON: [u8; 8] = [0x48, 0x8B, 0x89, 0x0F, 0xE8, 0x00, 0xFF, 0xCC];
+        (0..len).map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            if seed % 3 == 0 { COMMON[(seed >> 8) as usize % COMMON.len()] } else { (seed >> 24) as u8 }
+        }).collect()
+    }
+
+    fn patterns() -> Vec<Pattern> {
+        [
+            "48 8B 05 ? ? ? ? 48 85 C0",
+            "E8 [rel32] 84 C0",
+            "4? 89 5C 24 ?",
+            "?8 8B",
+            "CC",
+            "? ?",
+            "0F B6 ?? ?? 3C 01 75",
+            "DE AD BE EF",
+        ].iter().map(|p| Pattern::compile(p)).collect()
+    }
+
+    fn planted(len: usize, seed: u64) -> Vec<u8> {
+        let mut buf = noise(len, seed);
+        let plants: [&[u8]; 4] = [
+            &[0x48, 0x8B, 0x05, 1, 2, 3, 4, 0x48, 0x85, 0xC0],
+            &[0xE8, 0x10, 0, 0, 0, 0x84, 0xC0],
+            &[0x0F, 0xB6, 0x41, 0x10, 0x3C, 0x01, 0x75],
+            &[0xDE, 0xAD, 0xBE, 0xEF],
+        ];
+        for (i, plant) in plants.iter().enumerate() {
+            //Near both ends as well, where the SIMD loop hands over to the scalar one
+            for offset in [0, len / 3 + i, len - plant.len(), len - plant.len() - 17] {
+                buf[offset..offset + plant.len()].copy_from_slice(plant);
+            }
+        }
+        buf
+    }
+
+    fn naive(patterns: &[Pattern], buf: &[u8]) -> Vec<Vec<usize>> {
+        patterns.iter().map(|p| p.scan_all(buf)).collect()
+    }
+
+    #[test]
+    fn agrees_with_scan_all() {
+        let patterns = patterns();
+        for (len, seed) in [(64, 1), (1000, 2), (4099, 3), (65536, 4)] {
+            let buf = planted(len, seed);
+            let expected = naive(&patterns, &buf);
+            for scalar in [false, true] {
+                let mut set = patterns.iter().cloned().collect::<PatternSet>();
+                set.prefilter.scalar = scalar;
+                assert_eq!(set.scan_all(&buf), expected, "len {} scalar {}", len, scalar);
+                let first = expected.iter().map(|hits| hits.first().cloned()).collect::<Vec<_>>();
+                assert_eq!(set.scan(&buf), first, "len {} scalar {}", len, scalar);
+            }
+        }
+    }
+
+    #[test]
+    fn short_and_empty_buffers() {
+        let set = patterns().into_iter().collect::<PatternSet>();
+        assert!(set.scan_all(&[]).iter().all(|hits| hits.is_empty()));
+        assert_eq!(set.scan(&[0xCC]), vec![None, None, None, None, Some(0), None, None, None]);
+        assert!(PatternSet::new().scan(&[1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn visit_stops_early() {
+        let set = ["CC"].iter().collect::<PatternSet>();
+        let buf = vec![0xCC; 100];
+        let mut seen = 0;
+        set.visit(&buf, |_, _| {
+            seen += 1;
+            seen < 5
+        });
+        assert_eq!(seen, 5);
+    }
+
+    #[test]
+    fn resolve_reports_ambiguity() {
+        let set = ["DE AD BE EF", "01 02 03 04 05 06"].iter().collect::<PatternSet>();
+        let resolutions = set.resolve(&planted(1000, 5));
+        assert_eq!(resolutions[0].hits(), 4);
+        assert!(resolutions[0].is_ambiguous());
+        assert_eq!(resolutions[1].hits(), 0);
+    }
+
+    //cargo test --release -- --ignored --nocapture bench
+    #[test]
+    #[ignore]
+    fn bench_against_naive() {
+        let patterns = patterns();
+        let buf = planted(32 << 20, 6);
+        let start = std::time::Instant::now();
+        let expected = naive(&patterns, &buf);
+        let naive_time = start.elapsed();
+        for scalar in [true, false] {
+            let mut set = patterns.iter().cloned().collect::<PatternSet>();
+            set.prefilter.scalar = scalar;
+            let start = std::time::Instant::now();
+            assert_eq!(set.scan_all(&buf), expected);
+            println!("{} patterns over {} MiB: naive {:?}, set{} {:?}", patterns.len(), buf.len() >> 20,
+                     naive_time, if scalar { " (scalar)" } else { "" }, start.elapsed());
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::ffi::CStr;
+
+use cgmath::{Deg, Euler, Quaternion, Vector2, Vector3};
+
+use crate::hash::Hash;
+
+pub struct NativeStackReader<'a> {
+    stack: &'a [u64],
+    pos: usize,
+}
+
+impl<'a> NativeStackReader<'a> {
+    pub fn new(stack: &'a [u64]) -> NativeStackReader<'a> {
+        NativeStackReader {
+            stack,
+            pos: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.stack[self.pos] == 0
+    }
+
+    pub fn read_u64(&mut self) -> u64 {
+        let pos = self.pos;
+        self.pos += 1;
+        self.stack[pos]
+    }
+
+    pub unsafe fn read_ptr<T>(&mut self) -> T where T: Sized {
+        let pos = self.pos;
+        self.pos += 1;
+        self.stack[pos..].as_ptr().cast::<T>().read()
+    }
+
+    pub fn read<T>(&mut self) -> T where T: NativeStackValue {
+        T::read_from_stack(self)
+    }
+
+    pub fn read_option<T>(&mut self) -> Option<T> where T: NativeStackValue {
+        if self.is_null() {
+            self.pos += 1;
+            None
+        } else {
+            Some(self.read())
+        }
+    }
+}
+
+pub struct NativeStackWriter<'a> {
+    stack: &'a mut [u64],
+    pos: usize,
+}
+
+impl<'a> NativeStackWriter<'a> {
+    pub fn new(stack: &'a mut [u64]) -> NativeStackWriter<'a> {
+        NativeStackWriter {
+            stack,
+            pos: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn write_u64(&mut self, raw: u64) -> usize {
+        self.stack[self.pos] = raw;
+        self.pos += 1;
+        1
+    }
+
+    pub unsafe fn write_ptr<T>(&mut self, value: T) -> usize where T: Sized {
+        let pos = self.pos;
+        self.pos += 1;
+        self.stack[pos..].as_mut_ptr().cast::<T>().write(value);
+        1
+    }
+
+    pub fn write<T>(&mut self, value: T) -> usize where T: NativeStackValue {
+        let pos = self.pos;
+        value.write_to_stack(self);
+        self.pos - pos
+    }
+
+    pub fn write_option<T>(&mut self, value: Option<T>) -> usize where T: NativeStackValue {
+        if let Some(value) = value {
+            self.write(value)
+        } else {
+            self.write_u64(0)
+        }
+    }
+}
+
+pub trait NativeStackValue {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self where Self: Sized {
+        let size = std::mem::size_of::<Self>();
+        if size <= 8 {
+            unsafe {
+                stack.read_ptr::<Self>()
+            }
+        } else {
+            panic!(
+                "Cannot read value of type `{}` from stack as it exceeds default reader's size limits ({} bytes)",
+                std::any::type_name::<Self>(),
+                size
+            )
+        }
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) where Self: Sized {
+        let size = std::mem::size_of::<Self>();
+        if size <= 8 {
+            unsafe {
+                stack.write_ptr(self);
+            }
+        } else {
+            panic!(
+                "Cannot write value of type `{}` to stack as it exceeds default writer's size limits ({} bytes)",
+                std::any::type_name::<Self>(),
+                size
+            )
+        }
+    }
+}
+
+#[repr(C)]
+pub struct NativeCallContext<'arg, 'ret> {
+    returns: &'ret mut [u64; 3],
+    arg_count: u32,
+    args: &'arg mut [u64; 32],
+    data_count: u32,
+    data: [u32; 48],
+}
+
+impl<'arg, 'ret> NativeCallContext<'arg, 'ret> {
+    pub fn new(args: &'arg mut [u64; 32], returns: &'ret mut [u64; 3], arg_count: u32) -> NativeCallContext<'arg, 'ret> {
+        NativeCallContext {
+            returns,
+            arg_count,
+            args,
+            data_count: 0,
+            data: [0; 48],
+        }
+    }
+
+    pub fn get_args(&self) -> NativeStackReader {
+        NativeStackReader::new(&*self.args)
+    }
+
+    pub fn get_args_mut(&mut self) -> NativeStackWriter {
+        NativeStackWriter::new(&mut *self.args)
+    }
+
+    pub fn get_results(&self) -> NativeStackReader {
+        NativeStackReader::new(&*self.returns)
+    }
+
+    pub fn arg_count(&self) -> u32 {
+        self.arg_count
+    }
+
+    /// Argument words pushed so far.
+    pub fn args(&self) -> &[u64] {
+        &self.args[..(self.arg_count as usize).min(self.args.len())]
+    }
+
+    pub fn returns(&self) -> &[u64; 3] {
+        self.returns
+    }
+
+    pub fn raw_args(&mut self) -> &mut [u64; 32] {
+        &mut *self.args
+    }
+
+    pub fn raw_returns(&mut self) -> &mut [u64; 3] {
+        &mut *self.returns
+    }
+
+    pub fn push_arg<A>(&mut self, arg: A) -> usize where A: NativeStackValue {
+        let i = self.arg_count as usize;
+        let mut writer = NativeStackWriter::new(&mut self.args[i..]);
+        let len = writer.write(arg);
+        self.arg_count += len as u32;
+        len
+    }
+
+    pub fn get_result<R>(&mut self) -> R where R: NativeStackValue {
+        let mut reader = NativeStackReader::new(&*self.returns);
+        reader.read()
+    }
+
+    pub fn set_result<R>(&mut self, result: R) -> usize where R: NativeStackValue {
+        let mut writer = NativeStackWriter::new(&mut *self.returns);
+        writer.write(result)
+    }
+}
+
+pub type NativeFunction = extern fn(*mut NativeCallContext);
+
+impl NativeStackValue for &str {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        let c_str = unsafe { CStr::from_ptr(stack.read_u64() as *mut _) };
+        c_str.to_str().expect(&format!("Failed to read C string: {:?}", c_str))
+    }
+
+    //The copy lives in the frame arena until the native returns, pass a `&CStr` to natives keeping the pointer
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write_u64(crate::arena::alloc_c_str(self) as u64);
+    }
+}
+
+impl NativeStackValue for &CStr {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        unsafe { CStr::from_ptr(stack.read_u64() as *mut _) }
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write_u64(self.as_ptr() as u64);
+    }
+}
+
+impl NativeStackValue for String {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        <&str as NativeStackValue>::read_from_stack(stack).to_owned()
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        self.as_str().write_to_stack(stack);
+    }
+}
+
+impl NativeStackValue for u8 {}
+
+impl NativeStackValue for &mut u8 {}
+
+impl NativeStackValue for i32 {}
+
+impl NativeStackValue for &mut i32 {}
+
+impl NativeStackValue for u32 {}
+
+impl NativeStackValue for &mut u32 {}
+
+impl NativeStackValue for f32 {}
+
+impl NativeStackValue for &mut f32 {}
+
+impl NativeStackValue for Deg<f32> {}
+
+impl NativeStackValue for &mut Deg<f32> {}
+
+impl NativeStackValue for bool {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        stack.read::<u32>() as u8 == 1
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self as u32);
+    }
+}
+
+impl NativeStackValue for u64 {}
+
+impl NativeStackValue for &mut u64 {}
+
+impl NativeStackValue for () {}
+
+impl NativeStackValue for Hash {}
+
+impl NativeStackValue for &mut Hash {}
+
+impl NativeStackValue for Quaternion<f32> {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        let x = stack.read();
+        let y = stack.read();
+        let z = stack.read();
+        Euler::<Deg<f32>>::new(x, y, z).into()
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        let euler = Euler::from(self);
+        stack.write(Deg::from(euler.x));
+        stack.write(Deg::from(euler.y));
+        stack.write(Deg::from(euler.z));
+    }
+}
+
+impl<T> NativeStackValue for Vector3<T> where T: NativeStackValue + Copy + Clone {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        let x = stack.read();
+        let y = stack.read();
+        let z = stack.read();
+        Vector3::new(x, y, z)
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self.x);
+        stack.write(self.y);
+        stack.write(self.z);
+    }
+}
+
+impl<T> NativeStackValue for Vector2<T> where T: NativeStackValue + Copy + Clone {
+    fn read_from_stack(stack: &mut NativeStackReader) -> Self {
+        let x = stack.read();
+        let y = stack.read();
+        Vector2::new(x, y)
+    }
+
+    fn write_to_stack(self, stack: &mut NativeStackWriter) {
+        stack.write(self.x);
+        stack.write(self.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written<T>(value: T) -> Vec<u64> where T: NativeStackValue {
+        let mut stack = [0; 8];
+        let mut writer = NativeStackWriter::new(&mut stack);
+        let len = writer.write(value);
+        assert_eq!(len, writer.pos());
+        stack[..len].to_vec()
+    }
+
+    fn read<T>(stack: &[u64]) -> T where T: NativeStackValue {
+        let mut reader = NativeStackReader::new(stack);
+        let value = reader.read();
+        assert_eq!(reader.pos(), stack.len());
+        value
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(written(7u32), vec![7]);
+        assert_eq!(written(-1i32), vec![0xFFFF_FFFF]);
+        assert_eq!(written(1.5f32), vec![1.5f32.to_bits() as u64]);
+        assert_eq!(written(u64::MAX), vec![u64::MAX]);
+        assert_eq!(written(Hash(0xDEAD)), vec![0xDEAD]);
+        assert_eq!(written(true), vec![1]);
+        assert_eq!(written(false), vec![0]);
+        //Natives only set the low byte of booleans
+        assert!(read::<bool>(&[0xFFFF_FF01]));
+        assert!(!read::<bool>(&[0x100]));
+        assert_eq!(read::<f32>(&[(-0.5f32).to_bits() as u64 | 0xABCD_0000_0000]), -0.5);
+    }
+
+    #[test]
+    fn vectors() {
+        let vector = Vector3::new(1.0f32, -2.5, 300.0);
+        let stack = written(vector);
+        assert_eq!(stack, vec![1.0f32.to_bits() as u64, (-2.5f32).to_bits() as u64, 300.0f32.to_bits() as u64]);
+        assert_eq!(read::<Vector3<f32>>(&stack), vector);
+        assert_eq!(written(Vector3::new(true, false, true)), vec![1, 0, 1]);
+        let stack = written(Vector2::new(4u32, 5));
+        assert_eq!(stack, vec![4, 5]);
+        assert_eq!(read::<Vector2<u32>>(&stack), Vector2::new(4, 5));
+    }
+
+    #[test]
+    fn quaternion_as_euler_degrees() {
+        let rotation: Quaternion<f32> = Euler::new(Deg(10.0), Deg(-20.0), Deg(30.0)).into();
+        let stack = written(rotation);
+        assert_eq!(stack.len(), 3);
+        let degrees: Vec<f32> = stack.iter().map(|&word| f32::from_bits(word as u32)).collect();
+        for (degrees, expected) in degrees.iter().zip([10.0, -20.0, 30.0]) {
+            assert!((degrees - expected).abs() < 1e-3, "{:?}", degrees);
+        }
+        let back = read::<Quaternion<f32>>(&stack);
+        assert!((back.s - rotation.s).abs() < 1e-5 && (back.v.z - rotation.v.z).abs() < 1e-5);
+    }
+
+    #[test]
+    fn strings() {
+        let name = CStr::from_bytes_with_nul(b"WORLD_HUMAN_SMOKING\0").unwrap();
+        let stack = written(name);
+        assert_eq!(stack, vec![name.as_ptr() as u64]);
+        assert_eq!(read::<&CStr>(&stack), name);
+        assert_eq!(read::<&str>(&stack), "WORLD_HUMAN_SMOKING");
+        assert_eq!(read::<String>(&stack), "WORLD_HUMAN_SMOKING");
+        let _frame = crate::arena::frame();
+        let stack = written("CELL_PHONE");
+        assert_ne!(stack[0], 0);
+        assert_eq!(read::<&str>(&stack), "CELL_PHONE");
+        assert_eq!(read::<String>(&written(String::from("owned"))), "owned");
+    }
+
+    #[test]
+    fn options() {
+        let mut stack = [0; 2];
+        let mut writer = NativeStackWriter::new(&mut stack);
+        assert_eq!(writer.write_option::<u32>(None), 1);
+        assert_eq!(writer.write_option(Some(9u32)), 1);
+        assert_eq!(stack, [0, 9]);
+        let mut reader = NativeStackReader::new(&stack);
+        assert_eq!(reader.read_option::<u32>(), None);
+        assert_eq!(reader.read_option::<u32>(), Some(9));
+    }
+
+    #[test]
+    fn call_context() {
+        let mut args = [0; 32];
+        let mut returns = [0; 3];
+        let mut context = NativeCallContext::new(&mut args, &mut returns, 0);
+        assert_eq!(context.push_arg(3u32), 1);
+        assert_eq!(context.push_arg(Vector3::new(1.0f32, 2.0, 3.0)), 3);
+        assert_eq!(context.push_arg(true), 1);
+        assert_eq!(context.arg_count(), 5);
+        assert_eq!(context.args()[0], 3);
+        assert_eq!(context.args()[4], 1);
+        let mut reader = context.get_args();
+        assert_eq!(reader.read::<u32>(), 3);
+        assert_eq!(reader.read::<Vector3<f32>>(), Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(context.set_result(Vector3::new(4.0f32, 5.0, 6.0)), 3);
+        assert_eq!(context.get_result::<Vector3<f32>>(), Vector3::new(4.0, 5.0, 6.0));
+        assert_eq!(context.returns()[1], 5.0f32.to_bits() as u64);
+    }
+}
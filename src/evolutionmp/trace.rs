@@ -0,0 +1,418 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicBool, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde_derive::Serialize;
+
+use crate::hash::Hash;
+use crate::nativedb;
+
+/// Trace dump, relative to the launcher directory.
+pub const TRACE_FILE: &str = "natives.jsonl";
+/// Argument words kept per call, the rest being dropped.
+pub const MAX_ARGS: usize = 16;
+pub const DEFAULT_CAPACITY: usize = 4096;
+
+const RECORD_WORDS: usize = 3 + MAX_ARGS + 3;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref TRACE: TraceBuffer = TraceBuffer::new(DEFAULT_CAPACITY);
+    static ref FILTER: RwLock<TraceFilter> = RwLock::new(TraceFilter::default());
+}
+
+/// A native call, as seen by `invoke!` or the script runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NativeRecord {
+    pub hash: u64,
+    /// Hash of the calling script, 0 outside of scripts
+    pub script: u32,
+    pub duration: Duration,
+    /// Number of argument words passed, `args` keeping at most `MAX_ARGS` of them
+    pub arg_count: usize,
+    pub args: [u64; MAX_ARGS],
+    pub returns: [u64; 3],
+}
+
+impl NativeRecord {
+    pub fn new(hash: u64, script: u32, args: &[u64], returns: &[u64; 3], duration: Duration) -> NativeRecord {
+        let mut record = NativeRecord {
+            hash,
+            script,
+            duration,
+            arg_count: args.len(),
+            args: [0; MAX_ARGS],
+            returns: *returns,
+        };
+        let kept = args.len().min(MAX_ARGS);
+        record.args[..kept].copy_from_slice(&args[..kept]);
+        record
+    }
+
+    pub fn args(&self) -> &[u64] {
+        &self.args[..self.arg_count.min(MAX_ARGS)]
+    }
+
+    fn encode(&self) -> [u64; RECORD_WORDS] {
+        let mut words = [0; RECORD_WORDS];
+        words[0] = self.hash;
+        words[1] = (self.script as u64) << 32 | self.arg_count.min(u32::MAX as usize) as u64;
+        words[2] = self.duration.as_nanos().min(u64::MAX as u128) as u64;
+        words[3..3 + MAX_ARGS].copy_from_slice(&self.args);
+        words[3 + MAX_ARGS..].copy_from_slice(&self.returns);
+        words
+    }
+
+    fn decode(words: &[u64; RECORD_WORDS]) -> NativeRecord {
+        let mut record = NativeRecord {
+            hash: words[0],
+            script: (words[1] >> 32) as u32,
+            duration: Duration::from_nanos(words[2]),
+            arg_count: words[1] as u32 as usize,
+            args: [0; MAX_ARGS],
+            returns: [0; 3],
+        };
+        record.args.copy_from_slice(&words[3..3 + MAX_ARGS]);
+        record.returns.copy_from_slice(&words[3 + MAX_ARGS..]);
+        record
+    }
+
+    /// The record as a line of JSON, names resolved from the native database and the hash dictionary.
+    pub fn to_json(&self) -> String {
+        let native = nativedb::lookup(self.hash);
+        let line = TraceLine {
+            hash: format!("0x{:016X}", self.hash),
+            name: native.map(|n| format!("{}::{}", n.namespace, n.name)),
+            script: if self.script == 0 { None } else { Some(format!("{}", Hash(self.script))) },
+            args: self.args().iter().map(|w| format!("0x{:X}", w)).collect(),
+            arg_count: self.arg_count,
+            returns: self.returns.iter().map(|w| format!("0x{:X}", w)).collect(),
+            duration_ns: self.duration.as_nanos() as u64,
+        };
+        serde_json::to_string(&line).expect("trace serialization failed")
+    }
+}
+
+#[derive(Serialize)]
+struct TraceLine {
+    hash: String,
+    name: Option<String>,
+    script: Option<String>,
+    args: Vec<String>,
+    arg_count: usize,
+    returns: Vec<String>,
+    duration_ns: u64,
+}
+
+//Sequence lock: odd while a record is being written, `2 * n + 2` once the n-th record is complete
+struct Slot {
+    seq: AtomicU64,
+    words: [AtomicU64; RECORD_WORDS],
+}
+
+/// Fixed-size buffer of the latest records, written and read without locks.
+/// Readers skip the records being overwritten while they copy them.
+pub struct TraceBuffer {
+    slots: Box<[Slot]>,
+    head: AtomicU64,
+    //Records before it were cleared; sequence numbers keep growing so writers in flight can't clobber newer records
+    start: AtomicU64,
+}
+
+impl TraceBuffer {
+    pub fn new(capacity: usize) -> TraceBuffer {
+        let slots = (0..capacity.max(1))
+            .map(|_| Slot { seq: AtomicU64::new(0), words: std::array::from_fn(|_| AtomicU64::new(0)) })
+            .collect();
+        TraceBuffer { slots, head: AtomicU64::new(0), start: AtomicU64::new(0) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Number of records pushed since creation or the last `clear`, including overwritten ones.
+    pub fn total(&self) -> u64 {
+        let start = self.start.load(Ordering::Acquire);
+        self.head.load(Ordering::Acquire).saturating_sub(start)
+    }
+
+    /// Never waits: a record whose slot is still being written by a writer lapping the ring is dropped.
+    pub fn push(&self, record: &NativeRecord) {
+        let n = self.head.fetch_add(1, Ordering::AcqRel);
+        let slot = &self.slots[(n % self.slots.len() as u64) as usize];
+        let mut seq = slot.seq.load(Ordering::Relaxed);
+        loop {
+            //Newer records win over older ones, and a slot being written is left to its writer
+            if seq > 2 * n || !seq.is_multiple_of(2) {
+                return;
+            }
+            match slot.seq.compare_exchange_weak(seq, 2 * n + 1, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => seq = current
+            }
+        }
+        fence(Ordering::Release);
+        for (word, value) in slot.words.iter().zip(record.encode()) {
+            word.store(value, Ordering::Relaxed);
+        }
+        slot.seq.store(2 * n + 2, Ordering::Release);
+    }
+
+    /// Complete records still in the buffer, oldest first.
+    pub fn snapshot(&self) -> Vec<NativeRecord> {
+        let start = self.start.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        let start = head.saturating_sub(self.slots.len() as u64).max(start);
+        let mut records = Vec::with_capacity(head.saturating_sub(start) as usize);
+        for n in start..head {
+            let slot = &self.slots[(n % self.slots.len() as u64) as usize];
+            let seq = slot.seq.load(Ordering::Acquire);
+            if seq != 2 * n + 2 {
+                continue;
+            }
+            let mut words = [0; RECORD_WORDS];
+            for (value, word) in words.iter_mut().zip(slot.words.iter()) {
+                *value = word.load(Ordering::Relaxed);
+            }
+            fence(Ordering::Acquire);
+            if slot.seq.load(Ordering::Relaxed) == seq {
+                records.push(NativeRecord::decode(&words));
+            }
+        }
+        records
+    }
+
+    /// Forgets every record pushed so far, writers still pushing them included.
+    pub fn clear(&self) {
+        self.start.fetch_max(self.head.load(Ordering::Acquire), Ordering::AcqRel);
+    }
+}
+
+/// Restricts tracing to some natives or scripts, empty sets letting everything through.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    pub hashes: HashSet<u64>,
+    pub scripts: HashSet<u32>,
+    /// Namespaces of the native database, natives missing from it never matching
+    pub namespaces: HashSet<String>,
+}
+
+impl TraceFilter {
+    pub fn matches(&self, hash: u64, script: u32) -> bool {
+        (self.hashes.is_empty() || self.hashes.contains(&hash))
+            && (self.scripts.is_empty() || self.scripts.contains(&script))
+            && (self.namespaces.is_empty() || nativedb::lookup(hash)
+                .is_some_and(|n| self.namespaces.iter().any(|ns| ns.eq_ignore_ascii_case(n.namespace))))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty() && self.scripts.is_empty() && self.namespaces.is_empty()
+    }
+}
+
+impl Display for TraceFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return f.write_str("everything");
+        }
+        let mut parts = Vec::new();
+        parts.extend(self.hashes.iter().map(|h| format!("0x{:016X}", h)));
+        parts.extend(self.scripts.iter().map(|s| format!("script {}", Hash(*s))));
+        parts.extend(self.namespaces.iter().map(|ns| format!("namespace {}", ns)));
+        parts.sort();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn filter() -> TraceFilter {
+    FILTER.read().expect("lock poisoned").clone()
+}
+
+pub fn set_filter(filter: TraceFilter) {
+    *FILTER.write().expect("lock poisoned") = filter;
+}
+
+/// Records a call if tracing is on and the filter lets it through.
+pub fn record(hash: u64, script: u32, args: &[u64], returns: &[u64; 3], duration: Duration) {
+    if enabled() && FILTER.read().expect("lock poisoned").matches(hash, script) {
+        TRACE.push(&NativeRecord::new(hash, script, args, returns, duration));
+    }
+}
+
+pub fn records() -> Vec<NativeRecord> {
+    TRACE.snapshot()
+}
+
+pub fn clear() {
+    TRACE.clear()
+}
+
+/// Writes the buffered records as JSON lines, oldest first, returning how many were written.
+pub fn dump<W>(out: &mut W) -> std::io::Result<usize> where W: Write {
+    let records = records();
+    for record in &records {
+        writeln!(out, "{}", record.to_json())?;
+    }
+    Ok(records.len())
+}
+
+pub fn dump_to_file<P>(path: P) -> std::io::Result<usize> where P: AsRef<Path> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let written = dump(&mut file)?;
+    file.flush()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+
+    fn record(hash: u64) -> NativeRecord {
+        NativeRecord::new(hash, 0, &[hash, 2], &[hash, 0, 0], Duration::from_nanos(hash))
+    }
+
+    fn hashes(buffer: &TraceBuffer) -> Vec<u64> {
+        buffer.snapshot().iter().map(|r| r.hash).collect()
+    }
+
+    #[test]
+    fn record_encoding() {
+        let args = (0..20).collect::<Vec<u64>>();
+        let record = NativeRecord::new(0xABCD, 0x1234_5678, &args, &[1, 2, 3], Duration::from_micros(5));
+        assert_eq!(record.arg_count, 20);
+        assert_eq!(record.args(), &args[..MAX_ARGS]);
+        assert_eq!(NativeRecord::decode(&record.encode()), record);
+        let empty = NativeRecord::new(1, 0, &[], &[0; 3], Duration::ZERO);
+        assert!(empty.args().is_empty());
+        assert_eq!(NativeRecord::decode(&empty.encode()), empty);
+    }
+
+    #[test]
+    fn ring_keeps_latest() {
+        let buffer = TraceBuffer::new(4);
+        assert_eq!(buffer.capacity(), 4);
+        assert!(buffer.snapshot().is_empty());
+        for hash in 1..=3 {
+            buffer.push(&record(hash));
+        }
+        assert_eq!(hashes(&buffer), vec![1, 2, 3]);
+        for hash in 4..=10 {
+            buffer.push(&record(hash));
+        }
+        assert_eq!(hashes(&buffer), vec![7, 8, 9, 10]);
+        assert_eq!(buffer.total(), 10);
+        assert_eq!(buffer.snapshot()[0], record(7));
+        assert_eq!(TraceBuffer::new(0).capacity(), 1);
+    }
+
+    #[test]
+    fn clear_forgets_records() {
+        let buffer = TraceBuffer::new(4);
+        for hash in 1..=6 {
+            buffer.push(&record(hash));
+        }
+        buffer.clear();
+        assert_eq!(buffer.total(), 0);
+        assert!(buffer.snapshot().is_empty());
+        buffer.push(&record(7));
+        assert_eq!(hashes(&buffer), vec![7]);
+        assert_eq!(buffer.total(), 1);
+    }
+
+    #[test]
+    fn busy_slot_drops_record() {
+        let buffer = TraceBuffer::new(2);
+        buffer.push(&record(1));
+        //A writer that claimed the second record and still holds its slot
+        buffer.head.fetch_add(1, Ordering::AcqRel);
+        buffer.slots[1].seq.store(2 * 1 + 1, Ordering::Relaxed);
+        buffer.push(&record(3));
+        //Lapping the ring onto the held slot gives up instead of waiting for it
+        buffer.push(&record(4));
+        assert_eq!(hashes(&buffer), vec![3]);
+        buffer.slots[1].seq.store(2 * 1 + 2, Ordering::Relaxed);
+        buffer.push(&record(5));
+        assert_eq!(hashes(&buffer), vec![5]);
+        assert_eq!(buffer.total(), 5);
+    }
+
+    #[test]
+    fn clear_while_pushing() {
+        let buffer = Arc::new(TraceBuffer::new(64));
+        let stop = Arc::new(AtomicBool::new(false));
+        let writers = (0..4).map(|_| {
+            let buffer = buffer.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    buffer.push(&record(1));
+                }
+            })
+        }).collect::<Vec<_>>();
+        for _ in 0..1000 {
+            buffer.clear();
+            assert!(buffer.snapshot().iter().all(|r| *r == record(1)));
+        }
+        stop.store(true, Ordering::Relaxed);
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        //Records pushed after a clear are never dropped in favour of stale ones
+        buffer.clear();
+        for hash in 2..66 {
+            buffer.push(&record(hash));
+        }
+        assert_eq!(hashes(&buffer), (2..66).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn json_lines() {
+        let record = NativeRecord::new(0x7239B21A38F536BA, 0, &[0x10], &[1, 0, 0], Duration::from_nanos(1500));
+        let json: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "hash": "0x7239B21A38F536BA",
+            "name": "ENTITY::DOES_ENTITY_EXIST",
+            "script": null,
+            "args": ["0x10"],
+            "arg_count": 1,
+            "returns": ["0x1", "0x0", "0x0"],
+            "duration_ns": 1500
+        }));
+        let unknown = NativeRecord::new(1, 0xDEADBEEF, &[], &[0; 3], Duration::ZERO);
+        let json: serde_json::Value = serde_json::from_str(&unknown.to_json()).unwrap();
+        assert_eq!(json["name"], serde_json::Value::Null);
+        assert_eq!(json["script"], "0xDEADBEEF");
+    }
+
+    #[test]
+    fn filters() {
+        let mut filter = TraceFilter::default();
+        assert!(filter.is_empty() && filter.matches(1, 2));
+        assert_eq!(filter.to_string(), "everything");
+        filter.namespaces.insert(String::from("entity"));
+        assert!(filter.matches(0x7239B21A38F536BA, 0));
+        assert!(!filter.matches(1, 0));
+        filter.scripts.insert(7);
+        assert!(!filter.matches(0x7239B21A38F536BA, 0));
+        assert!(filter.matches(0x7239B21A38F536BA, 7));
+        filter.hashes.insert(0x7239B21A38F536BA);
+        assert_eq!(filter.to_string(), "0x7239B21A38F536BA, namespace entity, script 0x00000007");
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::manifest::{BindingOverride, ManifestSection};
+
+/// Executable version of the game, `1.0.<build>.<revision>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    pub revision: u16,
+}
+
+impl GameVersion {
+    pub const fn new(major: u16, minor: u16, build: u16, revision: u16) -> GameVersion {
+        GameVersion { major, minor, build, revision }
+    }
+}
+
+impl Display for GameVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}.{}.{}.{}", self.major, self.minor, self.build, self.revision))
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = VersionError;
+
+    //Version strings may omit the revision or separate the parts with commas
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.trim().split(['.', ','])
+            .map(|p| p.trim().parse::<u16>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| VersionError::Invalid(String::from(s)))?;
+        match parts.as_slice() {
+            [major, minor, build] => Ok(GameVersion::new(*major, *minor, *build, 0)),
+            [major, minor, build, revision] => Ok(GameVersion::new(*major, *minor, *build, *revision)),
+            _ => Err(VersionError::Invalid(String::from(s)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionError {
+    Undetected,
+    Invalid(String),
+    Unsupported(GameVersion),
+}
+
+impl Display for VersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionError::Undetected => f.write_str("unable to detect the game version"),
+            VersionError::Invalid(version) => f.write_fmt(format_args!("invalid game version `{}`", version)),
+            VersionError::Unsupported(version) => f.write_fmt(format_args!("unsupported build {}", version))
+        }
+    }
+}
+
+impl std::error::Error for VersionError {}
+
+/// Sizes and offsets of game structures that can't be found by a pattern.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub pack_file_size: usize,
+    pub relative_device_size: usize,
+    pub engine_power: i32,
+    pub oil_volume: i32,
+}
+
+/// Pattern or offset replacing the compiled-in one of a binding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub binding: &'static str,
+    pub pattern: Option<&'static str>,
+    pub offset: Option<isize>,
+}
+
+/// Range of builds sharing the same layout and binding variants.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Build {
+    pub first: u16,
+    pub last: u16,
+    pub layout: Layout,
+    pub variants: &'static [Variant],
+}
+
+impl Build {
+    pub fn supports(&self, version: &GameVersion) -> bool {
+        version.major == 1 && version.minor == 0 && (self.first..=self.last).contains(&version.build)
+    }
+
+    pub fn variant(&self, binding: &str) -> Option<&'static Variant> {
+        self.variants.iter().find(|v| v.binding == binding)
+    }
+
+    /// Binding variants of the build in the form of a manifest section.
+    pub fn overrides(&self) -> ManifestSection {
+        let mut section = ManifestSection::default();
+        for v in self.variants {
+            section.bindings.insert(String::from(v.binding), BindingOverride {
+                pattern: v.pattern.map(String::from),
+                offset: v.offset,
+                follow: None,
+            });
+        }
+        section
+    }
+}
+
+/// Builds with a layout checked against their executable, other ones being refused by `find_build`.
+/// A new build needs an entry with its layout before the client starts on it; the manifest only
+/// overrides bindings of builds listed here.
+pub const BUILDS: &[Build] = &[
+    //Layout the client was written against
+    Build {
+        first: 2060,
+        last: 2060,
+        layout: Layout {
+            pack_file_size: 368 + (0x650 - 0x590),
+            relative_device_size: 272,
+            engine_power: 0xAE8,
+            oil_volume: 0x104,
+        },
+        variants: &[],
+    },
+];
+
+//Structures embedding game objects are sized for the largest supported build
+pub const PACK_FILE_CAPACITY: usize = {
+    let mut max = 0;
+    let mut i = 0;
+    while i < BUILDS.len() {
+        if BUILDS[i].layout.pack_file_size > max {
+            max = BUILDS[i].layout.pack_file_size;
+        }
+        i += 1;
+    }
+    max
+};
+
+pub const RELATIVE_DEVICE_CAPACITY: usize = {
+    let mut max = 0;
+    let mut i = 0;
+    while i < BUILDS.len() {
+        if BUILDS[i].layout.relative_device_size > max {
+            max = BUILDS[i].layout.relative_device_size;
+        }
+        i += 1;
+    }
+    max
+};
+
+pub fn find_build(version: &GameVersion) -> Result<&'static Build, VersionError> {
+    BUILDS.iter().find(|b| b.supports(version))
+        .ok_or(VersionError::Unsupported(*version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!("1.0.2060.1".parse(), Ok(GameVersion::new(1, 0, 2060, 1)));
+        assert_eq!(" 1, 0, 2060, 0 ".parse(), Ok(GameVersion::new(1, 0, 2060, 0)));
+        assert_eq!("1.0.2060".parse(), Ok(GameVersion::new(1, 0, 2060, 0)));
+        for invalid in ["", "1.0", "1.0.2060.1.2", "1.0.x.1", "1.0.70000.0", "1..2060"] {
+            assert_eq!(invalid.parse::<GameVersion>(), Err(VersionError::Invalid(String::from(invalid))));
+        }
+        let version = GameVersion::new(1, 0, 2060, 2);
+        assert_eq!(version.to_string().parse(), Ok(version));
+        assert!(GameVersion::new(1, 0, 2060, 0) < GameVersion::new(1, 0, 2189, 0));
+    }
+
+    #[test]
+    fn find_builds() {
+        let build = find_build(&GameVersion::new(1, 0, 2060, 1)).unwrap();
+        assert_eq!(build.first, 2060);
+        assert_eq!(find_build(&GameVersion::new(1, 0, 2060, 0)), Ok(build));
+        for version in [GameVersion::new(1, 0, 2059, 0), GameVersion::new(1, 0, 2061, 0), GameVersion::new(1, 1, 2060, 0)] {
+            assert_eq!(find_build(&version), Err(VersionError::Unsupported(version)));
+        }
+        assert_eq!(VersionError::Unsupported(GameVersion::new(1, 0, 9999, 1)).to_string(), "unsupported build 1.0.9999.1");
+    }
+
+    #[test]
+    fn table_is_consistent() {
+        for (i, build) in BUILDS.iter().enumerate() {
+            assert!(build.first <= build.last);
+            assert!(BUILDS[i + 1..].iter().all(|other| other.last < build.first || other.first > build.last),
+                    "build {} overlaps another entry", build.first);
+            assert!(build.layout.pack_file_size <= PACK_FILE_CAPACITY);
+            assert!(build.layout.relative_device_size <= RELATIVE_DEVICE_CAPACITY);
+            for variant in build.variants {
+                assert!(crate::signature::bindings::find(variant.binding).is_some(), "unknown binding {}", variant.binding);
+                assert!(variant.pattern.is_some() || variant.offset.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn variants_as_overrides() {
+        const VARIANTS: &[Variant] = &[
+            Variant { binding: "GAME_STATE", pattern: Some("83 3D | [rel32+1] ? 75"), offset: None },
+            Variant { binding: "SKIP_INIT", pattern: None, offset: Some(-4) },
+        ];
+        let build = Build { variants: VARIANTS, ..BUILDS[0] };
+        assert_eq!(build.variant("SKIP_INIT"), Some(&VARIANTS[1]));
+        assert_eq!(build.variant("PED"), None);
+        let section = build.overrides();
+        assert_eq!(section.bindings.len(), 2);
+        assert_eq!(section.bindings["GAME_STATE"].pattern.as_deref(), VARIANTS[0].pattern);
+        assert_eq!(section.bindings["SKIP_INIT"].offset, Some(-4));
+        assert!(section.bindings.values().all(|o| o.follow.is_none()));
+    }
+}
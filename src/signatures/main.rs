@@ -0,0 +1,139 @@
+use std::process::exit;
+
+use evolutionmp::nativetable::{self, MemoryDump};
+use evolutionmp::signature::bindings::{self, BindingReport};
+use evolutionmp::signature::{generate, Image};
+
+const USAGE: &str = "Usage:
+    signatures check <GTA5.exe> [--dump]
+    signatures generate <GTA5.exe> <address> [--dump]
+    signatures natives diff <old.txt> <new.txt>
+    signatures natives <memory.bin> <memory-base> <table> <image-base>";
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let dump = args.iter().any(|a| a == "--dump");
+    let args = args.iter().filter(|a| !a.starts_with("--")).map(|a| a.as_str()).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["check", path] => exit(check(&load(path, dump))),
+        ["generate", path, address] => exit(generate_at(&load(path, dump), address)),
+        ["natives", "diff", old, new] => exit(diff_natives(old, new)),
+        ["natives", path, base, table, image_base] => exit(dump_natives(path, base, table, image_base)),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+}
+
+fn load(path: &str, dump: bool) -> Image {
+    match Image::load(path, dump) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("Unable to load {}: {}", path, e);
+            exit(2);
+        }
+    }
+}
+
+fn check(image: &Image) -> i32 {
+    println!("Image base 0x{:X}, timestamp 0x{:08X}, {} sections", image.base, image.timestamp, image.sections.len());
+    let reports = bindings::check(image.data());
+    for report in &reports {
+        println!("{}", format_report(report));
+    }
+    let failed = reports.iter().filter(|r| !r.is_ok()).count();
+    println!("{} out of {} bindings resolved", reports.len() - failed, reports.len());
+    if failed == 0 { 0 } else { 1 }
+}
+
+fn format_report(report: &BindingReport) -> String {
+    let status = if report.is_ok() {
+        "ok"
+    } else if report.resolution.hits() == 0 {
+        "missing"
+    } else if report.resolution.is_ambiguous() {
+        "ambiguous"
+    } else {
+        "invalid"
+    };
+    let location = report.location.map(|l| l.to_string()).unwrap_or_else(|| String::from("-"));
+    format!("{:<9} {:<18} {:<28} {:>3} hits  {}", status, report.binding.module, report.binding.name, report.resolution.hits(), location)
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    let parsed = match address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => address.parse::<u64>()
+    };
+    if parsed.is_err() {
+        eprintln!("Invalid address `{}`", address);
+    }
+    parsed.ok()
+}
+
+//Accepts both RVAs and virtual addresses within the preferred image base
+fn generate_at(image: &Image, address: &str) -> i32 {
+    let address = match parse_address(address) {
+        Some(address) => address,
+        None => return 2
+    };
+    let rva = if address >= image.base { address - image.base } else { address };
+    match generate(image.data(), rva) {
+        Ok(pattern) => {
+            println!("{}", pattern);
+            0
+        }
+        Err(e) => {
+            eprintln!("Unable to generate a signature: {}", e);
+            1
+        }
+    }
+}
+
+//Decodes the native table from raw memory copied at `base`, printing hashes with the RVAs of their handlers
+fn dump_natives(path: &str, base: &str, table: &str, image_base: &str) -> i32 {
+    let (base, table, image_base) = match (parse_address(base), parse_address(table), parse_address(image_base)) {
+        (Some(base), Some(table), Some(image_base)) => (base, table, image_base),
+        _ => return 2
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", path, e);
+            return 2;
+        }
+    };
+    match nativetable::decode(&MemoryDump::new(base, bytes), table) {
+        Ok(registrations) => {
+            //Memory dumps don't tell where the image ends, every handler past its base is taken as part of it
+            print!("{}", nativetable::to_text(&registrations, image_base, u64::MAX));
+            eprintln!("{} natives registered", registrations.len());
+            0
+        }
+        Err(e) => {
+            eprintln!("Unable to decode the native table: {}", e);
+            1
+        }
+    }
+}
+
+fn diff_natives(old: &str, new: &str) -> i32 {
+    let load = |path: &str| match std::fs::read_to_string(path) {
+        Ok(text) => nativetable::parse_text(&text).map_err(|e| format!("{}: {}", path, e)),
+        Err(e) => Err(format!("{}: {}", path, e))
+    };
+    match (load(old), load(new)) {
+        (Ok(old), Ok(new)) => {
+            let changes = nativetable::diff(&old, &new);
+            for change in &changes {
+                println!("{}", change);
+            }
+            if changes.is_empty() { 0 } else { 1 }
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Unable to load registrations: {}", e);
+            2
+        }
+    }
+}
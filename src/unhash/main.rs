@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::process::exit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use evolutionmp::hash::{Hash, JoaatHasher};
+use evolutionmp::hash::dictionary::{self, Category, HashDictionary};
+
+use template::Template;
+
+mod template;
+
+const USAGE: &str = "Usage:
+    unhash <hash|@file>... [--wordlist <file>]... [--template <template>]... [--seeds <dir>]
+           [--mutate] [--category <category>] [--case-sensitive] [--threads <n>] [--output <file>]
+
+Templates mix literal text with `{a|b}` alternatives, `{01..99}` ranges,
+`{word}` for wordlist entries and `{name}` for known names.
+Without wordlists or templates, known names are mutated with numeric suffixes.";
+
+//Tried on every known name stripped of its trailing digits, each suffix being tried once
+const MUTATIONS: &[&str] = &["{name}{0..9}", "{name}{00..99}", "{name}{000..999}", "{name}_{0..9}", "{name}_{00..99}", "{name}_{000..999}"];
+
+struct Options {
+    targets: HashSet<u32>,
+    wordlists: Vec<String>,
+    templates: Vec<String>,
+    seeds: Option<String>,
+    mutate: bool,
+    category: Category,
+    lowercase: bool,
+    threads: usize,
+    output: Option<String>,
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        exit(2);
+    });
+
+    let mut known = dictionary::builtin();
+    if let Some(seeds) = &options.seeds {
+        for e in known.load_dir(seeds) {
+            eprintln!("Ignoring name list: {}", e);
+        }
+    }
+    let mut targets = options.targets.clone();
+    targets.retain(|hash| match known.get(Hash(*hash)) {
+        Some(name) => {
+            eprintln!("0x{:08X} is already known as {}", hash, name);
+            false
+        }
+        None => true
+    });
+    if targets.is_empty() {
+        return;
+    }
+
+    let words = Arc::new(read_wordlists(&options.wordlists));
+    let names = Arc::new(known_names(&known));
+    let mut templates = Vec::new();
+    for template in &options.templates {
+        match Template::parse(template, &words, &names) {
+            Ok(t) => templates.push(t),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(2);
+            }
+        }
+    }
+    if !words.is_empty() && options.templates.is_empty() {
+        templates.push(Template::from_segments(vec![words.clone()]));
+    }
+    if options.mutate || templates.is_empty() {
+        let stems = Arc::new(stems(&names));
+        for mutation in MUTATIONS {
+            let template = Template::parse(mutation, &words, &stems).expect("invalid mutation");
+            templates.push(template);
+        }
+    }
+
+    let found = Mutex::new(BTreeMap::<u32, Vec<String>>::new());
+    for template in &templates {
+        eprintln!("Trying {} candidates", template.size());
+        search(template, &targets, options.lowercase, options.threads, &found);
+    }
+
+    let found = found.into_inner().expect("mutex poisoned");
+    let mut confirmed = Vec::new();
+    for (hash, mut candidates) in found {
+        candidates.sort();
+        candidates.dedup();
+        if candidates.len() == 1 {
+            eprintln!("0x{:08X} = {}", hash, candidates[0]);
+            confirmed.extend(candidates);
+        } else {
+            eprintln!("0x{:08X} is ambiguous: {}", hash, candidates.join(", "));
+        }
+    }
+    eprintln!("Recovered {} out of {} unknown hashes", confirmed.len(), targets.len());
+    if let Err(e) = write_names(&options, &confirmed) {
+        eprintln!("Unable to write names: {}", e);
+        exit(1);
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        targets: HashSet::new(),
+        wordlists: Vec::new(),
+        templates: Vec::new(),
+        seeds: None,
+        mutate: false,
+        category: Category::Other,
+        lowercase: true,
+        threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        output: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--wordlist" => options.wordlists.push(value()?),
+            "--template" => options.templates.push(value()?),
+            "--seeds" => options.seeds = Some(value()?),
+            "--mutate" => options.mutate = true,
+            "--category" => options.category = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--case-sensitive" => options.lowercase = false,
+            "--threads" => options.threads = value()?.parse().map_err(|_| String::from("Invalid thread count"))?,
+            "--output" => options.output = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => match arg.strip_prefix('@') {
+                Some(path) => {
+                    let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+                    options.targets.extend(contents.split_whitespace().filter_map(parse_hash));
+                }
+                None => {
+                    let hash = parse_hash(&arg).ok_or_else(|| format!("Invalid hash `{}`", arg))?;
+                    options.targets.insert(hash);
+                }
+            }
+        }
+    }
+    if options.targets.is_empty() {
+        return Err(String::from("No hashes given"));
+    }
+    options.threads = options.threads.max(1);
+    Ok(options)
+}
+
+//Accepts hex and signed or unsigned decimal, ignoring punctuation around log tokens
+fn parse_hash(token: &str) -> Option<u32> {
+    let token = token.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-');
+    match token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => token.parse::<u32>().ok().or_else(|| token.parse::<i32>().ok().map(|h| h as u32))
+    }
+}
+
+fn read_wordlists(paths: &[String]) -> Vec<String> {
+    let mut words = Vec::new();
+    for path in paths {
+        match std::fs::read_to_string(path) {
+            Ok(list) => words.extend(list.lines().map(str::trim).filter(|w| !w.is_empty()).map(String::from)),
+            Err(e) => eprintln!("Unable to read {}: {}", path, e)
+        }
+    }
+    words.sort();
+    words.dedup();
+    words
+}
+
+fn known_names(known: &HashDictionary) -> Vec<String> {
+    let mut names = known.names().map(String::from).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn stems(names: &[String]) -> Vec<String> {
+    let mut stems = names.iter()
+        .map(|n| String::from(n.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end_matches('_')))
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    stems.sort();
+    stems.dedup();
+    stems
+}
+
+//Splits the work on the first segment with alternatives, the literal prefix before it being hashed once
+fn search(template: &Template, targets: &HashSet<u32>, lowercase: bool, threads: usize, found: &Mutex<BTreeMap<u32, Vec<String>>>) {
+    let segments = template.segments();
+    let split = segments.iter().position(|s| s.len() > 1).unwrap_or(0);
+    let mut prefix = JoaatHasher::from_partial(0, lowercase);
+    let mut chosen = Vec::with_capacity(segments.len());
+    for segment in &segments[..split] {
+        prefix.update(&segment[0]);
+        chosen.push(segment[0].as_str());
+    }
+    let Some(work) = segments.get(split) else {
+        return;
+    };
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let mut chosen = chosen.clone();
+            let next = &next;
+            scope.spawn(move || {
+                let mut local = Vec::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(alternative) = work.get(index) else {
+                        break;
+                    };
+                    let mut hasher = prefix;
+                    hasher.update(alternative);
+                    chosen.push(alternative.as_str());
+                    expand(&segments[split + 1..], hasher, &mut chosen, targets, &mut local);
+                    chosen.pop();
+                }
+                let mut found = found.lock().expect("mutex poisoned");
+                for (hash, name) in local {
+                    found.entry(hash).or_default().push(name);
+                }
+            });
+        }
+    });
+}
+
+fn expand<'a>(segments: &'a [Arc<Vec<String>>], hasher: JoaatHasher, chosen: &mut Vec<&'a str>,
+              targets: &HashSet<u32>, found: &mut Vec<(u32, String)>) {
+    match segments.split_first() {
+        Some((segment, rest)) => {
+            for alternative in segment.iter() {
+                let mut hasher = hasher;
+                hasher.update(alternative);
+                chosen.push(alternative);
+                expand(rest, hasher, chosen, targets, found);
+                chosen.pop();
+            }
+        }
+        None => {
+            let hash = hasher.finish().0;
+            if targets.contains(&hash) {
+                found.push((hash, chosen.concat()));
+            }
+        }
+    }
+}
+
+fn write_names(options: &Options, names: &[String]) -> std::io::Result<()> {
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(std::fs::OpenOptions::new().create(true).append(true).open(path)?),
+        None => Box::new(std::io::stdout())
+    };
+    if names.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "[{}]", options.category)?;
+    for name in names {
+        writeln!(out, "{}", name)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutations_are_distinct() {
+        let words = Arc::new(Vec::new());
+        let names = Arc::new(vec![String::from("prop")]);
+        let mut candidates = HashSet::new();
+        let mut total = 0;
+        for mutation in MUTATIONS {
+            let template = Template::parse(mutation, &words, &names).unwrap();
+            let mut expanded = vec![String::new()];
+            for segment in template.segments() {
+                expanded = expanded.iter().flat_map(|prefix| segment.iter().map(move |s| format!("{}{}", prefix, s))).collect();
+            }
+            total += expanded.len();
+            candidates.extend(expanded);
+        }
+        assert_eq!(candidates.len(), total);
+        assert_eq!(total, 2 * (10 + 100 + 1000));
+        assert!(candidates.contains("prop7") && candidates.contains("prop_07") && candidates.contains("prop_007"));
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// A candidate name generator: literal text mixed with placeholders in braces.
+///
+/// - `{a|b|c}` tries every alternative
+/// - `{01..99}` tries a numeric range, zero-padded to the width of its start
+/// - `{word}` tries every word of the wordlists
+/// - `{name}` tries every known name
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Arc<Vec<String>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    Unterminated(String),
+    InvalidRange(String),
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::Unterminated(t) => f.write_fmt(format_args!("unterminated placeholder in `{}`", t)),
+            TemplateError::InvalidRange(r) => f.write_fmt(format_args!("invalid range `{}`", r))
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str, words: &Arc<Vec<String>>, names: &Arc<Vec<String>>) -> Result<Template, TemplateError> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            match rest.find('{') {
+                Some(0) => {
+                    let end = rest.find('}').ok_or_else(|| TemplateError::Unterminated(String::from(template)))?;
+                    let placeholder = &rest[1..end];
+                    segments.push(match placeholder {
+                        "word" => words.clone(),
+                        "name" => names.clone(),
+                        _ if placeholder.contains("..") => Arc::new(parse_range(placeholder)?),
+                        _ => Arc::new(placeholder.split('|').map(String::from).collect())
+                    });
+                    rest = &rest[end + 1..];
+                }
+                Some(start) => {
+                    segments.push(Arc::new(vec![String::from(&rest[..start])]));
+                    rest = &rest[start..];
+                }
+                None => {
+                    segments.push(Arc::new(vec![String::from(rest)]));
+                    rest = "";
+                }
+            }
+        }
+        Ok(Template { segments })
+    }
+
+    pub fn from_segments(segments: Vec<Arc<Vec<String>>>) -> Template {
+        Template { segments }
+    }
+
+    pub fn segments(&self) -> &[Arc<Vec<String>>] {
+        &self.segments
+    }
+
+    /// Number of candidates the template expands to.
+    pub fn size(&self) -> u128 {
+        self.segments.iter().map(|s| s.len() as u128).product()
+    }
+}
+
+fn parse_range(range: &str) -> Result<Vec<String>, TemplateError> {
+    let invalid = || TemplateError::InvalidRange(String::from(range));
+    let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+    let width = start.len();
+    let start = start.parse::<u64>().map_err(|_| invalid())?;
+    let end = end.parse::<u64>().map_err(|_| invalid())?;
+    if start > end {
+        return Err(invalid());
+    }
+    Ok((start..=end).map(|n| format!("{:0width$}", n, width = width)).collect())
+}